@@ -0,0 +1,300 @@
+//! Decode the arguments of calls to the framework's entry functions.
+//! The bcs arguments are read by the parameter types of the function in the
+//! head release, so no generated code is needed.
+
+use crate::head_release_bundle;
+use anyhow::{bail, Context};
+use diem_types::transaction::EntryFunction;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{SignatureToken, StructHandleIndex},
+    CompiledModule,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::TypeTag,
+    value::{MoveTypeLayout, MoveValue},
+};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::fmt;
+
+static HEAD_MODULES: Lazy<Vec<CompiledModule>> = Lazy::new(|| {
+    head_release_bundle()
+        .code_and_compiled_modules()
+        .into_iter()
+        .map(|(_, m)| m.clone())
+        .collect()
+});
+
+/// The types an entry function can take as arguments
+#[derive(Debug, Clone, PartialEq)]
+enum ArgType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Vector(Box<ArgType>),
+    String,
+    Option(Box<ArgType>),
+    Object,
+}
+
+impl fmt::Display for ArgType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgType::Bool => write!(f, "bool"),
+            ArgType::U8 => write!(f, "u8"),
+            ArgType::U16 => write!(f, "u16"),
+            ArgType::U32 => write!(f, "u32"),
+            ArgType::U64 => write!(f, "u64"),
+            ArgType::U128 => write!(f, "u128"),
+            ArgType::U256 => write!(f, "u256"),
+            ArgType::Address => write!(f, "address"),
+            ArgType::Vector(t) => write!(f, "vector<{}>", t),
+            ArgType::String => write!(f, "0x1::string::String"),
+            ArgType::Option(t) => write!(f, "0x1::option::Option<{}>", t),
+            ArgType::Object => write!(f, "0x1::object::Object"),
+        }
+    }
+}
+
+impl ArgType {
+    /// strings, options and objects have the bcs of a vector or an address
+    fn layout(&self) -> MoveTypeLayout {
+        match self {
+            ArgType::Bool => MoveTypeLayout::Bool,
+            ArgType::U8 => MoveTypeLayout::U8,
+            ArgType::U16 => MoveTypeLayout::U16,
+            ArgType::U32 => MoveTypeLayout::U32,
+            ArgType::U64 => MoveTypeLayout::U64,
+            ArgType::U128 => MoveTypeLayout::U128,
+            ArgType::U256 => MoveTypeLayout::U256,
+            ArgType::Address | ArgType::Object => MoveTypeLayout::Address,
+            ArgType::Vector(t) | ArgType::Option(t) => MoveTypeLayout::Vector(Box::new(t.layout())),
+            ArgType::String => MoveTypeLayout::Vector(Box::new(MoveTypeLayout::U8)),
+        }
+    }
+
+    /// Numbers from u64 up are strings, like in the node's API, since json
+    /// numbers lose their precision. Byte vectors are hex.
+    fn to_json(&self, value: MoveValue) -> anyhow::Result<Value> {
+        Ok(match (self, value) {
+            (ArgType::Bool, MoveValue::Bool(b)) => json!(b),
+            (ArgType::U8, MoveValue::U8(n)) => json!(n),
+            (ArgType::U16, MoveValue::U16(n)) => json!(n),
+            (ArgType::U32, MoveValue::U32(n)) => json!(n),
+            (ArgType::U64, MoveValue::U64(n)) => json!(n.to_string()),
+            (ArgType::U128, MoveValue::U128(n)) => json!(n.to_string()),
+            (ArgType::U256, MoveValue::U256(n)) => json!(n.to_string()),
+            (ArgType::Address | ArgType::Object, MoveValue::Address(a)) => {
+                json!(a.to_hex_literal())
+            }
+            (ArgType::String, MoveValue::Vector(v)) => json!(String::from_utf8(bytes(v)?)?),
+            (ArgType::Vector(t), MoveValue::Vector(v)) if **t == ArgType::U8 => {
+                json!(format!("0x{}", hex::encode(bytes(v)?)))
+            }
+            (ArgType::Vector(t), MoveValue::Vector(v)) => Value::Array(
+                v.into_iter()
+                    .map(|v| t.to_json(v))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            (ArgType::Option(t), MoveValue::Vector(mut v)) => match v.pop() {
+                Some(v) => t.to_json(v)?,
+                None => Value::Null,
+            },
+            (t, v) => bail!("{:?} is not a {}", v, t),
+        })
+    }
+}
+
+fn bytes(values: Vec<MoveValue>) -> anyhow::Result<Vec<u8>> {
+    values
+        .into_iter()
+        .map(|v| match v {
+            MoveValue::U8(b) => Ok(b),
+            other => bail!("{:?} is not a u8", other),
+        })
+        .collect()
+}
+
+/// The function id of a call, e.g. 0x1::ol_account::transfer
+pub fn function_id(entry: &EntryFunction) -> String {
+    format!(
+        "{}::{}::{}",
+        entry.module().address().to_hex_literal(),
+        entry.module().name(),
+        entry.function()
+    )
+}
+
+/// The arguments of a call to a framework function, in order, each with its
+/// Move type and value. The signer is not an argument of the call.
+/// Fails if the function is not in the head release, or the arguments don't
+/// match its parameters.
+pub fn decode_entry_args(entry: &EntryFunction) -> anyhow::Result<Value> {
+    let module = HEAD_MODULES
+        .iter()
+        .find(|m| &m.self_id() == entry.module())
+        .with_context(|| format!("{} is not a framework module", entry.module()))?;
+    let handle = module
+        .function_defs()
+        .iter()
+        .map(|def| module.function_handle_at(def.function))
+        .find(|h| module.identifier_at(h.name) == entry.function())
+        .with_context(|| format!("{} is not a framework function", function_id(entry)))?;
+
+    let params: Vec<&SignatureToken> = module
+        .signature_at(handle.parameters)
+        .0
+        .iter()
+        .filter(|t| !is_signer(t))
+        .collect();
+    if params.len() != entry.args().len() {
+        bail!(
+            "{} takes {} arguments, the call has {}",
+            function_id(entry),
+            params.len(),
+            entry.args().len()
+        );
+    }
+
+    let args = params
+        .into_iter()
+        .zip(entry.args())
+        .enumerate()
+        .map(|(i, (token, arg))| {
+            let ty = arg_type(module, token, entry.ty_args())?;
+            let value = MoveValue::simple_deserialize(arg, &ty.layout())
+                .with_context(|| format!("argument {} is not a {}", i, ty))?;
+            Ok(json!({ "type": ty.to_string(), "value": ty.to_json(value)? }))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Value::Array(args))
+}
+
+fn is_signer(token: &SignatureToken) -> bool {
+    match token {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(t) => **t == SignatureToken::Signer,
+        _ => false,
+    }
+}
+
+fn arg_type(
+    module: &CompiledModule,
+    token: &SignatureToken,
+    ty_args: &[TypeTag],
+) -> anyhow::Result<ArgType> {
+    Ok(match token {
+        SignatureToken::Bool => ArgType::Bool,
+        SignatureToken::U8 => ArgType::U8,
+        SignatureToken::U16 => ArgType::U16,
+        SignatureToken::U32 => ArgType::U32,
+        SignatureToken::U64 => ArgType::U64,
+        SignatureToken::U128 => ArgType::U128,
+        SignatureToken::U256 => ArgType::U256,
+        SignatureToken::Address => ArgType::Address,
+        SignatureToken::Vector(t) => ArgType::Vector(Box::new(arg_type(module, t, ty_args)?)),
+        SignatureToken::Struct(idx) => struct_arg_type(module, *idx, &[], ty_args)?,
+        SignatureToken::StructInstantiation(idx, type_params) => {
+            struct_arg_type(module, *idx, type_params, ty_args)?
+        }
+        SignatureToken::TypeParameter(i) => tag_arg_type(
+            ty_args
+                .get(*i as usize)
+                .with_context(|| format!("the call has no type argument {}", i))?,
+        )?,
+        other => bail!("{:?} can't be an argument of an entry function", other),
+    })
+}
+
+fn struct_arg_type(
+    module: &CompiledModule,
+    idx: StructHandleIndex,
+    type_params: &[SignatureToken],
+    ty_args: &[TypeTag],
+) -> anyhow::Result<ArgType> {
+    let handle = module.struct_handle_at(idx);
+    let id = module.module_id_for_handle(module.module_handle_at(handle.module));
+    let name = module.identifier_at(handle.name).as_str();
+    if id.address() != &AccountAddress::ONE {
+        bail!("{}::{} can't be decoded", id, name);
+    }
+    Ok(match (id.name().as_str(), name) {
+        ("string", "String") => ArgType::String,
+        ("option", "Option") => ArgType::Option(Box::new(arg_type(
+            module,
+            type_params.first().context("an option without a type")?,
+            ty_args,
+        )?)),
+        ("object", "Object") => ArgType::Object,
+        _ => bail!("{}::{} can't be decoded", id, name),
+    })
+}
+
+/// the type of a generic argument, from the type arguments of the call
+fn tag_arg_type(tag: &TypeTag) -> anyhow::Result<ArgType> {
+    Ok(match tag {
+        TypeTag::Bool => ArgType::Bool,
+        TypeTag::U8 => ArgType::U8,
+        TypeTag::U16 => ArgType::U16,
+        TypeTag::U32 => ArgType::U32,
+        TypeTag::U64 => ArgType::U64,
+        TypeTag::U128 => ArgType::U128,
+        TypeTag::U256 => ArgType::U256,
+        TypeTag::Address => ArgType::Address,
+        TypeTag::Vector(t) => ArgType::Vector(Box::new(tag_arg_type(t)?)),
+        TypeTag::Struct(s)
+            if s.address == AccountAddress::ONE
+                && s.module.as_str() == "string"
+                && s.name.as_str() == "String" =>
+        {
+            ArgType::String
+        }
+        other => bail!("{} can't be decoded", other),
+    })
+}
+
+#[test]
+fn test_decode_entry_args() {
+    use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+    let bob = AccountAddress::from_hex_literal("0xb0b").unwrap();
+    let transfer = |args: Vec<Vec<u8>>| {
+        EntryFunction::new(
+            ModuleId::new(AccountAddress::ONE, Identifier::new("ol_account").unwrap()),
+            Identifier::new("transfer").unwrap(),
+            vec![],
+            args,
+        )
+    };
+
+    let entry = transfer(vec![
+        bcs::to_bytes(&bob).unwrap(),
+        bcs::to_bytes(&100u64).unwrap(),
+    ]);
+    assert_eq!(function_id(&entry), "0x1::ol_account::transfer");
+    assert_eq!(
+        decode_entry_args(&entry).unwrap(),
+        json!([
+            { "type": "address", "value": bob.to_hex_literal() },
+            { "type": "u64", "value": "100" },
+        ])
+    );
+
+    // a missing argument, a truncated one, and a function which isn't there
+    assert!(decode_entry_args(&transfer(vec![bcs::to_bytes(&bob).unwrap()])).is_err());
+    assert!(decode_entry_args(&transfer(vec![bcs::to_bytes(&bob).unwrap(), vec![1]])).is_err());
+    let missing = EntryFunction::new(
+        ModuleId::new(AccountAddress::ONE, Identifier::new("ol_account").unwrap()),
+        Identifier::new("no_such_function").unwrap(),
+        vec![],
+        vec![],
+    );
+    assert!(decode_entry_args(&missing).is_err());
+}
@@ -1,4 +1,5 @@
 pub mod builder;
+pub mod entry_args;
 pub mod framework_cli;
 pub mod release;
 pub mod upgrade_fixtures;
@@ -1,5 +1,7 @@
 mod move_cli;
+mod node_check;
 mod node_cli;
+mod node_status;
 
 use anyhow::anyhow;
 use clap::{Parser, Subcommand};
@@ -85,6 +87,8 @@ fn main() -> anyhow::Result<()> {
                     Some(Sub::Genesis(genesis_cli)) => {
                         if let Err(e) = genesis_cli.execute().await {
                             eprintln!("Failed to execute genesis tool, message: {}", &e);
+                            // audits and verifications are used in scripts
+                            std::process::exit(1);
                         }
                     }
 
@@ -0,0 +1,549 @@
+//! Preflight checks of a node config, so a misconfiguration is reported
+//! before the node starts instead of as a panic during startup.
+
+use diem_config::{
+    config::{Identity, InitialSafetyRulesConfig, NetworkConfig, NodeConfig},
+    network_id::NetworkId,
+};
+use diem_types::network_address::{NetworkAddress, Protocol};
+use lotus_genesis_tools::{
+    genesis_reader::{bootstrap_db_reader_from_gen_tx, read_blob_to_tx},
+    verify_genesis::waypoint_from_config,
+};
+use std::{
+    fs,
+    net::{SocketAddr, TcpListener},
+    path::Path,
+};
+use sysinfo::{DiskExt, System, SystemExt};
+
+const GB: u64 = 1024 * 1024 * 1024;
+/// below this the node will not start
+const MIN_FREE_DISK: u64 = 10 * GB;
+/// below this the node will fill the disk soon
+const LOW_FREE_DISK: u64 = 50 * GB;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+pub struct Finding {
+    pub level: Level,
+    pub message: String,
+}
+
+/// The findings of all the checks
+#[derive(Debug, Default)]
+pub struct Preflight {
+    pub findings: Vec<Finding>,
+}
+
+impl Preflight {
+    fn ok(&mut self, message: String) {
+        self.push(Level::Ok, message)
+    }
+
+    fn warn(&mut self, message: String) {
+        self.push(Level::Warn, message)
+    }
+
+    fn fail(&mut self, message: String) {
+        self.push(Level::Fail, message)
+    }
+
+    fn push(&mut self, level: Level, message: String) {
+        self.findings.push(Finding { level, message })
+    }
+
+    /// true if nothing would prevent the node from starting
+    pub fn passed(&self) -> bool {
+        !self.findings.iter().any(|f| f.level == Level::Fail)
+    }
+
+    pub fn print(&self) {
+        for f in &self.findings {
+            let tag = match f.level {
+                Level::Ok => "OK",
+                Level::Warn => "WARN",
+                Level::Fail => "FAIL",
+            };
+            println!("[{:>4}] {}", tag, f.message);
+        }
+    }
+}
+
+/// Run all the checks on a node config
+pub fn check_node_config(config: &NodeConfig, config_path: &Path) -> Preflight {
+    let mut p = Preflight::default();
+    check_role(&mut p, config, config_path);
+    check_data_dir(&mut p, &config.base.data_dir);
+    check_genesis(&mut p, config);
+    check_ports(&mut p, config);
+    check_identity_files(&mut p, config);
+    check_peers(&mut p, config);
+    p
+}
+
+fn check_role(p: &mut Preflight, config: &NodeConfig, config_path: &Path) {
+    let is_validator = config.base.role.is_validator();
+    match (is_validator, config.validator_network.is_some()) {
+        (true, false) => p.fail(
+            "the role is validator but there is no validator_network, is this a fullnode config?"
+                .to_string(),
+        ),
+        (false, true) => p.fail(
+            "the role is full_node but there is a validator_network, set `role: 'validator'` or remove it"
+                .to_string(),
+        ),
+        _ => p.ok(format!("role: {}", config.base.role)),
+    }
+
+    let file_name = config_path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !is_validator && file_name.starts_with("validator") {
+        p.warn(format!(
+            "{} is a full_node config, check this is the file you meant to start",
+            config_path.display()
+        ));
+    }
+    if is_validator {
+        let home = config_path.parent().unwrap_or_else(|| Path::new("."));
+        if !home.join("validator-identity.yaml").exists() {
+            p.warn(format!(
+                "no validator-identity.yaml next to {}, the validator keys may be missing",
+                config_path.display()
+            ));
+        }
+    }
+}
+
+fn check_data_dir(p: &mut Preflight, data_dir: &Path) {
+    if !data_dir.exists() {
+        p.warn(format!(
+            "data_dir {} does not exist, it will be created and the node will sync from scratch",
+            data_dir.display()
+        ));
+    } else if fs::metadata(data_dir)
+        .map(|m| m.permissions().readonly())
+        .unwrap_or(true)
+    {
+        p.fail(format!("data_dir {} is not writable", data_dir.display()));
+    } else {
+        p.ok(format!("data_dir: {}", data_dir.display()));
+    }
+
+    // the disk is the one with the longest mount point containing the data dir
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    let existing = data_dir
+        .ancestors()
+        .find(|a| a.exists())
+        .and_then(|a| a.canonicalize().ok());
+    let disk = existing.and_then(|dir| {
+        sys.disks()
+            .iter()
+            .filter(|d| dir.starts_with(d.mount_point()))
+            .max_by_key(|d| d.mount_point().as_os_str().len())
+            .map(|d| d.available_space())
+    });
+    match disk {
+        Some(free) if free < MIN_FREE_DISK => p.fail(format!(
+            "only {} GB free for the data_dir, free some space or move the data_dir",
+            free / GB
+        )),
+        Some(free) if free < LOW_FREE_DISK => p.warn(format!(
+            "only {} GB free for the data_dir, the db will outgrow it",
+            free / GB
+        )),
+        Some(free) => p.ok(format!("{} GB free for the data_dir", free / GB)),
+        None => p.warn("could not find the free disk space of the data_dir".to_string()),
+    }
+}
+
+fn check_genesis(p: &mut Preflight, config: &NodeConfig) {
+    let waypoint = match waypoint_from_config(&config.base.waypoint) {
+        Ok(Some(w)) => w,
+        Ok(None) => {
+            p.warn(
+                "the waypoint is not in the config or a file, skipping the genesis check"
+                    .to_string(),
+            );
+            return;
+        }
+        Err(e) => {
+            p.fail(format!("cannot read the waypoint: {:#}", e));
+            return;
+        }
+    };
+
+    if config.execution.genesis.is_some() {
+        p.ok("genesis transaction is set in the config".to_string());
+        return;
+    }
+
+    let blob = &config.execution.genesis_file_location;
+    if !blob.exists() {
+        p.fail(format!(
+            "genesis file {} does not exist, download it with `lotus config fullnode-init` or fix execution.genesis_file_location",
+            blob.display()
+        ));
+        return;
+    }
+
+    // after an upgrade the waypoint is the one of the upgrade, not of the blob
+    if waypoint.version() != 0 {
+        p.warn(format!(
+            "waypoint {} is not a genesis waypoint, skipping the genesis blob check",
+            waypoint
+        ));
+        return;
+    }
+
+    let computed = read_blob_to_tx(blob.to_owned())
+        .and_then(|tx| bootstrap_db_reader_from_gen_tx(&tx))
+        .map(|(_, w)| w);
+    match computed {
+        Ok(w) if w == waypoint => p.ok(format!("genesis blob matches waypoint {}", w)),
+        Ok(w) => p.fail(format!(
+            "genesis blob {} has waypoint {}, but the config has {}. Check both with `lotus genesis verify`",
+            blob.display(),
+            w,
+            waypoint
+        )),
+        Err(e) => p.fail(format!(
+            "cannot read genesis blob {}: {:#}",
+            blob.display(),
+            e
+        )),
+    }
+}
+
+fn check_ports(p: &mut Preflight, config: &NodeConfig) {
+    let mut ports: Vec<(String, SocketAddr)> = vec![];
+
+    let networks = config
+        .validator_network
+        .iter()
+        .chain(config.full_node_networks.iter());
+    for net in networks {
+        match socket_addr(&net.listen_address) {
+            Some(a) => ports.push((format!("{} network", net.network_id), a)),
+            None => p.warn(format!(
+                "cannot check listen_address {} of the {} network",
+                net.listen_address, net.network_id
+            )),
+        }
+    }
+    if config.api.enabled {
+        ports.push(("api".to_string(), config.api.address));
+    }
+
+    for (name, addr) in ports {
+        match TcpListener::bind(addr) {
+            Ok(_) => p.ok(format!("{} port {} is free", name, addr.port())),
+            Err(e) => p.fail(format!(
+                "{} port {} is not available ({}), is a node already running?",
+                name,
+                addr.port(),
+                e
+            )),
+        }
+    }
+}
+
+/// the ip and tcp port of a listen address
+fn socket_addr(addr: &NetworkAddress) -> Option<SocketAddr> {
+    let mut ip = None;
+    let mut port = None;
+    for proto in addr.as_slice() {
+        match proto {
+            Protocol::Ip4(i) => ip = Some(std::net::IpAddr::V4(*i)),
+            Protocol::Ip6(i) => ip = Some(std::net::IpAddr::V6(*i)),
+            Protocol::Tcp(p) => port = Some(*p),
+            _ => {}
+        }
+    }
+    Some(SocketAddr::new(ip?, port?))
+}
+
+fn check_identity_files(p: &mut Preflight, config: &NodeConfig) {
+    let networks = config
+        .validator_network
+        .iter()
+        .chain(config.full_node_networks.iter());
+    for net in networks {
+        if let Identity::FromFile(id) = &net.identity {
+            check_key_file(p, &id.path, &format!("{} network identity", net.network_id));
+        }
+    }
+
+    if config.base.role.is_validator() {
+        if let InitialSafetyRulesConfig::FromFile {
+            identity_blob_path, ..
+        } = &config.consensus.safety_rules.initial_safety_rules_config
+        {
+            check_key_file(p, identity_blob_path, "safety rules identity");
+        }
+    }
+}
+
+/// key files must exist, and should only be readable by the owner
+fn check_key_file(p: &mut Preflight, path: &Path, name: &str) {
+    let meta = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(_) => {
+            p.fail(format!(
+                "{} file {} does not exist, restore it from your backup",
+                name,
+                path.display()
+            ));
+            return;
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = meta.permissions().mode();
+        if mode & 0o077 != 0 {
+            p.warn(format!(
+                "{} file {} can be read by other users (mode {:o}), run `chmod 600 {}`",
+                name,
+                path.display(),
+                mode & 0o777,
+                path.display()
+            ));
+            return;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = meta;
+
+    p.ok(format!("{}: {}", name, path.display()));
+}
+
+fn check_peers(p: &mut Preflight, config: &NodeConfig) {
+    // validators find each other on chain, and are the seeds of their own vfn
+    let is_validator = config.base.role.is_validator();
+    for net in &config.full_node_networks {
+        let needs_seeds = !is_validator && net.network_id == NetworkId::Public;
+        check_network_peers(p, net, needs_seeds);
+    }
+    if let Some(net) = &config.validator_network {
+        check_network_peers(p, net, false);
+    }
+}
+
+fn check_network_peers(p: &mut Preflight, net: &NetworkConfig, needs_seeds: bool) {
+    let mut count = 0;
+    for (peer, addrs) in &net.seed_addrs {
+        for a in addrs {
+            count += 1;
+            if a.find_noise_proto().is_none() || !is_dialable(a) {
+                p.fail(format!(
+                    "seed address {} of peer {} on the {} network needs a host, tcp port and noise-ik key",
+                    a, peer, net.network_id
+                ));
+            }
+        }
+    }
+    for (peer, seed) in &net.seeds {
+        count += 1;
+        if seed.addresses.is_empty() {
+            p.warn(format!(
+                "seed {} on the {} network has no addresses",
+                peer, net.network_id
+            ));
+        }
+    }
+
+    if count == 0 && needs_seeds {
+        p.warn(format!(
+            "no seed peers on the {} network, the node may never connect. Try `lotus config fullnode refresh-peers`",
+            net.network_id
+        ));
+    } else if count > 0 {
+        p.ok(format!(
+            "{} seed peers on the {} network",
+            count, net.network_id
+        ));
+    }
+}
+
+/// the address has a host and a port to dial
+fn is_dialable(addr: &NetworkAddress) -> bool {
+    let has_host = addr.as_slice().iter().any(|p| {
+        matches!(
+            p,
+            Protocol::Ip4(_)
+                | Protocol::Ip6(_)
+                | Protocol::Dns(_)
+                | Protocol::Dns4(_)
+                | Protocol::Dns6(_)
+        )
+    });
+    let has_port = addr
+        .as_slice()
+        .iter()
+        .any(|p| matches!(p, Protocol::Tcp(_)));
+    has_host && has_port
+}
+
+/// a fullnode config which passes every check but the disk space, in a temp dir
+#[cfg(test)]
+fn test_config(dir: &Path) -> NodeConfig {
+    use diem_config::config::{RoleType, WaypointConfig};
+    use diem_crypto::x25519;
+    use diem_types::chain_id::{ChainId, NamedChain};
+    use diem_vm_genesis::TestValidator;
+    use lotus_framework::head_release_bundle;
+    use lotus_genesis_tools::{
+        genesis::make_recovery_genesis_from_vec_legacy_recovery, vm::lotus_genesis_default,
+    };
+    use std::collections::HashMap;
+
+    let validators: Vec<_> = TestValidator::new_test_set(Some(1), None)
+        .into_iter()
+        .map(|t| t.data)
+        .collect();
+    let gen_tx = make_recovery_genesis_from_vec_legacy_recovery(
+        &mut [],
+        &validators,
+        &head_release_bundle(),
+        ChainId::test(),
+        &lotus_genesis_default(NamedChain::TESTING),
+    )
+    .unwrap();
+    let blob = dir.join("genesis.blob");
+    fs::write(&blob, bcs::to_bytes(&gen_tx).unwrap()).unwrap();
+    let (_, waypoint) =
+        bootstrap_db_reader_from_gen_tx(&read_blob_to_tx(blob.clone()).unwrap()).unwrap();
+
+    let key_path = dir.join("public-identity.yaml");
+    fs::write(&key_path, "key").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600)).unwrap();
+    }
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut net = NetworkConfig::network_with_id(NetworkId::Public);
+    net.listen_address = format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap();
+    net.identity = Identity::from_file(key_path);
+    let seed: NetworkAddress = "/ip4/10.0.0.1/tcp/6182".parse().unwrap();
+    net.seed_addrs = HashMap::from([(
+        diem_types::PeerId::random(),
+        vec![seed.append_prod_protos(x25519::PublicKey::from([7u8; 32]), 0)],
+    )]);
+
+    let mut config = NodeConfig::default();
+    config.base.role = RoleType::FullNode;
+    config.base.data_dir = dir.join("data");
+    config.base.waypoint = WaypointConfig::FromConfig(waypoint);
+    config.validator_network = None;
+    config.full_node_networks = vec![net];
+    config.execution.genesis = None;
+    config.execution.genesis_file_location = blob;
+    config.api.enabled = false;
+    config
+}
+
+#[cfg(test)]
+fn failures(p: &Preflight) -> Vec<&str> {
+    p.findings
+        .iter()
+        .filter(|f| f.level == Level::Fail)
+        .map(|f| f.message.as_str())
+        .collect()
+}
+
+#[test]
+fn preflight_good_config() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let config = test_config(dir.path());
+    let config_path = dir.path().join("fullnode.yaml");
+
+    // the free disk space depends on the machine, so check_data_dir is left out
+    let mut p = Preflight::default();
+    check_role(&mut p, &config, &config_path);
+    check_genesis(&mut p, &config);
+    check_ports(&mut p, &config);
+    check_identity_files(&mut p, &config);
+    check_peers(&mut p, &config);
+    assert!(p.passed(), "{:?}", failures(&p));
+    assert!(p
+        .findings
+        .iter()
+        .any(|f| f.message.starts_with("genesis blob matches waypoint")));
+}
+
+#[test]
+fn preflight_missing_genesis() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let config = test_config(dir.path());
+    fs::remove_file(&config.execution.genesis_file_location).unwrap();
+
+    let mut p = Preflight::default();
+    check_genesis(&mut p, &config);
+    assert!(!p.passed());
+    assert!(failures(&p)[0].contains("does not exist"));
+}
+
+#[test]
+fn preflight_waypoint_mismatch() {
+    use diem_config::config::WaypointConfig;
+    use diem_types::waypoint::Waypoint;
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let mut config = test_config(dir.path());
+    // a genesis waypoint of another chain
+    let other: Waypoint = "0:95023f4d6a7e24cac3e52cad29697184db260214210b57aef3f1031ad4d8c02c"
+        .parse()
+        .unwrap();
+    config.base.waypoint = WaypointConfig::FromConfig(other);
+
+    let mut p = Preflight::default();
+    check_genesis(&mut p, &config);
+    assert!(!p.passed());
+    assert!(failures(&p)[0].contains("but the config has"));
+}
+
+#[test]
+fn preflight_port_in_use() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let config = test_config(dir.path());
+    let listen = socket_addr(&config.full_node_networks[0].listen_address).unwrap();
+    let _running_node = TcpListener::bind(listen).unwrap();
+
+    let mut p = Preflight::default();
+    check_ports(&mut p, &config);
+    assert!(!p.passed());
+    assert!(failures(&p)[0].contains("is not available"));
+}
+
+#[test]
+fn preflight_missing_keys() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let config = test_config(dir.path());
+    fs::remove_file(dir.path().join("public-identity.yaml")).unwrap();
+
+    let mut p = Preflight::default();
+    check_identity_files(&mut p, &config);
+    assert!(!p.passed());
+    assert!(failures(&p)[0].contains("does not exist, restore it"));
+}
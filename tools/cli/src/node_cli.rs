@@ -1,5 +1,6 @@
+use crate::{node_check::check_node_config, node_status::node_status};
 use anyhow::{anyhow, bail};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use diem_config::config::NodeConfig;
 use lotus_types::global_config_dir;
 use std::path::PathBuf;
@@ -7,9 +8,28 @@ use std::path::PathBuf;
 #[derive(Parser)]
 /// Start a libra node
 pub struct NodeCli {
+    #[clap(subcommand)]
+    subcommand: Option<NodeSub>,
+
     #[clap(short, long)]
     /// filepath to the validator or fullnode yaml config file.
     config_path: Option<PathBuf>,
+
+    #[clap(long)]
+    /// start without the preflight checks of the config
+    skip_check: bool,
+}
+
+#[derive(Subcommand)]
+enum NodeSub {
+    /// Check the node config, genesis, ports, keys and disk without starting the node
+    Check,
+    /// Show the status of the running node, from its API and metrics
+    Status {
+        #[clap(long)]
+        /// optional, path to the lotus-cli-config.yaml with the upstream to compare with
+        cli_config_path: Option<PathBuf>,
+    },
 }
 
 impl NodeCli {
@@ -35,6 +55,29 @@ impl NodeCli {
             )
         })?;
 
+        if let Some(NodeSub::Status { cli_config_path }) = &self.subcommand {
+            let rt = tokio::runtime::Runtime::new()?;
+            return rt.block_on(node_status(&config, cli_config_path.clone()));
+        }
+
+        if let Some(NodeSub::Check) = &self.subcommand {
+            let preflight = check_node_config(&config, &path);
+            preflight.print();
+            if !preflight.passed() {
+                bail!("the node config has problems which will prevent it from starting");
+            }
+            println!("SUCCESS: the node is ready to start");
+            return Ok(());
+        }
+
+        if !self.skip_check {
+            let preflight = check_node_config(&config, &path);
+            if !preflight.passed() {
+                preflight.print();
+                bail!("preflight checks failed, fix the above or start with --skip-check");
+            }
+        }
+
         // Start the node
         diem_node::start(config, None, true).expect("Node should start correctly");
 
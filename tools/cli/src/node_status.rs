@@ -0,0 +1,203 @@
+//! Status of a local node, from its own API and metrics
+
+use anyhow::Context;
+use diem_config::config::NodeConfig;
+use lotus_query::account_queries::get_current_validators;
+use lotus_types::{core_types::app_cfg::AppCfg, exports::Client};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+};
+use url::Url;
+
+/// a node listening on all interfaces is reached on localhost
+fn local_url(addr: SocketAddr) -> anyhow::Result<Url> {
+    let ip = if addr.ip().is_unspecified() {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    } else {
+        addr.ip()
+    };
+    Ok(format!("http://{}/", SocketAddr::new(ip, addr.port())).parse()?)
+}
+
+/// Samples of the prometheus text format, as metric name, labels and value.
+/// Metric names are kept without the diem_ or aptos_ prefix.
+fn parse_metrics(text: &str) -> Vec<(String, BTreeMap<String, String>, f64)> {
+    text.lines()
+        .filter(|l| !l.starts_with('#') && !l.trim().is_empty())
+        .filter_map(|l| {
+            let (series, value) = l.rsplit_once(' ')?;
+            let value: f64 = value.parse().ok()?;
+            let (name, labels) = match series.split_once('{') {
+                Some((n, rest)) => {
+                    let labels = rest
+                        .trim_end_matches('}')
+                        .split(',')
+                        .filter_map(|kv| {
+                            let (k, v) = kv.split_once('=')?;
+                            Some((k.to_string(), v.trim_matches('"').to_string()))
+                        })
+                        .collect();
+                    (n, labels)
+                }
+                None => (series, BTreeMap::new()),
+            };
+            let name = name
+                .trim_start_matches("diem_")
+                .trim_start_matches("aptos_");
+            Some((name.to_string(), labels, value))
+        })
+        .collect()
+}
+
+/// sum of all the samples of a metric
+fn metric_sum(metrics: &[(String, BTreeMap<String, String>, f64)], name: &str) -> Option<f64> {
+    let values: Vec<f64> = metrics
+        .iter()
+        .filter(|(n, _, _)| n == name)
+        .map(|(_, _, v)| *v)
+        .collect();
+    (!values.is_empty()).then(|| values.iter().sum())
+}
+
+/// the sample of a metric with this label
+fn metric_with_label(
+    metrics: &[(String, BTreeMap<String, String>, f64)],
+    name: &str,
+    label: &str,
+    value: &str,
+) -> Option<f64> {
+    metrics
+        .iter()
+        .find(|(n, l, _)| n == name && l.get(label).map(|v| v.as_str()) == Some(value))
+        .map(|(_, _, v)| *v)
+}
+
+/// Print the status of the node running with this config. The upstream it is
+/// compared with comes from the cli config, at the default path if none is given.
+pub async fn node_status(
+    config: &NodeConfig,
+    cli_config_path: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    println!("role: {}", config.base.role);
+    println!(
+        "state sync: bootstrapping {:?}, continuous {:?}",
+        config.state_sync.state_sync_driver.bootstrapping_mode,
+        config.state_sync.state_sync_driver.continuous_syncing_mode
+    );
+
+    // API
+    let api_url = local_url(config.api.address)?;
+    let client = Client::new(api_url.clone());
+    let local_version = match client.get_index().await {
+        Ok(res) => {
+            let index = res.into_inner();
+            let version: u64 = index.ledger_version.into();
+            println!("api: {}", api_url);
+            println!("chain id: {}", index.chain_id);
+            println!("epoch: {}", index.epoch);
+            println!("ledger version: {}", version);
+            println!("ledger timestamp: {}", index.ledger_timestamp);
+            Some(version)
+        }
+        Err(e) => {
+            println!("api: not reachable at {} ({})", api_url, e);
+            None
+        }
+    };
+
+    // Metrics
+    let inspection = format!(
+        "{}:{}",
+        config.inspection_service.address, config.inspection_service.port
+    );
+    let metrics_url = match inspection.parse::<SocketAddr>() {
+        Ok(a) => local_url(a)?.join("metrics")?,
+        Err(_) => format!("http://{}/metrics", inspection).parse()?,
+    };
+    match reqwest::get(metrics_url.clone()).await {
+        Ok(res) => {
+            let metrics = parse_metrics(&res.text().await?);
+
+            let mut peers: BTreeMap<String, f64> = BTreeMap::new();
+            metrics
+                .iter()
+                .filter(|(n, _, _)| n == "connections")
+                .for_each(|(_, labels, v)| {
+                    let net = labels.get("network_id").cloned().unwrap_or_default();
+                    *peers.entry(net).or_default() += v;
+                });
+            if peers.is_empty() {
+                println!("peers: none connected");
+            }
+            for (net, count) in peers {
+                println!("peers on {}: {}", net, count);
+            }
+
+            // every transaction is in the ttl index
+            if let Some(size) =
+                metric_with_label(&metrics, "core_mempool_index_size", "index", "system_ttl")
+            {
+                println!("mempool: {} transactions", size);
+            }
+            if let Some(round) = metric_sum(&metrics, "consensus_current_round") {
+                println!("consensus round: {}", round);
+            }
+        }
+        Err(e) => println!("metrics: not reachable at {} ({})", metrics_url, e),
+    }
+
+    // Validator set
+    if let Some(net) = &config.validator_network {
+        let account = net.peer_id();
+        match get_current_validators(&client).await {
+            Ok(vals) if vals.contains(&account) => {
+                println!("validator {} is in the current set", account)
+            }
+            Ok(_) => println!("validator {} is NOT in the current set", account),
+            Err(_) => println!("validator set: not available from the local api"),
+        }
+    }
+
+    // Upstream
+    let upstream = AppCfg::load(cli_config_path)
+        .and_then(|cfg| cfg.pick_url(None))
+        .context("no upstream in lotus-cli-config.yaml");
+    if let (Ok(url), Some(local)) = (upstream, local_version) {
+        match Client::new(url.clone()).get_index().await {
+            Ok(res) => {
+                let remote: u64 = res.into_inner().ledger_version.into();
+                println!(
+                    "upstream {}: version {}, this node is {} versions behind",
+                    url,
+                    remote,
+                    remote.saturating_sub(local)
+                );
+            }
+            Err(e) => println!("upstream {}: not reachable ({})", url, e),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn parse_prometheus_text() {
+    let text = r#"
+# HELP diem_connections Number of current connections and their direction
+# TYPE diem_connections gauge
+diem_connections{direction="inbound",network_id="Public",peer_id="a",role_type="full_node"} 3
+diem_connections{direction="outbound",network_id="Public",peer_id="a",role_type="full_node"} 2
+diem_connections{direction="outbound",network_id="Validator",peer_id="b",role_type="validator"} 4
+diem_consensus_current_round 1234
+"#;
+    let m = parse_metrics(text);
+    assert_eq!(metric_sum(&m, "connections"), Some(9.0));
+    assert_eq!(metric_sum(&m, "consensus_current_round"), Some(1234.0));
+    assert_eq!(metric_sum(&m, "core_mempool_index_size"), None);
+    assert_eq!(
+        metric_with_label(&m, "connections", "network_id", "Validator"),
+        Some(4.0)
+    );
+}
@@ -1,6 +1,10 @@
 use crate::{
+    contacts_cli::ContactsSub,
+    fullnode_cli::FullnodeSub,
     legacy_config,
     make_yaml_public_fullnode::{download_genesis, get_genesis_waypoint, init_fullnode_yaml},
+    network_cli::NetworkSub,
+    profile_cli::ProfileSub,
     validator_config::{validator_dialogue, vfn_dialogue},
 };
 use anyhow::{Context, Result};
@@ -67,6 +71,22 @@ enum ConfigSub {
     /// Show the addresses and configs on this device
     View {},
 
+    /// Add, list, switch between and remove the account profiles
+    #[clap(subcommand)]
+    Profile(ProfileSub),
+
+    /// Address book of other accounts, which can be used as @name in place of an address
+    #[clap(subcommand)]
+    Contacts(ContactsSub),
+
+    /// Check the nodes of the network playlist
+    #[clap(subcommand)]
+    Network(NetworkSub),
+
+    /// Maintain the config of an existing fullnode
+    #[clap(subcommand)]
+    Fullnode(FullnodeSub),
+
     // COMMIT NOTE: we havent'used vendor tooling configs for anything.
     /// Generate validators' config file
     ValidatorInit {
@@ -153,6 +173,14 @@ impl ConfigCli {
                 Ok(())
             }
 
+            Some(ConfigSub::Profile(sub)) => sub.run(self.path.clone(), self.chain_name).await,
+
+            Some(ConfigSub::Contacts(sub)) => sub.run(self.path.clone()),
+
+            Some(ConfigSub::Network(sub)) => sub.run(self.path.clone(), self.chain_name).await,
+
+            Some(ConfigSub::Fullnode(sub)) => sub.run(self.path.clone(), self.chain_name).await,
+
             // Initialize configuration wizard
             Some(ConfigSub::Init {
                 force_address,
@@ -245,7 +273,7 @@ impl ConfigCli {
             // Initialize fullnode configuration
             Some(ConfigSub::FullnodeInit { home_path }) => {
                 download_genesis(home_path.to_owned()).await?;
-                println!("downloaded genesis block");
+                println!("downloaded genesis block, check it matches the waypoint with `lotus genesis verify`");
 
                 let p = init_fullnode_yaml(home_path.to_owned(), true).await?;
 
@@ -0,0 +1,65 @@
+//! Manage the address book in lotus-cli-config.yaml
+
+use anyhow::Result;
+use lotus_types::{
+    core_types::{address_book::parse_hex_address, app_cfg::AppCfg},
+    exports::AccountAddress,
+};
+use std::path::PathBuf;
+
+#[derive(clap::Subcommand)]
+pub enum ContactsSub {
+    /// Save an address under a name, to use as @name in place of the address
+    Add {
+        /// name of the contact
+        name: String,
+        /// account address, the legacy 16-byte format is also accepted
+        #[clap(value_parser = parse_hex_address)]
+        address: AccountAddress,
+        /// optional, a note about the account
+        #[clap(short, long)]
+        note: Option<String>,
+    },
+    /// List the contacts
+    List,
+    /// Remove a contact
+    Remove {
+        /// name of the contact
+        name: String,
+    },
+}
+
+impl ContactsSub {
+    pub fn run(&self, config_path: Option<PathBuf>) -> Result<()> {
+        let mut cfg = AppCfg::load(config_path)?;
+
+        match self {
+            ContactsSub::Add {
+                name,
+                address,
+                note,
+            } => {
+                cfg.add_contact(name, *address, note.to_owned())?;
+                println!("added @{}: {}", name.trim_start_matches('@'), address);
+            }
+            ContactsSub::List => {
+                for c in &cfg.contacts {
+                    println!(
+                        "@{:<12} {} {}",
+                        c.name,
+                        c.address.to_hex_literal(),
+                        c.note.as_deref().unwrap_or("")
+                    );
+                }
+                return Ok(());
+            }
+            ContactsSub::Remove { name } => {
+                let c = cfg.remove_contact(name)?;
+                println!("removed @{}: {}", c.name, c.address);
+            }
+        }
+
+        cfg.save_file()?;
+        Ok(())
+    }
+}
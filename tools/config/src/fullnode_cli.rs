@@ -0,0 +1,98 @@
+//! Maintain the config of a fullnode
+
+use crate::make_yaml_public_fullnode::{
+    add_peers_to_yaml, fetch_seed_addresses, filter_reachable_peers, seed_addresses_from_chain,
+    seed_addresses_from_db, FN_FILENAME,
+};
+use anyhow::{bail, Result};
+use lotus_types::{
+    core_types::app_cfg::AppCfg,
+    exports::{Client, NamedChain},
+    global_config_dir,
+};
+use std::{path::PathBuf, time::Duration};
+use url::Url;
+
+#[derive(clap::Subcommand)]
+pub enum FullnodeSub {
+    /// Replace the seed peers of an existing fullnode.yaml with the fullnode
+    /// addresses of the current validators
+    RefreshPeers {
+        /// optional, path to the fullnode.yaml. Defaults to $HOME/.lotus/fullnode.yaml
+        #[clap(short('y'), long)]
+        fullnode_yaml: Option<PathBuf>,
+        /// optional, a trusted node to read the validator set from.
+        /// Otherwise a node from the config file
+        #[clap(short, long)]
+        url: Option<Url>,
+        /// optional, read the validator set from a local database instead of a node's API
+        #[clap(long, conflicts_with = "url")]
+        db: Option<PathBuf>,
+        /// optional, use the static seed peers file instead of the chain state
+        #[clap(long, conflicts_with_all = ["url", "db"])]
+        static_seeds: bool,
+        /// optional, keep the peers even if they don't accept a TCP connection
+        #[clap(long)]
+        skip_dial: bool,
+        /// optional, seconds to wait for each TCP connection
+        #[clap(long, default_value = "3")]
+        dial_timeout_secs: u64,
+    },
+}
+
+impl FullnodeSub {
+    pub async fn run(
+        &self,
+        config_path: Option<PathBuf>,
+        chain_name: Option<NamedChain>,
+    ) -> Result<()> {
+        match self {
+            FullnodeSub::RefreshPeers {
+                fullnode_yaml,
+                url,
+                db,
+                static_seeds,
+                skip_dial,
+                dial_timeout_secs,
+            } => {
+                let path = fullnode_yaml
+                    .to_owned()
+                    .unwrap_or_else(|| global_config_dir().join(FN_FILENAME));
+                if !path.exists() {
+                    bail!(
+                        "no fullnode config at {}, create one with `lotus config fullnode-init`",
+                        path.display()
+                    );
+                }
+
+                let mut peers = if *static_seeds {
+                    fetch_seed_addresses(None).await?
+                } else if let Some(db) = db {
+                    seed_addresses_from_db(db)?
+                } else {
+                    let url = match url {
+                        Some(u) => u.to_owned(),
+                        None => AppCfg::load(config_path)?.pick_url(chain_name)?,
+                    };
+                    println!("reading the validator set from {}", url);
+                    seed_addresses_from_chain(&Client::new(url)).await?
+                };
+                println!("found {} peers", peers.len());
+
+                if !*skip_dial {
+                    peers = filter_reachable_peers(peers, Duration::from_secs(*dial_timeout_secs))
+                        .await;
+                    println!("{} peers are reachable", peers.len());
+                }
+
+                if peers.is_empty() {
+                    bail!("no seed peers found, leaving {} unchanged", path.display());
+                }
+
+                add_peers_to_yaml(&path, peers)?;
+                println!("seed peers updated in {}", path.display());
+            }
+        }
+        Ok(())
+    }
+}
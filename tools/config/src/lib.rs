@@ -1,6 +1,10 @@
 pub mod config_cli;
+pub mod contacts_cli;
+pub mod fullnode_cli;
 pub mod legacy_config;
 pub mod make_profile; // TODO: deprecated?
 pub mod make_yaml_public_fullnode;
 pub mod make_yaml_validator;
+pub mod network_cli;
+pub mod profile_cli;
 pub mod validator_config;
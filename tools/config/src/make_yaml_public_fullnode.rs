@@ -1,18 +1,30 @@
-use diem_config::config::NodeConfig;
+use anyhow::Context;
+use diem_config::config::{
+    NodeConfig, RocksdbConfigs, BUFFERED_STATE_TARGET_ITEMS,
+    DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD, NO_OP_STORAGE_PRUNER_CONFIG,
+};
 use diem_crypto::x25519;
+use diem_db::DiemDB;
+use diem_state_view::account_with_state_view::AsAccountWithStateView;
+use diem_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
 use diem_types::{
-    network_address::{DnsName, NetworkAddress},
+    account_address::{from_identity_public_key, AccountAddress},
+    account_view::AccountView,
+    network_address::{DnsName, NetworkAddress, Protocol},
     waypoint::Waypoint,
     PeerId,
 };
-use lotus_types::global_config_dir;
+use lotus_query::account_queries::{get_current_validators, get_val_config};
+use lotus_types::{exports::Client, global_config_dir};
 use serde::Deserialize;
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
+    time::Duration,
 };
+use tokio::{net::TcpStream, time::timeout};
 
-const FN_FILENAME: &str = "fullnode.yaml";
+pub const FN_FILENAME: &str = "fullnode.yaml";
 const VFN_FILENAME: &str = "vfn.yaml";
 const DEFAULT_WAYPOINT_VERSION: &str = "6.9.0";
 #[derive(Debug, Deserialize)]
@@ -83,6 +95,135 @@ pub async fn fetch_seed_addresses(
     Ok(seeds)
 }
 
+/// Seed peers from the fullnode addresses the current validators registered on chain.
+/// The node at the client should be a trusted one.
+pub async fn seed_addresses_from_chain(
+    client: &Client,
+) -> anyhow::Result<HashMap<PeerId, Vec<NetworkAddress>>> {
+    let mut seeds = HashMap::new();
+    for val in get_current_validators(client).await? {
+        // a validator without a readable config is skipped, like an undecodable address
+        let addresses = get_val_config(client, val)
+            .await
+            .context("can't get the validator config")
+            .and_then(|c| {
+                c.fullnode_network_addresses()
+                    .context("can't BCS decode the fullnode network address")
+            });
+        add_seed_addresses(&mut seeds, val, addresses);
+    }
+    Ok(seeds)
+}
+
+/// Seed peers from the validator set in a local database, such as the one of a stopped node.
+/// The database is opened read-only.
+pub fn seed_addresses_from_db(
+    db_path: &Path,
+) -> anyhow::Result<HashMap<PeerId, Vec<NetworkAddress>>> {
+    let db = DiemDB::open(
+        db_path,
+        true, /* readonly */
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfigs::default(),
+        false, /* indexer */
+        BUFFERED_STATE_TARGET_ITEMS,
+        DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+    )
+    .context("Failed to open DB.")?;
+    let db_rw = DbReaderWriter::new(db);
+
+    let state_view = db_rw.reader.latest_state_checkpoint_view()?;
+    let val_set = state_view
+        .as_account_with_state_view(&AccountAddress::ONE)
+        .get_validator_set()?
+        .context("db returns None for validator set struct")?;
+
+    let mut seeds = HashMap::new();
+    for val in val_set.payload() {
+        let addresses = val
+            .config()
+            .fullnode_network_addresses()
+            .context("can't BCS decode the fullnode network address");
+        add_seed_addresses(&mut seeds, *val.account_address(), addresses);
+    }
+    Ok(seeds)
+}
+
+/// the peer id of a fullnode is derived from the noise key in its address
+fn add_seed_addresses(
+    seeds: &mut HashMap<PeerId, Vec<NetworkAddress>>,
+    validator: AccountAddress,
+    addresses: anyhow::Result<Vec<NetworkAddress>>,
+) {
+    let addresses = match addresses {
+        Ok(a) => a,
+        Err(e) => {
+            println!("WARN: skipping validator {}: {:#}", validator, e);
+            return;
+        }
+    };
+    for addr in addresses {
+        match addr.find_noise_proto() {
+            Some(pubkey) => seeds
+                .entry(from_identity_public_key(pubkey))
+                .or_default()
+                .push(addr),
+            None => println!(
+                "WARN: skipping fullnode address of {} without a noise key: {}",
+                validator, addr
+            ),
+        }
+    }
+}
+
+/// Keep only the addresses which accept a TCP connection, and drop the peers left
+/// without any address.
+pub async fn filter_reachable_peers(
+    seeds: HashMap<PeerId, Vec<NetworkAddress>>,
+    dial_timeout: Duration,
+) -> HashMap<PeerId, Vec<NetworkAddress>> {
+    let mut reachable = HashMap::new();
+    for (peer, addresses) in seeds {
+        let mut ok = vec![];
+        for addr in addresses {
+            if is_reachable(&addr, dial_timeout).await {
+                ok.push(addr);
+            } else {
+                println!("unreachable, skipping: {}", addr);
+            }
+        }
+        if !ok.is_empty() {
+            reachable.insert(peer, ok);
+        }
+    }
+    reachable
+}
+
+/// dial the host and tcp port of a network address
+async fn is_reachable(addr: &NetworkAddress, dial_timeout: Duration) -> bool {
+    let mut host = None;
+    let mut port = None;
+    for p in addr.as_slice() {
+        match p {
+            Protocol::Ip4(ip) => host = Some(ip.to_string()),
+            Protocol::Ip6(ip) => host = Some(format!("[{}]", ip)),
+            Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => {
+                host = Some(name.to_string())
+            }
+            Protocol::Tcp(p) => port = Some(*p),
+            _ => {}
+        }
+    }
+    let target = match (host, port) {
+        (Some(h), Some(p)) => format!("{}:{}", h, p),
+        _ => return false,
+    };
+    matches!(
+        timeout(dial_timeout, TcpStream::connect(target)).await,
+        Ok(Ok(_))
+    )
+}
+
 /// Create a fullnode yaml to bootstrap node
 pub fn make_fullnode_yaml(home_dir: Option<PathBuf>, waypoint: Waypoint) -> anyhow::Result<String> {
     let home_dir = home_dir.unwrap_or_else(global_config_dir);
@@ -297,3 +438,60 @@ async fn persist_genesis() {
         .unwrap();
     assert!(l.file_name().to_str().unwrap().contains("genesis.blob"));
 }
+
+#[test]
+fn seed_peers_from_validator_addresses() {
+    let pubkey = x25519::PublicKey::from([7u8; 32]);
+    let with_noise = "/ip4/10.0.0.1/tcp/6182"
+        .parse::<NetworkAddress>()
+        .unwrap()
+        .append_prod_protos(pubkey, 0);
+    let without_noise = "/ip4/10.0.0.2/tcp/6182".parse::<NetworkAddress>().unwrap();
+
+    let mut seeds = HashMap::new();
+    add_seed_addresses(
+        &mut seeds,
+        AccountAddress::ONE,
+        Ok(vec![with_noise.clone(), without_noise]),
+    );
+    // a validator whose config can't be read is skipped
+    add_seed_addresses(
+        &mut seeds,
+        AccountAddress::TWO,
+        Err(anyhow::anyhow!("no validator config")),
+    );
+
+    assert_eq!(seeds.len(), 1);
+    assert_eq!(
+        seeds.get(&from_identity_public_key(pubkey)),
+        Some(&vec![with_noise])
+    );
+}
+
+#[tokio::test]
+async fn filter_unreachable_peers() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let open_port = listener.local_addr().unwrap().port();
+    // bind and drop, so nothing listens on the port
+    let closed_port = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let addr = |port: u16| {
+        format!("/ip4/127.0.0.1/tcp/{}", port)
+            .parse::<NetworkAddress>()
+            .unwrap()
+    };
+    let up = PeerId::random();
+    let down = PeerId::random();
+    let mut seeds = HashMap::new();
+    seeds.insert(up, vec![addr(open_port), addr(closed_port)]);
+    seeds.insert(down, vec![addr(closed_port)]);
+
+    let reachable = filter_reachable_peers(seeds, Duration::from_secs(2)).await;
+    assert_eq!(reachable.len(), 1);
+    assert_eq!(reachable.get(&up), Some(&vec![addr(open_port)]));
+}
@@ -0,0 +1,81 @@
+//! Check the nodes of the network playlist in lotus-cli-config.yaml
+
+use anyhow::Result;
+use lotus_types::{core_types::app_cfg::AppCfg, exports::NamedChain};
+use std::path::PathBuf;
+
+#[derive(clap::Subcommand)]
+pub enum NetworkSub {
+    /// Check the health of every node in the playlist, and save the scores.
+    /// The best scored node is then used by the cli tools
+    Doctor {
+        /// optional, only print the results, don't save them
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+impl NetworkSub {
+    pub async fn run(
+        &self,
+        config_path: Option<PathBuf>,
+        chain_name: Option<NamedChain>,
+    ) -> Result<()> {
+        match self {
+            NetworkSub::Doctor { dry_run } => {
+                let mut cfg = AppCfg::load(config_path)?;
+                let np = cfg.get_network_profile_mut(chain_name)?;
+                println!(
+                    "checking {} nodes of the {} playlist",
+                    np.nodes.len(),
+                    np.chain_name
+                );
+                np.doctor().await?;
+
+                println!(
+                    "{:<5} {:<40} {:>8} {:>10} {:>10} {:>7} {:>6}",
+                    "score", "url", "chain", "latency", "lag", "errors", "views"
+                );
+                for n in &np.nodes {
+                    let h = match &n.health {
+                        Some(h) => h,
+                        None => continue,
+                    };
+                    let chain = match (h.chain_id, h.chain_ok) {
+                        (_, true) => "ok".to_string(),
+                        (Some(id), false) => format!("WRONG {}", id),
+                        (None, false) => "-".to_string(),
+                    };
+                    println!(
+                        "{:<5} {:<40} {:>8} {:>10} {:>10} {:>6.0}% {:>6}",
+                        if h.is_dead {
+                            "DEAD".to_string()
+                        } else {
+                            h.score.to_string()
+                        },
+                        n.url.as_str(),
+                        chain,
+                        h.latency_ms
+                            .map(|l| format!("{}ms", l))
+                            .unwrap_or_else(|| "-".to_string()),
+                        h.ledger_lag
+                            .map(|l| l.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        h.error_rate * 100.0,
+                        if h.serves_views { "yes" } else { "no" },
+                    );
+                }
+
+                if np.the_best_scored().is_none() {
+                    println!("WARN: no healthy node found, try updating the playlist with `lotus config fix --force-url`");
+                }
+
+                if !*dry_run {
+                    let p = cfg.save_file()?;
+                    println!("scores saved to {}", p.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
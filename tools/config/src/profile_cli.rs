@@ -0,0 +1,143 @@
+//! Manage the account profiles in lotus-cli-config.yaml
+
+use crate::legacy_config;
+use anyhow::Result;
+use diem_crypto::{ed25519::Ed25519PrivateKey, ValidCryptoMaterialStringExt};
+use lotus_types::{
+    core_types::app_cfg::{AppCfg, Profile},
+    exports::{AccountAddress, AuthenticationKey, Client, NamedChain},
+    type_extensions::client_ext::ClientExt,
+};
+use std::path::PathBuf;
+
+#[derive(clap::Subcommand)]
+pub enum ProfileSub {
+    /// Add an account, from a mnemonic unless an address and authkey are given
+    Add {
+        /// optional. Nickname for the profile, defaults to the start of the address
+        #[clap(short, long)]
+        nickname: Option<String>,
+        /// optional. Account address, requires --authkey
+        #[clap(long)]
+        address: Option<AccountAddress>,
+        /// optional. Authentication key, requires --address
+        #[clap(long)]
+        authkey: Option<AuthenticationKey>,
+        /// use a private key to add the profile. Warning: intended for testing only.
+        #[clap(long)]
+        test_private_key: Option<String>,
+        /// make this the default profile
+        #[clap(short, long)]
+        default: bool,
+    },
+    /// List the profiles, the default is marked with a *
+    List,
+    /// Make a profile the default one
+    Use {
+        /// nickname or address of the profile
+        name: String,
+    },
+    /// Remove a profile
+    Remove {
+        /// nickname or address of the profile
+        name: String,
+    },
+    /// Change the nickname of a profile
+    Rename {
+        /// nickname or address of the profile
+        name: String,
+        /// the new nickname
+        new_nickname: String,
+    },
+}
+
+impl ProfileSub {
+    /// The chain_name is saved as the profile's chain when adding a profile
+    pub async fn run(
+        &self,
+        config_path: Option<PathBuf>,
+        chain_name: Option<NamedChain>,
+    ) -> Result<()> {
+        let mut cfg = AppCfg::load(config_path)?;
+
+        match self {
+            ProfileSub::Add {
+                nickname,
+                address,
+                authkey,
+                test_private_key,
+                default,
+            } => {
+                let (auth_key, mut account) = match (address, authkey) {
+                    (Some(addr), Some(auth)) => (*auth, *addr),
+                    _ => {
+                        let keys = if let Some(pk) = test_private_key {
+                            let pk = Ed25519PrivateKey::from_encoded_string(pk)?;
+                            lotus_wallet::account_keys::get_account_from_private(&pk)
+                        } else {
+                            legacy_config::prompt_for_account()?
+                        };
+                        (keys.auth_key, keys.account)
+                    }
+                };
+
+                // the address may differ from the authkey after a rotation
+                let chain = chain_name.unwrap_or(cfg.workspace.default_chain_id);
+                if let Ok(url) = cfg.pick_url(Some(chain)) {
+                    let client = Client::new(url);
+                    if client.get_index().await.is_ok() {
+                        if let Ok(a) = client.lookup_originating_address(auth_key).await {
+                            account = a;
+                        }
+                    }
+                }
+
+                let mut profile = Profile::new(auth_key, account);
+                if let Some(n) = nickname {
+                    n.clone_into(&mut profile.nickname);
+                }
+                profile.chain_name = chain_name;
+                let nickname = profile.nickname.clone();
+                cfg.maybe_add_profile(profile)?;
+
+                if *default {
+                    cfg.set_default_profile(&nickname)?;
+                }
+                println!("added profile {} for account {}", nickname, account);
+            }
+            ProfileSub::List => {
+                let default_account = cfg.get_profile(None).ok().map(|p| p.account);
+                for p in &cfg.user_profiles {
+                    let marker = if Some(p.account) == default_account {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!(
+                        "{} {:<12} {} {}",
+                        marker,
+                        p.nickname,
+                        p.account.to_hex_literal(),
+                        cfg.profile_chain(p)
+                    );
+                }
+                return Ok(());
+            }
+            ProfileSub::Use { name } => {
+                cfg.set_default_profile(name)?;
+                println!("default profile is now {}", name);
+            }
+            ProfileSub::Remove { name } => {
+                cfg.try_remove_profile(name)?;
+                println!("removed profile {}", name);
+            }
+            ProfileSub::Rename { name, new_nickname } => {
+                cfg.rename_profile(name, new_nickname)?;
+                println!("renamed profile {} to {}", name, new_nickname);
+            }
+        }
+
+        cfg.save_file()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+//! Audit any genesis blob, independently of the build.
+//! The blob is bootstrapped into a temporary db and the resulting state is
+//! summarized, and compared to a recovery file when one is given.
+
+use crate::{
+    compare::compare_recovery_vec_to_genesis_tx,
+    genesis_reader::{self, make_access_path, total_supply},
+    parse_json,
+    supply::{self, Supply},
+};
+use anyhow::Context;
+use diem_state_view::account_with_state_view::AsAccountWithStateView;
+use diem_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader};
+use diem_types::{account_view::AccountView, state_store::state_key::StateKeyInner};
+use lotus_types::{
+    exports::AccountAddress,
+    legacy_types::legacy_recovery_v6::{self, AccountRole},
+    move_resource::{
+        ancestry::AncestryResource,
+        donor_voice::RegistryResource,
+        gas_coin::{GasCoinStoreResource, SlowWalletBalance},
+    },
+};
+use move_core_types::language_storage::CORE_CODE_ADDRESS;
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
+
+/// number of state items read from the db at a time
+const STATE_CHUNK_SIZE: usize = 1000;
+
+/// A validator in the genesis validator set
+pub struct ValidatorSummary {
+    pub account: AccountAddress,
+    pub voting_power: u64,
+    pub network_addresses: Vec<String>,
+}
+
+/// What a genesis blob contains, and where it differs from what was expected
+#[derive(Default)]
+pub struct GenesisAudit {
+    /// accounts created in genesis
+    pub accounts: usize,
+    /// sum of all the account balances
+    pub balances_total: u64,
+    /// the coin supply on chain
+    pub total_supply: u128,
+    /// the supply breakdown of the recovery file, if any
+    pub expected_supply: Option<Supply>,
+    pub validators: Vec<ValidatorSummary>,
+    pub slow_wallets: usize,
+    pub slow_unlocked_total: u64,
+    pub community_wallets: Vec<AccountAddress>,
+    /// accounts with an ancestry tree
+    pub with_ancestry: usize,
+    /// accounts of the recovery file which should have an ancestry tree
+    pub expected_ancestry: Option<usize>,
+    pub discrepancies: Vec<String>,
+}
+
+impl GenesisAudit {
+    /// true if the blob has everything that was expected
+    pub fn passed(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    pub fn print(&self) {
+        println!("accounts created: {}", self.accounts);
+        println!("total supply: {}", self.total_supply);
+        println!("sum of account balances: {}", self.balances_total);
+        if let Some(s) = &self.expected_supply {
+            println!("recovery file supply:");
+            s.print();
+        }
+
+        println!("validator set: {}", self.validators.len());
+        for v in &self.validators {
+            println!(
+                "  {} voting power {} addresses [{}]",
+                v.account,
+                v.voting_power,
+                v.network_addresses.join(", ")
+            );
+        }
+
+        println!(
+            "slow wallets: {}, unlocked {}",
+            self.slow_wallets, self.slow_unlocked_total
+        );
+        println!("community wallets: {}", self.community_wallets.len());
+        for a in &self.community_wallets {
+            println!("  {}", a);
+        }
+        match self.expected_ancestry {
+            Some(expected) => println!(
+                "ancestry: {} accounts, {} expected from the recovery file",
+                self.with_ancestry, expected
+            ),
+            None => println!("ancestry: {} accounts", self.with_ancestry),
+        }
+
+        if self.passed() {
+            println!("no discrepancies found");
+        } else {
+            println!("{} discrepancies:", self.discrepancies.len());
+            for d in &self.discrepancies {
+                println!("  {}", d);
+            }
+        }
+    }
+}
+
+/// Audit a genesis blob. With a recovery file every migrated account is
+/// checked, and with expected validators the validator set is checked.
+pub fn audit_genesis_blob(
+    blob: PathBuf,
+    recovery: Option<PathBuf>,
+    expected_validators: &[AccountAddress],
+) -> anyhow::Result<GenesisAudit> {
+    let gen_tx = genesis_reader::read_blob_to_tx(blob)?;
+    let (db_rw, _) = genesis_reader::bootstrap_db_reader_from_gen_tx(&gen_tx)?;
+    let db = &db_rw.reader;
+
+    let mut audit = GenesisAudit {
+        total_supply: total_supply(db).context("no coin supply in genesis")?,
+        ..Default::default()
+    };
+
+    // state of every account
+    let accounts = all_accounts(db)?;
+    audit.accounts = accounts.len();
+    let state_view = db.latest_state_checkpoint_view()?;
+    for a in &accounts {
+        let account_state = state_view.as_account_with_state_view(a);
+        if let Some(b) = account_state.get_move_resource::<GasCoinStoreResource>()? {
+            audit.balances_total += b.coin();
+        }
+        if let Some(s) = account_state.get_move_resource::<SlowWalletBalance>()? {
+            audit.slow_wallets += 1;
+            audit.slow_unlocked_total += s.unlocked;
+        }
+        if account_state.get_resource::<AncestryResource>()?.is_some() {
+            audit.with_ancestry += 1;
+        }
+    }
+    if audit.balances_total as u128 > audit.total_supply {
+        audit.discrepancies.push(format!(
+            "account balances add up to {}, more than the total supply {}",
+            audit.balances_total, audit.total_supply
+        ));
+    }
+
+    // system state
+    let root = state_view.as_account_with_state_view(&CORE_CODE_ADDRESS);
+    let val_set = root
+        .get_validator_set()?
+        .context("no validator set in genesis")?;
+    audit.validators = val_set
+        .payload()
+        .map(|v| ValidatorSummary {
+            account: *v.account_address(),
+            voting_power: v.consensus_voting_power(),
+            network_addresses: v
+                .config()
+                .validator_network_addresses()
+                .map(|addrs| addrs.iter().map(|a| a.to_string()).collect())
+                .unwrap_or_default(),
+        })
+        .collect();
+    audit.community_wallets = root
+        .get_move_resource::<RegistryResource>()?
+        .map(|r| r.list)
+        .unwrap_or_default();
+
+    check_validators(&mut audit, expected_validators);
+
+    if let Some(p) = recovery {
+        let mut recovery = parse_json::recovery_file_parse(p)?;
+        let supply = supply::populate_supply_stats_from_legacy(&recovery)?;
+
+        for e in compare_recovery_vec_to_genesis_tx(&mut recovery, db, &supply)? {
+            let account = e
+                .account
+                .map(|a| a.to_hex_literal())
+                .unwrap_or_else(|| format!("recovery index {}", e.index));
+            audit.discrepancies.push(format!(
+                "{}: {}, expected {}, migrated {}",
+                account, e.message, e.expected, e.migrated
+            ));
+        }
+
+        legacy_recovery_v6::strip_system_address(&mut recovery);
+        let mut expected_ancestry = 0;
+        let mut expected_cw = BTreeSet::new();
+        let mut migrated_total: u128 = 0;
+        for r in recovery.iter().filter(|r| r.role != AccountRole::Drop) {
+            let account = match r.account {
+                Some(a) => a,
+                None => continue,
+            };
+            migrated_total += r.balance.as_ref().map(|b| b.coin as u128).unwrap_or(0);
+            if !accounts.contains(&account) {
+                audit
+                    .discrepancies
+                    .push(format!("{}: account was not created", account));
+                continue;
+            }
+            if r.ancestry.is_some() {
+                expected_ancestry += 1;
+                let account_state = state_view.as_account_with_state_view(&account);
+                if account_state.get_resource::<AncestryResource>()?.is_none() {
+                    audit
+                        .discrepancies
+                        .push(format!("{}: ancestry was not migrated", account));
+                }
+            }
+            if let Some(cw) = &r.comm_wallet {
+                expected_cw.extend(cw.list.iter().cloned());
+            }
+        }
+        for cw in expected_cw {
+            if !audit.community_wallets.contains(&cw) {
+                audit
+                    .discrepancies
+                    .push(format!("{}: not a community wallet", cw));
+            }
+        }
+        // genesis also mints to the validators, so the supply can only be larger
+        if audit.total_supply < migrated_total {
+            audit.discrepancies.push(format!(
+                "total supply is {}, less than the {} migrated from the recovery file",
+                audit.total_supply, migrated_total
+            ));
+        }
+        audit.expected_ancestry = Some(expected_ancestry);
+        audit.expected_supply = Some(supply);
+    }
+
+    Ok(audit)
+}
+
+fn check_validators(audit: &mut GenesisAudit, expected: &[AccountAddress]) {
+    if expected.is_empty() {
+        return;
+    }
+    let in_set: Vec<AccountAddress> = audit.validators.iter().map(|v| v.account).collect();
+    for v in expected {
+        if !in_set.contains(v) {
+            audit
+                .discrepancies
+                .push(format!("{}: validator is not in the genesis set", v));
+        }
+    }
+    for v in in_set {
+        if !expected.contains(&v) {
+            audit.discrepancies.push(format!(
+                "{}: validator was not expected in the genesis set",
+                v
+            ));
+        }
+    }
+}
+
+/// every address with a 0x1::account::Account resource
+fn all_accounts(db: &Arc<dyn DbReader>) -> anyhow::Result<BTreeSet<AccountAddress>> {
+    let version = db.get_latest_version()?;
+    let leaves = db.get_state_leaf_count(version)?;
+    let account_path = make_access_path(CORE_CODE_ADDRESS, "account", "Account")?.path;
+
+    let mut found = BTreeSet::new();
+    let mut start = 0;
+    while start < leaves {
+        let chunk = db.get_state_value_chunk_with_proof(version, start, STATE_CHUNK_SIZE)?;
+        if chunk.raw_values.is_empty() {
+            break;
+        }
+        for (key, _) in &chunk.raw_values {
+            if let StateKeyInner::AccessPath(ap) = key.inner() {
+                if ap.path == account_path {
+                    found.insert(ap.address);
+                }
+            }
+        }
+        start += chunk.raw_values.len();
+    }
+    Ok(found)
+}
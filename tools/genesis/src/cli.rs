@@ -1,11 +1,36 @@
+use anyhow::Context;
 use clap::{Args, Parser, Subcommand};
+use diem_genesis::config::HostAndPort;
+use diem_temppath::TempPath;
 
 use crate::{
-    genesis_builder, parse_json, testnet_setup,
+    audit::audit_genesis_blob,
+    compare,
+    custom_testnet::{CustomTestnet, TESTNET_FILE},
+    genesis_builder,
+    genesis_manifest::{GenesisManifest, MANIFEST_FILE},
+    genesis_reader,
+    genesis_repo::{GenesisRepo, GithubRepo, LocalRepo},
+    migration_rules::{apply_rules, ChangeReport, MigrationRules},
+    parse_json,
+    recovery_from_snapshot::recovery_from_snapshot,
+    testnet_setup,
+    verify_genesis::{GenesisVerification, GENESIS_BLOB_FILENAME},
     wizard::{GenesisWizard, GITHUB_TOKEN_FILENAME},
 };
-use lotus_types::{core_types::fixtures::TestPersona, exports::NamedChain, global_config_dir};
-use std::{fs, net::Ipv4Addr, path::PathBuf};
+use lotus_config::make_yaml_public_fullnode::{download_genesis, get_genesis_waypoint};
+use lotus_types::{
+    core_types::fixtures::TestPersona,
+    exports::{AccountAddress, ChainId, NamedChain},
+    global_config_dir,
+    legacy_types::legacy_recovery_v6::LegacyRecoveryV6,
+};
+use std::{
+    fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+};
+use url::Url;
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 /// Generate genesis transactions for testnet and upgrades
@@ -27,46 +52,56 @@ impl GenesisCli {
         let chain_name = self.chain.unwrap_or(NamedChain::TESTNET); // chain_id = 2
 
         match &self.command {
-            Some(Sub::Build { github, drop_list }) => {
-                let mut recovery = if let Some(p) = github.json_legacy.clone() {
-                    parse_json::recovery_file_parse(p)?
-                } else {
-                    vec![]
-                };
-
-                if let Some(dp) = drop_list {
-                    parse_json::drop_accounts(&mut recovery, dp)?;
-                };
-
-                // TODO: there's no reason a github token should be needed to
-                // read the genesis
-                let github_token = fs::read_to_string(data_path.join(GITHUB_TOKEN_FILENAME))?;
+            Some(Sub::Build {
+                github,
+                drop_list,
+                rules,
+            }) => {
+                let (mut recovery, report) = github.recovery(drop_list.as_ref(), rules.as_ref())?;
+                let repo = github.genesis_repo(&data_path)?;
 
-                genesis_builder::build(
-                    github.org_github.to_owned(),
-                    github.name_github.to_owned(),
-                    github_token,
-                    data_path,
+                let files = genesis_builder::build(
+                    Some(repo.as_ref()),
+                    data_path.clone(),
                     github.local_framework,
                     &mut recovery,
                     chain_name,
                     None,
                 )?;
+
+                if let Some(report) = report {
+                    report.save(&data_path.join("genesis"))?;
+                    let gen_tx = genesis_reader::read_blob_to_tx(files[0].clone())?;
+                    let (db_rw, _) = genesis_reader::bootstrap_db_reader_from_gen_tx(&gen_tx)?;
+                    let errs = compare::compare_changes_to_genesis_tx(&report, &db_rw.reader)?;
+                    if !errs.is_empty() {
+                        for e in &errs {
+                            println!("{:?}", e);
+                        }
+                        anyhow::bail!("{} migration rules did not apply in genesis", errs.len());
+                    }
+                    println!(
+                        "all {} changes of the migration rules are in genesis",
+                        report.changes.len()
+                    );
+                }
             }
             Some(Sub::Register { github }) => {
-                GenesisWizard::new(
-                    github.org_github.to_owned(),
-                    github.name_github.to_owned(),
+                let mut wizard = GenesisWizard::new(
+                    github.org_github.to_owned().unwrap_or_default(),
+                    github.name_github.to_owned().unwrap_or_default(),
                     Some(data_path),
                     chain_name,
-                )
-                .start_wizard(
-                    github.local_framework,
-                    github.json_legacy.clone(),
-                    github.token_github_file.clone(),
-                    false,
-                )
-                .await?;
+                );
+                wizard.local_repo = github.local_repo.to_owned();
+                wizard
+                    .start_wizard(
+                        github.local_framework,
+                        github.json_legacy.clone(),
+                        github.token_github_file.clone(),
+                        false,
+                    )
+                    .await?;
             }
 
             Some(Sub::Testnet {
@@ -85,6 +120,165 @@ impl GenesisCli {
                 )
                 .await?
             }
+            Some(Sub::CustomTestnet {
+                count,
+                registrations,
+                host,
+                json_legacy,
+            }) => {
+                let info = CustomTestnet {
+                    count: *count,
+                    registrations: registrations.to_owned(),
+                    hosts: host.to_owned(),
+                    chain: chain_name,
+                    data_path: data_path.clone(),
+                    legacy_data_path: json_legacy.to_owned(),
+                }
+                .setup()
+                .await?;
+                for n in &info.nodes {
+                    println!(
+                        "{} {} {}",
+                        n.name,
+                        n.account,
+                        n.validator_host.as_deref().unwrap_or_default()
+                    );
+                }
+                println!(
+                    "testnet of {} validators described in {}",
+                    info.nodes.len(),
+                    data_path.join(TESTNET_FILE).display()
+                );
+            }
+            Some(Sub::Verify {
+                genesis_blob,
+                waypoint_file,
+                node_config,
+                upstream_url,
+                download,
+            }) => {
+                if *download {
+                    fs::create_dir_all(data_path.join("genesis"))?;
+                    download_genesis(Some(data_path.clone())).await?;
+                    get_genesis_waypoint(Some(data_path.clone())).await?;
+                }
+
+                let mut v = GenesisVerification::from_home(&data_path);
+                if let Some(p) = genesis_blob {
+                    v.genesis_blob = p.to_owned();
+                }
+                if waypoint_file.is_some() {
+                    v.waypoint_file = waypoint_file.to_owned();
+                }
+                if !node_config.is_empty() {
+                    v.node_configs = node_config.to_owned();
+                }
+                v.upstream = upstream_url.to_owned();
+
+                let waypoint = v.run().await?;
+                println!("SUCCESS: genesis verified, waypoint {}", waypoint);
+            }
+            Some(Sub::VerifyManifest {
+                manifest,
+                github,
+                drop_list,
+                rules,
+            }) => {
+                let manifest_path = manifest
+                    .to_owned()
+                    .unwrap_or_else(|| data_path.join("genesis").join(MANIFEST_FILE));
+                let expected = GenesisManifest::read(&manifest_path)?;
+                // build for the chain of the manifest, unless one is given
+                let chain_name = match self.chain {
+                    Some(c) => c,
+                    None => NamedChain::from_chain_id(&ChainId::new(expected.chain_id))
+                        .map_err(|e| anyhow::anyhow!(e))?,
+                };
+
+                let (mut recovery, _) = github.recovery(drop_list.as_ref(), rules.as_ref())?;
+                let repo = github.genesis_repo(&data_path)?;
+
+                // rebuild in a temp home, so the existing genesis files are untouched
+                let tmp_home = TempPath::new();
+                tmp_home.create_as_dir()?;
+                genesis_builder::build(
+                    Some(repo.as_ref()),
+                    tmp_home.path().to_owned(),
+                    github.local_framework,
+                    &mut recovery,
+                    chain_name,
+                    None,
+                )?;
+                let rebuilt =
+                    GenesisManifest::read(&tmp_home.path().join("genesis").join(MANIFEST_FILE))?;
+
+                let diff = expected.diff(&rebuilt);
+                if !diff.is_empty() {
+                    for d in &diff {
+                        println!("DIFFERENT: {}", d);
+                    }
+                    anyhow::bail!(
+                        "the rebuilt genesis does not match {}",
+                        manifest_path.display()
+                    );
+                }
+                println!(
+                    "SUCCESS: rebuilt the same genesis, waypoint {}",
+                    rebuilt.waypoint
+                );
+            }
+            Some(Sub::RecoveryFromSnapshot {
+                manifest,
+                output,
+                scratch_dir,
+            }) => {
+                let output = output.to_owned().unwrap_or_else(|| {
+                    manifest
+                        .parent()
+                        .unwrap_or_else(|| Path::new("."))
+                        .join("migration.json")
+                });
+                let r = recovery_from_snapshot(manifest, scratch_dir.as_deref()).await?;
+                r.save(&output)?;
+
+                println!("accounts exported: {}", r.recovery.len());
+                println!("supply:");
+                r.supply.print();
+                if !r.errors.is_empty() {
+                    println!("accounts which could not be converted: {}", r.errors.len());
+                    for e in &r.errors {
+                        println!(
+                            "  {}: {}",
+                            e.account.as_deref().unwrap_or("unknown account"),
+                            e.message
+                        );
+                    }
+                    println!(
+                        "errors saved to {}",
+                        output.with_extension("errors.json").display()
+                    );
+                }
+                println!("recovery file saved to {}", output.display());
+            }
+            Some(Sub::Audit {
+                blob,
+                recovery,
+                validator,
+            }) => {
+                let blob = blob
+                    .to_owned()
+                    .unwrap_or_else(|| data_path.join("genesis").join(GENESIS_BLOB_FILENAME));
+                println!("auditing {}", blob.display());
+                let audit = audit_genesis_blob(blob, recovery.to_owned(), validator)?;
+                audit.print();
+                if !audit.passed() {
+                    anyhow::bail!(
+                        "genesis audit found {} discrepancies",
+                        audit.discrepancies.len()
+                    );
+                }
+                println!("SUCCESS: genesis audit passed");
+            }
             _ => {
                 println!("\nIf you're looking for trouble \nYou came to the right place");
             }
@@ -100,11 +294,15 @@ struct GithubArgs {
     #[clap(long)]
     token_github_file: Option<PathBuf>,
     /// what are the settings for the genesis repo configs
-    #[clap(short, long)]
-    org_github: String,
+    #[clap(short, long, required_unless_present = "local_repo")]
+    org_github: Option<String>,
     /// name of the repo
-    #[clap(short, long)]
-    name_github: String,
+    #[clap(short, long, required_unless_present = "local_repo")]
+    name_github: Option<String>,
+    /// use a local directory, e.g. a git checkout, as the genesis repo instead
+    /// of GitHub. No github token is needed
+    #[clap(long, conflicts_with_all = ["org_github", "name_github", "token_github_file"])]
+    local_repo: Option<PathBuf>,
     /// uses the local framework build
     #[clap(short, long)]
     local_framework: bool,
@@ -113,6 +311,62 @@ struct GithubArgs {
     json_legacy: Option<PathBuf>,
 }
 
+impl GithubArgs {
+    /// the recovery file, without the dropped accounts and with the
+    /// migration rules applied
+    fn recovery(
+        &self,
+        drop_list: Option<&PathBuf>,
+        rules: Option<&PathBuf>,
+    ) -> anyhow::Result<(Vec<LegacyRecoveryV6>, Option<ChangeReport>)> {
+        let mut recovery = if let Some(p) = self.json_legacy.clone() {
+            parse_json::recovery_file_parse(p)?
+        } else {
+            vec![]
+        };
+
+        if let Some(dp) = drop_list {
+            parse_json::drop_accounts(&mut recovery, dp)?;
+        };
+
+        let report = match rules {
+            Some(p) => {
+                let report = apply_rules(&mut recovery, &MigrationRules::read(p)?)?;
+                println!(
+                    "applied the migration rules of {}, {} accounts changed",
+                    p.display(),
+                    report.final_states().len()
+                );
+                Some(report)
+            }
+            None => None,
+        };
+        Ok((recovery, report))
+    }
+
+    /// the local genesis repo, or the GitHub one with the token from a file
+    fn genesis_repo(&self, data_path: &Path) -> anyhow::Result<Box<dyn GenesisRepo>> {
+        if let Some(p) = &self.local_repo {
+            return Ok(Box::new(LocalRepo::new(p.to_owned())?));
+        }
+        let token_path = self
+            .token_github_file
+            .clone()
+            .unwrap_or_else(|| data_path.join(GITHUB_TOKEN_FILENAME));
+        let token = fs::read_to_string(&token_path).with_context(|| {
+            format!(
+                "could not read the github token at {}, or use --local-repo",
+                token_path.display()
+            )
+        })?;
+        Ok(Box::new(GithubRepo::new(
+            self.org_github.to_owned().unwrap_or_default(),
+            self.name_github.to_owned().unwrap_or_default(),
+            token.trim().to_owned(),
+        )))
+    }
+}
+
 #[derive(Subcommand)]
 enum Sub {
     Build {
@@ -123,6 +377,10 @@ enum Sub {
         /// Ark B
         #[clap(long)]
         drop_list: Option<PathBuf>,
+
+        /// optional, YAML file of migration rules to apply to the recovery file
+        #[clap(long)]
+        rules: Option<PathBuf>,
     }, // just do genesis without wizard
     Register {
         /// github args
@@ -146,4 +404,91 @@ enum Sub {
         #[clap(short, long)]
         keep_legacy_address: Option<Vec<TestPersona>>,
     },
+
+    /// testnet with any number of validators, with generated keys or the
+    /// registration files of existing validators. Writes a testnet.json of all the nodes
+    CustomTestnet {
+        /// number of validators to generate keys and node files for
+        #[clap(short('n'), long, default_value = "4")]
+        count: usize,
+        /// optional, a dir with a folder per validator with its operator.yaml
+        /// and owner.yaml, instead of generating keys
+        #[clap(short, long)]
+        registrations: Option<PathBuf>,
+        /// optional, validator host:port of each validator in order. Defaults
+        /// to localhost, with ports 10 apart from 6180
+        #[clap(long)]
+        host: Vec<HostAndPort>,
+        /// path to file for legacy migration file
+        #[clap(short, long)]
+        json_legacy: Option<PathBuf>,
+    },
+
+    /// Check that a genesis blob matches the waypoint file, the waypoints in the
+    /// node configs, and optionally the genesis of a trusted node.
+    /// Uses the files in the home dir unless paths are given, so it can run offline
+    Verify {
+        /// optional, path to the genesis.blob
+        #[clap(long)]
+        genesis_blob: Option<PathBuf>,
+        /// optional, path to the waypoint.txt
+        #[clap(long)]
+        waypoint_file: Option<PathBuf>,
+        /// optional, node configs to check, otherwise the fullnode.yaml,
+        /// validator.yaml and vfn.yaml in the home dir
+        #[clap(long)]
+        node_config: Vec<PathBuf>,
+        /// optional, backup service URL of a trusted node (usually port 6186),
+        /// to compare the ledger info of epoch 0 with
+        #[clap(short, long)]
+        upstream_url: Option<Url>,
+        /// download the latest genesis.blob and waypoint.txt to the home dir first
+        #[clap(long)]
+        download: bool,
+    },
+
+    /// Rebuild genesis from the same inputs as a genesis_manifest.json, and
+    /// check the result is identical
+    VerifyManifest {
+        /// optional, path to the genesis_manifest.json. Defaults to the one in the home dir
+        #[clap(short, long)]
+        manifest: Option<PathBuf>,
+        /// github args
+        #[clap(flatten)]
+        github: GithubArgs,
+        /// Ark B
+        #[clap(long)]
+        drop_list: Option<PathBuf>,
+        /// optional, YAML file of migration rules to apply to the recovery file
+        #[clap(long)]
+        rules: Option<PathBuf>,
+    },
+
+    /// Make the recovery file for a migration genesis from a state snapshot backup
+    RecoveryFromSnapshot {
+        /// path to the state.manifest of the snapshot backup
+        #[clap(short, long)]
+        manifest: PathBuf,
+        /// optional, where to save the recovery json. Defaults to migration.json next to the backup
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// optional, where to sort the accounts, needs about the size of the
+        /// snapshot free. Defaults to next to the backup dir
+        #[clap(long)]
+        scratch_dir: Option<PathBuf>,
+    },
+
+    /// Audit a genesis blob: accounts, supply, validators, slow wallets,
+    /// community wallets and ancestry. Exits with an error on any discrepancy
+    Audit {
+        /// optional, path to the genesis.blob. Defaults to the one in the home dir
+        #[clap(short, long)]
+        blob: Option<PathBuf>,
+        /// optional, recovery file the genesis was built from, to check every account
+        #[clap(short, long)]
+        recovery: Option<PathBuf>,
+        /// optional, the validators expected in the genesis set
+        #[clap(short, long)]
+        validator: Vec<AccountAddress>,
+    },
 }
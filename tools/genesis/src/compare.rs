@@ -3,7 +3,10 @@
 //! every day is like sunday
 //! -- morrissey via github copilot
 
-use crate::{genesis_reader, genesis_reader::total_supply, parse_json, supply::Supply};
+use crate::{
+    genesis_reader, genesis_reader::total_supply, migration_rules::ChangeReport, parse_json,
+    supply::Supply,
+};
 use anyhow::{self, Context};
 use diem_state_view::account_with_state_view::AsAccountWithStateView;
 use diem_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader};
@@ -48,85 +51,161 @@ pub fn compare_recovery_vec_to_genesis_tx(
     let mut r_as_vec = recovery.to_vec();
     legacy_recovery_v6::strip_system_address(&mut r_as_vec);
 
-    r_as_vec
+    let db_state_view = db_reader.latest_state_checkpoint_view()?;
+    let error = |i: usize, account: Option<AccountAddress>, message: String| CompareError {
+        index: i as u64,
+        account,
+        expected: 0,
+        migrated: 0,
+        message,
+    };
+
+    for (i, old) in r_as_vec
         .iter_mut()
         .progress_with_style(OLProgress::bar())
         .with_message("auditing migration")
         .enumerate()
-        .for_each(|(i, old)| {
-            if old.role == AccountRole::Drop {
-                return;
-            };
-            if old.account.is_none() {
-                err_list.push(CompareError {
-                    index: i as u64,
-                    account: None,
-                    expected: 0,
-                    migrated: 0,
-                    message: "account is None".to_string(),
-                }); // instead of balance, if there is an account that is None, we insert the index of the recovery file
-                return;
-            };
-
-            let convert_address = old.account.unwrap();
-
-            // Ok now let's compare to what's on chain
-            let db_state_view = db_reader.latest_state_checkpoint_view().unwrap();
-            let account_state_view = db_state_view.as_account_with_state_view(&convert_address);
+    {
+        if old.role == AccountRole::Drop {
+            continue;
+        };
+        let convert_address = match old.account {
+            Some(a) => a,
+            None => {
+                // instead of balance, if there is an account that is None, we insert the index of the recovery file
+                err_list.push(error(i, None, "account is None".to_string()));
+                continue;
+            }
+        };
 
-            let on_chain_balance = account_state_view
-                .get_move_resource::<GasCoinStoreResource>()
-                .expect("should have move resource");
+        // Ok now let's compare to what's on chain
+        let account_state_view = db_state_view.as_account_with_state_view(&convert_address);
 
-            if on_chain_balance.is_none() {
+        let on_chain_balance = match account_state_view.get_move_resource::<GasCoinStoreResource>()
+        {
+            Ok(Some(b)) => b,
+            Ok(None) => {
                 println!("account without a balance struct: {}", &convert_address);
-                return;
+                continue;
+            }
+            Err(e) => {
+                err_list.push(error(
+                    i,
+                    old.account,
+                    format!("cannot read the balance: {:#}", e),
+                ));
+                continue;
             }
-            let on_chain_balance = on_chain_balance.expect("should have balance");
+        };
+
+        // CHECK: we should have scaled the balance correctly, including
+        // adjusting for validators
+        let old_coin = old.balance.as_ref().map(|b| b.coin).unwrap_or(0);
+        if on_chain_balance.coin() != old_coin {
+            err_list.push(CompareError {
+                index: i as u64,
+                account: old.account,
+                expected: old_coin,
+                migrated: on_chain_balance.coin(),
+                message: "unexpected balance".to_string(),
+            });
+        }
+
+        user_supply += on_chain_balance.coin();
+
+        // Check Slow Wallet Balance was migrated as expected
+        if let Some(old_slow) = &old.slow_wallet {
+            let new_slow = match account_state_view.get_move_resource::<SlowWalletBalance>() {
+                Ok(Some(s)) => s,
+                Ok(None) => {
+                    err_list.push(error(
+                        i,
+                        old.account,
+                        "slow wallet was not migrated".to_string(),
+                    ));
+                    continue;
+                }
+                Err(e) => {
+                    err_list.push(error(
+                        i,
+                        old.account,
+                        format!("cannot read the slow wallet: {:#}", e),
+                    ));
+                    continue;
+                }
+            };
 
-            // CHECK: we should have scaled the balance correctly, including
-            // adjusting for validators
-            let old_balance = old.balance.as_ref().expect("should have a balance struct");
-            if on_chain_balance.coin() != old_balance.coin {
+            if new_slow.unlocked != old_slow.unlocked {
+                err_list.push(CompareError {
+                    index: i as u64,
+                    account: old.account,
+                    expected: old_slow.unlocked,
+                    migrated: new_slow.unlocked,
+                    message: "unexpected slow wallet unlocked".to_string(),
+                });
+            }
+            // CHECK: the unlocked amount should never be greater than balance
+            if new_slow.unlocked > on_chain_balance.coin() {
                 err_list.push(CompareError {
                     index: i as u64,
                     account: old.account,
-                    expected: old_balance.coin,
+                    expected: new_slow.unlocked,
                     migrated: on_chain_balance.coin(),
-                    message: "unexpected balance".to_string(),
+                    message: "unlocked greater than balance".to_string(),
                 });
             }
+        }
+    }
+    Ok(err_list)
+}
 
-            user_supply += on_chain_balance.coin();
-
-            // Check Slow Wallet Balance was migrated as expected
-            if let Some(old_slow) = &old.slow_wallet {
-                let new_slow = account_state_view
-                    .get_move_resource::<SlowWalletBalance>()
-                    .expect("should have a slow wallet struct")
-                    .unwrap();
-
-                if new_slow.unlocked != old_slow.unlocked {
-                    err_list.push(CompareError {
-                        index: i as u64,
-                        account: old.account,
-                        expected: old_slow.unlocked,
-                        migrated: new_slow.unlocked,
-                        message: "unexpected slow wallet unlocked".to_string(),
-                    });
-                }
-                // CHECK: the unlocked amount should never be greater than balance
-                if new_slow.unlocked > on_chain_balance.coin() {
-                    err_list.push(CompareError {
-                        index: i as u64,
-                        account: old.account,
-                        expected: new_slow.unlocked,
-                        migrated: on_chain_balance.coin(),
-                        message: "unlocked greater than balance".to_string(),
-                    });
-                }
+/// Check the accounts changed by migration rules have the state the rules
+/// expect. The index of a CompareError is the index of the rule.
+pub fn compare_changes_to_genesis_tx(
+    report: &ChangeReport,
+    db_reader: &Arc<dyn DbReader>,
+) -> anyhow::Result<Vec<CompareError>> {
+    let mut err_list: Vec<CompareError> = vec![];
+    let db_state_view = db_reader.latest_state_checkpoint_view()?;
+
+    for change in report.final_states() {
+        let account_state_view = db_state_view.as_account_with_state_view(&change.account);
+        let on_chain_balance = account_state_view
+            .get_move_resource::<GasCoinStoreResource>()?
+            .map(|b| b.coin())
+            .unwrap_or(0);
+
+        // dropped accounts must not get any coins
+        let expected = if change.dropped { 0 } else { change.balance };
+        if on_chain_balance != expected {
+            err_list.push(CompareError {
+                index: change.rule_index as u64,
+                account: Some(change.account),
+                expected,
+                migrated: on_chain_balance,
+                message: format!("unexpected balance after rule: {}", change.rule),
+            });
+        }
+
+        if let Some(unlocked) = change.slow_unlocked.filter(|_| !change.dropped) {
+            let migrated = account_state_view
+                .get_move_resource::<SlowWalletBalance>()?
+                .map(|s| s.unlocked)
+                .unwrap_or(0);
+            if migrated != unlocked {
+                err_list.push(CompareError {
+                    index: change.rule_index as u64,
+                    account: Some(change.account),
+                    expected: unlocked,
+                    migrated,
+                    message: format!(
+                        "unexpected slow wallet unlocked after rule: {}",
+                        change.rule
+                    ),
+                });
             }
-        });
+        }
+    }
     Ok(err_list)
 }
 
@@ -0,0 +1,408 @@
+//! A testnet with any number of validators, for tests which need more than
+//! the four fixture personas. Keys are generated, or read from the
+//! registration files of existing validators.
+
+use crate::{
+    genesis_builder::{self, validator_config_from_mnem},
+    genesis_repo::{GenesisRepo, LocalRepo},
+    parse_json,
+    verify_genesis::{GENESIS_BLOB_FILENAME, WAYPOINT_FILENAME},
+};
+use anyhow::{bail, Context};
+use diem_crypto::ValidCryptoMaterialStringExt;
+use diem_genesis::config::{HostAndPort, ValidatorConfiguration};
+use lotus_config::{make_yaml_validator::NODE_YAML_FILE, validator_config};
+use lotus_types::exports::{AccountAddress, NamedChain};
+use lotus_wallet::{
+    key_gen::keygen,
+    utils::write_to_user_only_file,
+    validator_files::{OPERATOR_FILE, OWNER_FILE},
+};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const TESTNET_FILE: &str = "testnet.json";
+const DEFAULT_VALIDATOR_PORT: u16 = 6180;
+/// the vfn and public networks listen on the next ports
+const FULLNODE_PORT_OFFSET: u16 = 2;
+/// ports between two nodes on the same machine
+const LOCAL_PORT_STEP: u16 = 10;
+const DEFAULT_API_PORT: u16 = 8080;
+
+/// One node of the testnet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestnetNode {
+    pub name: String,
+    pub account: AccountAddress,
+    pub consensus_public_key: Option<String>,
+    pub validator_network_public_key: Option<String>,
+    pub full_node_network_public_key: Option<String>,
+    pub validator_host: Option<String>,
+    pub full_node_host: Option<String>,
+    /// the api port, for nodes set up by this tool
+    pub api_port: Option<u16>,
+    /// home dir of the node, for nodes set up by this tool
+    pub home: Option<PathBuf>,
+}
+
+/// Every node of the testnet, saved as testnet.json
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestnetInfo {
+    pub chain_id: u8,
+    pub waypoint: String,
+    pub genesis_blob: PathBuf,
+    pub nodes: Vec<TestnetNode>,
+}
+
+/// How to make the testnet
+pub struct CustomTestnet {
+    /// number of validators to generate keys for
+    pub count: usize,
+    /// a dir with a folder per validator, each with its operator.yaml and owner.yaml
+    pub registrations: Option<PathBuf>,
+    /// validator host:port of each validator, in order. Missing ones are
+    /// on localhost with ports counting up from 6180
+    pub hosts: Vec<HostAndPort>,
+    pub chain: NamedChain,
+    pub data_path: PathBuf,
+    pub legacy_data_path: Option<PathBuf>,
+}
+
+impl CustomTestnet {
+    /// host:port of the validator at this index
+    fn host(&self, idx: usize) -> anyhow::Result<HostAndPort> {
+        match self.hosts.get(idx) {
+            Some(h) => Ok(h.to_owned()),
+            None => {
+                let port = DEFAULT_VALIDATOR_PORT + idx as u16 * LOCAL_PORT_STEP;
+                HostAndPort::from_str(&format!("127.0.0.1:{}", port))
+            }
+        }
+    }
+
+    /// Generate the keys and node files, build genesis, and write testnet.json
+    pub async fn setup(&self) -> anyhow::Result<TestnetInfo> {
+        let mut nodes = vec![];
+        let mut val_cfg: Vec<ValidatorConfiguration> = vec![];
+
+        if let Some(dir) = &self.registrations {
+            let repo = LocalRepo::new(dir.to_owned())?;
+            for (idx, name) in registration_dirs(dir)?.into_iter().enumerate() {
+                let mut cfg = genesis_builder::get_config(&repo, &name, false)
+                    .with_context(|| format!("registration of {} in {}", name, repo.location()))?;
+                if let Some(host) = self.hosts.get(idx) {
+                    cfg.validator_host = Some(host.to_owned());
+                }
+                nodes.push(node_info(&name, &cfg, None, None)?);
+                val_cfg.push(cfg);
+            }
+        } else {
+            if self.count == 0 {
+                bail!("a testnet needs at least one validator");
+            }
+            for idx in 0..self.count {
+                let name = format!("val_{}", idx);
+                let home = self.data_path.join("testnet").join(&name);
+                let host = self.host(idx)?;
+                let api_port = DEFAULT_API_PORT + idx as u16;
+
+                let (.., mnem) = keygen();
+                fs::create_dir_all(&home)?;
+                let mnem_file = format!("{}.mnem", name);
+                write_to_user_only_file(&home.join(&mnem_file), &mnem_file, mnem.as_bytes())?;
+
+                validator_config::initialize_validator(
+                    Some(home.clone()),
+                    Some(&name),
+                    host.clone(),
+                    Some(mnem.clone()),
+                    false,
+                    Some(self.chain),
+                )
+                .await?;
+                set_node_ports(&home.join(NODE_YAML_FILE), host.port, api_port)?;
+
+                let mut cfg = validator_config_from_mnem(mnem, &host, false)?;
+                cfg.full_node_host = Some(HostAndPort::from_str(&format!(
+                    "{}:{}",
+                    host.host,
+                    host.port + FULLNODE_PORT_OFFSET
+                ))?);
+                nodes.push(node_info(&name, &cfg, Some(api_port), Some(home))?);
+                val_cfg.push(cfg);
+            }
+        }
+
+        if val_cfg.is_empty() {
+            bail!("no validators found for the testnet");
+        }
+
+        let mut recovery = match &self.legacy_data_path {
+            Some(p) => parse_json::recovery_file_parse(p.to_owned())?,
+            None => vec![],
+        };
+
+        genesis_builder::build(
+            None,
+            self.data_path.clone(),
+            true,
+            &mut recovery,
+            self.chain,
+            Some(val_cfg),
+        )?;
+
+        // every node set up here gets a copy of the genesis
+        let genesis_dir = self.data_path.join("genesis");
+        for home in nodes.iter().filter_map(|n| n.home.as_ref()) {
+            fs::create_dir_all(home.join("genesis"))?;
+            for f in [GENESIS_BLOB_FILENAME, WAYPOINT_FILENAME] {
+                fs::copy(genesis_dir.join(f), home.join("genesis").join(f))?;
+            }
+        }
+
+        let info = TestnetInfo {
+            chain_id: self.chain.id(),
+            waypoint: fs::read_to_string(genesis_dir.join(WAYPOINT_FILENAME))?
+                .trim()
+                .to_string(),
+            genesis_blob: genesis_dir.join(GENESIS_BLOB_FILENAME),
+            nodes,
+        };
+        fs::write(
+            self.data_path.join(TESTNET_FILE),
+            serde_json::to_string_pretty(&info)?,
+        )?;
+        Ok(info)
+    }
+}
+
+/// the validator folders of a registrations dir, sorted by name
+fn registration_dirs(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let mut names = vec![];
+    for entry in fs::read_dir(dir)? {
+        let p = entry?.path();
+        if p.join(OPERATOR_FILE).exists() && p.join(OWNER_FILE).exists() {
+            if let Some(name) = p.file_name() {
+                names.push(name.to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn node_info(
+    name: &str,
+    cfg: &ValidatorConfiguration,
+    api_port: Option<u16>,
+    home: Option<PathBuf>,
+) -> anyhow::Result<TestnetNode> {
+    Ok(TestnetNode {
+        name: name.to_string(),
+        account: cfg.owner_account_address.into(),
+        consensus_public_key: cfg
+            .consensus_public_key
+            .as_ref()
+            .map(|k| k.to_encoded_string())
+            .transpose()?,
+        validator_network_public_key: cfg
+            .validator_network_public_key
+            .as_ref()
+            .map(|k| k.to_encoded_string())
+            .transpose()?,
+        full_node_network_public_key: cfg
+            .full_node_network_public_key
+            .as_ref()
+            .map(|k| k.to_encoded_string())
+            .transpose()?,
+        validator_host: cfg.validator_host.as_ref().map(|h| h.to_string()),
+        full_node_host: cfg.full_node_host.as_ref().map(|h| h.to_string()),
+        api_port,
+        home,
+    })
+}
+
+/// Give a node its own ports, so many nodes can run on one machine, and
+/// drop the mainnet seed peers
+fn set_node_ports(node_yaml: &Path, validator_port: u16, api_port: u16) -> anyhow::Result<()> {
+    let s = fs::read_to_string(node_yaml)?;
+    let mut v: Value = serde_yaml::from_str(&s)?;
+
+    v["validator_network"]["listen_address"] =
+        Value::from(format!("/ip4/0.0.0.0/tcp/{}", validator_port));
+    if let Some(nets) = v["full_node_networks"].as_sequence_mut() {
+        for (i, net) in nets.iter_mut().enumerate() {
+            net["listen_address"] = Value::from(format!(
+                "/ip4/0.0.0.0/tcp/{}",
+                validator_port + 1 + i as u16
+            ));
+            if let Some(m) = net.as_mapping_mut() {
+                m.remove(&Value::from("seeds"));
+                m.remove(&Value::from("seed_addrs"));
+            }
+        }
+    }
+    v["api"]["address"] = Value::from(format!("127.0.0.1:{}", api_port));
+
+    write_to_user_only_file(
+        node_yaml,
+        NODE_YAML_FILE,
+        serde_yaml::to_string(&v)?.as_bytes(),
+    )
+}
+
+#[test]
+fn node_ports() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let p = dir.path().join(NODE_YAML_FILE);
+    fs::write(
+        &p,
+        "
+validator_network:
+  discovery_method: 'onchain'
+full_node_networks:
+- network_id:
+    private: 'vfn'
+  listen_address: '/ip4/0.0.0.0/tcp/6181'
+- network_id: 'public'
+  listen_address: '/ip4/0.0.0.0/tcp/6182'
+  seeds: {}
+api:
+  enabled: true
+  address: '127.0.0.1:8080'
+",
+    )
+    .unwrap();
+
+    set_node_ports(&p, 6210, 8083).unwrap();
+    let v: Value = serde_yaml::from_str(&fs::read_to_string(&p).unwrap()).unwrap();
+    assert_eq!(
+        v["validator_network"]["listen_address"].as_str(),
+        Some("/ip4/0.0.0.0/tcp/6210")
+    );
+    assert_eq!(
+        v["full_node_networks"][1]["listen_address"].as_str(),
+        Some("/ip4/0.0.0.0/tcp/6212")
+    );
+    assert!(v["full_node_networks"][1].get("seeds").is_none());
+    assert_eq!(v["api"]["address"].as_str(), Some("127.0.0.1:8083"));
+}
+
+#[cfg(test)]
+fn check_testnet_file(data_path: &Path, count: usize) -> TestnetInfo {
+    use diem_types::waypoint::Waypoint;
+
+    let info: TestnetInfo =
+        serde_json::from_str(&fs::read_to_string(data_path.join(TESTNET_FILE)).unwrap()).unwrap();
+    assert_eq!(info.chain_id, NamedChain::TESTING.id());
+    assert_eq!(info.nodes.len(), count);
+    assert!(info.genesis_blob.exists());
+    let waypoint = Waypoint::from_str(&info.waypoint).unwrap();
+    assert_eq!(waypoint.version(), 0);
+
+    let mut accounts: Vec<AccountAddress> = info.nodes.iter().map(|n| n.account).collect();
+    accounts.sort();
+    accounts.dedup();
+    assert_eq!(accounts.len(), count);
+    info
+}
+
+#[tokio::test]
+async fn testnet_with_generated_keys() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let count = 6;
+    CustomTestnet {
+        count,
+        registrations: None,
+        hosts: vec![],
+        chain: NamedChain::TESTING,
+        data_path: dir.path().to_owned(),
+        legacy_data_path: None,
+    }
+    .setup()
+    .await
+    .unwrap();
+
+    let info = check_testnet_file(dir.path(), count);
+    for (idx, n) in info.nodes.iter().enumerate() {
+        let port = DEFAULT_VALIDATOR_PORT + idx as u16 * LOCAL_PORT_STEP;
+        assert_eq!(n.name, format!("val_{}", idx));
+        assert_eq!(n.validator_host, Some(format!("127.0.0.1:{}", port)));
+        assert_eq!(
+            n.full_node_host,
+            Some(format!("127.0.0.1:{}", port + FULLNODE_PORT_OFFSET))
+        );
+        assert_eq!(n.api_port, Some(DEFAULT_API_PORT + idx as u16));
+        assert!(n.consensus_public_key.is_some());
+
+        // each node has its own ports and a copy of the genesis
+        let home = n.home.as_ref().unwrap();
+        let v: Value =
+            serde_yaml::from_str(&fs::read_to_string(home.join(NODE_YAML_FILE)).unwrap()).unwrap();
+        assert_eq!(
+            v["validator_network"]["listen_address"].as_str(),
+            Some(format!("/ip4/0.0.0.0/tcp/{}", port).as_str())
+        );
+        assert_eq!(
+            fs::read_to_string(home.join("genesis").join(WAYPOINT_FILENAME))
+                .unwrap()
+                .trim(),
+            info.waypoint
+        );
+    }
+}
+
+#[tokio::test]
+async fn testnet_from_registrations() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let count = 5;
+    // the generated nodes leave their operator.yaml and owner.yaml in their home
+    let generated = CustomTestnet {
+        count,
+        registrations: None,
+        hosts: vec![],
+        chain: NamedChain::TESTING,
+        data_path: dir.path().join("generated"),
+        legacy_data_path: None,
+    }
+    .setup()
+    .await
+    .unwrap();
+
+    let data_path = dir.path().join("registered");
+    let host = HostAndPort::from_str("10.0.0.1:6180").unwrap();
+    CustomTestnet {
+        count: 0,
+        registrations: Some(dir.path().join("generated").join("testnet")),
+        hosts: vec![host.clone()],
+        chain: NamedChain::TESTING,
+        data_path: data_path.clone(),
+        legacy_data_path: None,
+    }
+    .setup()
+    .await
+    .unwrap();
+
+    let info = check_testnet_file(&data_path, count);
+    for (n, g) in info.nodes.iter().zip(&generated.nodes) {
+        assert_eq!(n.name, g.name);
+        assert_eq!(n.account, g.account);
+        assert_eq!(n.consensus_public_key, g.consensus_public_key);
+        assert!(n.home.is_none());
+        assert!(n.api_port.is_none());
+    }
+    // the hosts given override the ones in the registration
+    assert_eq!(info.nodes[0].validator_host, Some(host.to_string()));
+    assert_eq!(
+        info.nodes[1].validator_host,
+        generated.nodes[1].validator_host
+    );
+}
@@ -1,6 +1,11 @@
 //! build the genesis file
 
-use crate::{compare, supply, vm, wizard::DEFAULT_GIT_BRANCH};
+use crate::{
+    compare,
+    genesis_manifest::{content_hash, GenesisManifest, RecordingRepo, MANIFEST_FILE},
+    genesis_repo::GenesisRepo,
+    supply, vm,
+};
 use crate::{
     genesis::make_recovery_genesis_from_vec_legacy_recovery,
     genesis_reader::bootstrap_db_reader_from_gen_tx,
@@ -19,7 +24,6 @@ use diem_genesis::{
     },
     GenesisInfo,
 };
-use diem_github_client::Client;
 use diem_types::{
     account_address::{AccountAddress, AccountAddressWithChecks},
     on_chain_config::{OnChainConsensusConfig, OnChainExecutionConfig},
@@ -65,12 +69,10 @@ struct LotusSimpleLayout {
     pub users: Vec<String>,
 }
 
-// TODO: too many arguments, minor infraction
-#[allow(clippy::too_many_arguments)]
+/// Build the genesis blob and waypoint from the registrations in the genesis
+/// repo, or for a testnet from the given validator configs
 pub fn build(
-    github_owner: String,
-    github_repository: String,
-    github_token: String,
+    genesis_repo: Option<&dyn GenesisRepo>,
     home_path: PathBuf,
     use_local_framework: bool,
     legacy_recovery: &mut [LegacyRecoveryV6],
@@ -91,6 +93,14 @@ pub fn build(
 
     let genesis_config = vm::lotus_genesis_default(chain_name);
 
+    // remember what genesis is built from, for the manifest
+    let recorder = genesis_repo.map(RecordingRepo::new);
+    let recovery_hash = if legacy_recovery.is_empty() {
+        None
+    } else {
+        Some(content_hash(&serde_json::to_vec(&legacy_recovery)?))
+    };
+
     let mut gen_info = if let Some(vals) = testnet_vals {
         let dummy_root = Ed25519PublicKey::from_encoded_string(
             "0x0000000000000000000000000000000000000000000000000000000000000000",
@@ -104,15 +114,10 @@ pub fn build(
             lotus_framework::head_release_bundle(),
             &silly_config(&genesis_config),
         )?
+    } else if let Some(repo) = &recorder {
+        fetch_genesis_info(repo, use_local_framework, &genesis_config, &chain_name)?
     } else {
-        fetch_genesis_info(
-            github_owner,
-            github_repository,
-            github_token,
-            use_local_framework,
-            &genesis_config,
-            &chain_name,
-        )?
+        bail!("a genesis repo is needed to build genesis, unless it is a testnet")
     };
     println!("building genesis block");
     let tx = make_recovery_genesis_from_vec_legacy_recovery(
@@ -133,17 +138,30 @@ pub fn build(
         .with_message("saving files");
     pb.enable_steady_tick(Duration::from_millis(100));
 
+    let genesis_bytes = bcs::to_bytes(gen_info.get_genesis())?;
     write_to_user_only_file(
         genesis_file.as_path(),
         GENESIS_FILE,
-        bcs::to_bytes(gen_info.get_genesis())?.as_slice(),
+        genesis_bytes.as_slice(),
     )?;
 
+    let waypoint = gen_info.generate_waypoint()?;
     write_to_user_only_file(
         waypoint_file.as_path(),
         WAYPOINT_FILE,
-        gen_info.generate_waypoint()?.to_string().as_bytes(),
+        waypoint.to_string().as_bytes(),
     )?;
+
+    let manifest = GenesisManifest {
+        chain_id: chain_name.id(),
+        genesis_config: (&genesis_config).into(),
+        framework: content_hash(&bcs::to_bytes(&gen_info.framework)?),
+        repo_files: recorder.map(|r| r.files()).unwrap_or_default(),
+        recovery: recovery_hash,
+        genesis_blob: content_hash(&genesis_bytes),
+        waypoint: waypoint.to_string(),
+    };
+    manifest.save(&output_dir)?;
     pb.finish_and_clear();
     OLProgress::complete(&format!(
         "genesis file saved to {}",
@@ -167,7 +185,11 @@ pub fn build(
     }
 
     OLProgress::complete("LFG, ready for genesis");
-    Ok(vec![genesis_file, waypoint_file])
+    Ok(vec![
+        genesis_file,
+        waypoint_file,
+        output_dir.join(MANIFEST_FILE),
+    ])
 }
 
 /// there are two structs called GenesisConfiguration in Vendor code, sigh.
@@ -192,31 +214,20 @@ fn silly_config(cfg: &VmGenesisGenesisConfiguration) -> GenesisConfiguration {
     }
 }
 
-/// Retrieves all information for mainnet genesis from the Git repository
+/// Retrieves all information for mainnet genesis from the genesis repository
 pub fn fetch_genesis_info(
-    github_owner: String,
-    github_repository: String,
-    github_token: String,
+    repo: &dyn GenesisRepo,
     use_local_framework: bool,
     genesis_config: &VmGenesisGenesisConfiguration,
     chain_id: &NamedChain,
 ) -> Result<GenesisInfo> {
-    // let client = git_options.get_client()?;
-    let client = Client::new(
-        github_owner, // doesn't matter
-        github_repository,
-        DEFAULT_GIT_BRANCH.to_string(),
-        github_token,
-    );
-
-    // let layout: Layout = client.get(Path::new(LAYOUT_FILE))?;
-    let l_file = client.get_file(&Path::new(LAYOUT_FILE).display().to_string())?;
-    let layout: LotusSimpleLayout = from_yaml(&String::from_utf8(base64::decode(l_file)?)?)?;
-    OLProgress::complete("fetched layout file");
+    let l_file = repo.get_file(LAYOUT_FILE)?;
+    let layout: LotusSimpleLayout = from_yaml(&String::from_utf8(l_file)?)?;
+    OLProgress::complete(&format!("fetched layout file from {}", repo.location()));
 
     let pb = OLProgress::spin_steady(500, "fetching validator registrations".to_string());
 
-    let validators = get_validator_configs(&client, &layout, false)?;
+    let validators = get_validator_configs(repo, &layout, false)?;
     OLProgress::complete("fetched validator configs");
     pb.finish_and_clear();
 
@@ -224,8 +235,8 @@ pub fn fetch_genesis_info(
         // use the local head release
         release::ReleaseTarget::Head.load_bundle()?
     } else {
-        // get from github
-        let bytes = base64::decode(client.get_file(FRAMEWORK_NAME)?)?;
+        // get from the genesis repo
+        let bytes = repo.get_file(FRAMEWORK_NAME)?;
         bcs::from_bytes::<ReleaseBundle>(&bytes)?
     };
 
@@ -246,14 +257,14 @@ pub fn fetch_genesis_info(
 
 /// Retrieves validator configurations
 fn get_validator_configs(
-    client: &Client,
+    repo: &dyn GenesisRepo,
     layout: &LotusSimpleLayout,
     is_mainnet: bool,
 ) -> Result<Vec<ValidatorConfiguration>> {
     let mut validators = Vec::new();
     let mut errors = Vec::new();
     for user in &layout.users {
-        match get_config(client, user, is_mainnet) {
+        match get_config(repo, user, is_mainnet) {
             Ok(validator) => {
                 validators.push(validator);
             }
@@ -270,16 +281,20 @@ fn get_validator_configs(
     }
 }
 
+/// Read the registration files of a validator in a genesis repo.
 /// Do proper parsing so more information is known about failures
-fn get_config(client: &Client, user: &str, _is_mainnet: bool) -> Result<ValidatorConfiguration> {
+pub fn get_config(
+    repo: &dyn GenesisRepo,
+    user: &str,
+    _is_mainnet: bool,
+) -> Result<ValidatorConfiguration> {
     // Load a user's configuration files
     let dir = PathBuf::from(user);
     let owner_file = dir.join(OWNER_FILE);
     let owner_file = owner_file.as_path();
 
-    let file = client.get_file(&Path::new(owner_file).display().to_string())?;
-    let owner_config: StringOwnerConfiguration =
-        from_yaml(&String::from_utf8(base64::decode(file)?)?)?;
+    let file = repo.get_file(&owner_file.display().to_string())?;
+    let owner_config: StringOwnerConfiguration = from_yaml(&String::from_utf8(file)?)?;
 
     // Check and convert fields in owner file
     let owner_account_address: AccountAddress = parse_required_option(
@@ -352,9 +367,8 @@ fn get_config(client: &Client, user: &str, _is_mainnet: bool) -> Result<Validato
     let operator_file = dir.join(OPERATOR_FILE);
     let operator_file = operator_file.as_path();
 
-    let file = client.get_file(&Path::new(operator_file).display().to_string())?;
-    let operator_config: StringOperatorConfiguration =
-        from_yaml(&String::from_utf8(base64::decode(file)?)?)?;
+    let file = repo.get_file(&operator_file.display().to_string())?;
+    let operator_config: StringOperatorConfiguration = from_yaml(&String::from_utf8(file)?)?;
 
     // Check and convert fields in operator file
     let operator_account_address_from_file: AccountAddress = parse_required_option(
@@ -441,7 +455,15 @@ pub fn testnet_validator_config(
     host: &HostAndPort,
     keep_legacy_addr: bool,
 ) -> anyhow::Result<ValidatorConfiguration> {
-    let mnem = persona.get_persona_mnem();
+    validator_config_from_mnem(persona.get_persona_mnem(), host, keep_legacy_addr)
+}
+
+/// create a validator config from any mnemonic
+pub fn validator_config_from_mnem(
+    mnem: String,
+    host: &HostAndPort,
+    keep_legacy_addr: bool,
+) -> anyhow::Result<ValidatorConfiguration> {
     let mut key_chain = get_keys_from_mnem(mnem)?;
 
     if keep_legacy_addr {
@@ -553,14 +575,13 @@ fn parse_optional_option<F: Fn(&str) -> Result<T, E>, T, E: std::fmt::Display>(
 #[test]
 #[ignore] //dev helper
 fn test_github_info() {
-    use crate::vm::lotus_genesis_default;
+    use crate::{genesis_repo::GithubRepo, vm::lotus_genesis_default};
     let gh_token_path = lotus_types::global_config_dir().join("github_token.txt");
     let token = std::fs::read_to_string(gh_token_path).unwrap();
+    let repo = GithubRepo::new("0o-de-lally".to_string(), "a-genesis".to_string(), token);
 
     let _genesis_info = fetch_genesis_info(
-        "0o-de-lally".to_string(),
-        "a-genesis".to_string(),
-        token,
+        &repo,
         true,
         &lotus_genesis_default(NamedChain::TESTING),
         &NamedChain::TESTING,
@@ -571,18 +592,10 @@ fn test_github_info() {
 #[test]
 #[ignore] //dev helper
 fn test_build() {
+    use crate::genesis_repo::GithubRepo;
     let home = lotus_types::global_config_dir();
     let token = std::fs::read_to_string(home.join("github_token.txt")).unwrap();
+    let repo = GithubRepo::new("0o-de-lally".to_string(), "a-genesis".to_string(), token);
 
-    build(
-        "0o-de-lally".to_string(),
-        "a-genesis".to_string(),
-        token,
-        home,
-        true,
-        &mut [],
-        NamedChain::TESTING,
-        None,
-    )
-    .unwrap();
+    build(Some(&repo), home, true, &mut [], NamedChain::TESTING, None).unwrap();
 }
@@ -0,0 +1,182 @@
+//! A manifest of everything a genesis was built from, so that independent
+//! validators can prove they built the same genesis.
+
+use crate::genesis_repo::GenesisRepo;
+use anyhow::{Context, Result};
+use diem_crypto::HashValue;
+use diem_vm_genesis::GenesisConfiguration as VmGenesisGenesisConfiguration;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeMap, fs, path::Path};
+
+pub const MANIFEST_FILE: &str = "genesis_manifest.json";
+
+/// hex sha3-256 of some bytes
+pub fn content_hash(bytes: &[u8]) -> String {
+    HashValue::sha3_256_of(bytes).to_hex()
+}
+
+/// The genesis parameters which change the genesis transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisConfigValues {
+    pub allow_new_validators: bool,
+    pub epoch_duration_secs: u64,
+    pub is_test: bool,
+    pub min_stake: u64,
+    pub min_voting_threshold: u128,
+    pub max_stake: u64,
+    pub recurring_lockup_duration_secs: u64,
+    pub required_proposer_stake: u64,
+    pub rewards_apy_percentage: u64,
+    pub voting_duration_secs: u64,
+    pub voting_power_increase_limit: u64,
+}
+
+impl From<&VmGenesisGenesisConfiguration> for GenesisConfigValues {
+    fn from(cfg: &VmGenesisGenesisConfiguration) -> Self {
+        Self {
+            allow_new_validators: cfg.allow_new_validators,
+            epoch_duration_secs: cfg.epoch_duration_secs,
+            is_test: cfg.is_test,
+            min_stake: cfg.min_stake,
+            min_voting_threshold: cfg.min_voting_threshold,
+            max_stake: cfg.max_stake,
+            recurring_lockup_duration_secs: cfg.recurring_lockup_duration_secs,
+            required_proposer_stake: cfg.required_proposer_stake,
+            rewards_apy_percentage: cfg.rewards_apy_percentage,
+            voting_duration_secs: cfg.voting_duration_secs,
+            voting_power_increase_limit: cfg.voting_power_increase_limit,
+        }
+    }
+}
+
+/// The hashes of the inputs of a genesis, and of the result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisManifest {
+    pub chain_id: u8,
+    pub genesis_config: GenesisConfigValues,
+    /// hash of the framework release bundle
+    pub framework: String,
+    /// hash of the layout file and every validator registration file, by path in the genesis repo
+    pub repo_files: BTreeMap<String, String>,
+    /// hash of the recovery file, after the dropped accounts are removed
+    pub recovery: Option<String>,
+    pub genesis_blob: String,
+    pub waypoint: String,
+}
+
+impl GenesisManifest {
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let p = dir.join(MANIFEST_FILE);
+        fs::write(&p, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("could not write {}", p.display()))
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        serde_json::from_str(&s).with_context(|| format!("could not parse {}", path.display()))
+    }
+
+    /// every difference with another manifest, empty if they are the same
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let mut d = vec![];
+        if self.chain_id != other.chain_id {
+            d.push(format!("chain id {} vs {}", self.chain_id, other.chain_id));
+        }
+        if self.genesis_config != other.genesis_config {
+            d.push(format!(
+                "genesis config {:?} vs {:?}",
+                self.genesis_config, other.genesis_config
+            ));
+        }
+        if self.framework != other.framework {
+            d.push("framework release bundle".to_string());
+        }
+        let paths: std::collections::BTreeSet<&String> = self
+            .repo_files
+            .keys()
+            .chain(other.repo_files.keys())
+            .collect();
+        for p in paths {
+            match (self.repo_files.get(p), other.repo_files.get(p)) {
+                (Some(a), Some(b)) if a == b => {}
+                (Some(_), Some(_)) => d.push(format!("{} has different contents", p)),
+                (Some(_), None) => d.push(format!("{} is missing", p)),
+                (None, _) => d.push(format!("{} was not in the manifest", p)),
+            }
+        }
+        if self.recovery != other.recovery {
+            d.push("recovery file".to_string());
+        }
+        if self.genesis_blob != other.genesis_blob {
+            d.push("genesis blob".to_string());
+        }
+        if self.waypoint != other.waypoint {
+            d.push(format!("waypoint {} vs {}", self.waypoint, other.waypoint));
+        }
+        d
+    }
+}
+
+/// A genesis repo which remembers the hash of every file read from it
+pub struct RecordingRepo<'a> {
+    inner: &'a dyn GenesisRepo,
+    files: RefCell<BTreeMap<String, String>>,
+}
+
+impl<'a> RecordingRepo<'a> {
+    pub fn new(inner: &'a dyn GenesisRepo) -> Self {
+        Self {
+            inner,
+            files: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// hashes of the files read so far, by path
+    pub fn files(&self) -> BTreeMap<String, String> {
+        self.files.borrow().clone()
+    }
+}
+
+impl GenesisRepo for RecordingRepo<'_> {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        let bytes = self.inner.get_file(path)?;
+        self.files
+            .borrow_mut()
+            .insert(path.to_string(), content_hash(&bytes));
+        Ok(bytes)
+    }
+
+    fn put_file(&self, path: &str, contents: &[u8]) -> Result<()> {
+        self.inner.put_file(path, contents)
+    }
+
+    fn location(&self) -> String {
+        self.inner.location()
+    }
+}
+
+#[test]
+fn manifest_diff() {
+    use crate::vm::lotus_genesis_default;
+    use diem_types::chain_id::NamedChain;
+
+    let a = GenesisManifest {
+        chain_id: 2,
+        genesis_config: (&lotus_genesis_default(NamedChain::TESTNET)).into(),
+        framework: content_hash(b"framework"),
+        repo_files: [("layout.yaml".to_string(), content_hash(b"layout"))].into(),
+        recovery: None,
+        genesis_blob: content_hash(b"blob"),
+        waypoint: "0:abc".to_string(),
+    };
+    assert!(a.diff(&a.clone()).is_empty());
+
+    let mut b = a.clone();
+    b.repo_files
+        .insert("0xabc/owner.yaml".to_string(), content_hash(b"owner"));
+    b.waypoint = "0:def".to_string();
+    let d = a.diff(&b);
+    assert_eq!(d.len(), 2);
+    assert!(d[0].contains("0xabc/owner.yaml"));
+}
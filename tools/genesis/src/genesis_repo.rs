@@ -0,0 +1,126 @@
+//! Where the genesis registrations are kept.
+//! The repo has a layout.yaml listing the validators, a folder per validator
+//! with its operator.yaml and owner.yaml, and optionally a framework.mrb.
+//! It can be a GitHub repo, or a local directory such as a git checkout, so
+//! that private testnets can do genesis offline and without GitHub accounts.
+
+use anyhow::{bail, Context, Result};
+use diem_github_client::Client;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::wizard::DEFAULT_GIT_BRANCH;
+
+/// Read and write files of a genesis repo, by their path in the repo
+pub trait GenesisRepo {
+    /// the contents of a file
+    fn get_file(&self, path: &str) -> Result<Vec<u8>>;
+    /// create or replace a file
+    fn put_file(&self, path: &str, contents: &[u8]) -> Result<()>;
+    /// where the repo is, for messages to the user
+    fn location(&self) -> String;
+}
+
+/// A genesis repo on GitHub
+pub struct GithubRepo {
+    owner: String,
+    repository: String,
+    client: Client,
+}
+
+impl GithubRepo {
+    pub fn new(owner: String, repository: String, token: String) -> Self {
+        let client = Client::new(
+            owner.clone(),
+            repository.clone(),
+            DEFAULT_GIT_BRANCH.to_string(),
+            token,
+        );
+        Self {
+            owner,
+            repository,
+            client,
+        }
+    }
+}
+
+impl GenesisRepo for GithubRepo {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        let encoded = self
+            .client
+            .get_file(path)
+            .with_context(|| format!("could not get {} from {}", path, self.location()))?;
+        Ok(base64::decode(encoded)?)
+    }
+
+    fn put_file(&self, path: &str, contents: &[u8]) -> Result<()> {
+        self.client
+            .put(path, &base64::encode(contents))
+            .with_context(|| format!("could not write {} to {}", path, self.location()))?;
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        format!("github.com/{}/{}", self.owner, self.repository)
+    }
+}
+
+/// A genesis repo in a local directory, e.g. a plain git checkout
+pub struct LocalRepo {
+    root: PathBuf,
+}
+
+impl LocalRepo {
+    pub fn new(root: PathBuf) -> Result<Self> {
+        if !root.is_dir() {
+            bail!("genesis repo {} is not a directory", root.display());
+        }
+        Ok(Self { root })
+    }
+
+    /// only paths inside the repo can be read or written
+    fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let rel = Path::new(path);
+        if !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+            bail!("{} is not a relative path inside the genesis repo", path);
+        }
+        Ok(self.root.join(rel))
+    }
+}
+
+impl GenesisRepo for LocalRepo {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        let p = self.resolve(path)?;
+        fs::read(&p).with_context(|| format!("could not read {}", p.display()))
+    }
+
+    fn put_file(&self, path: &str, contents: &[u8]) -> Result<()> {
+        let p = self.resolve(path)?;
+        if let Some(dir) = p.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&p, contents).with_context(|| format!("could not write {}", p.display()))
+    }
+
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+}
+
+#[test]
+fn local_repo_files() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let repo = LocalRepo::new(dir.path().to_owned()).unwrap();
+
+    repo.put_file("0xabc/operator.yaml", b"operator").unwrap();
+    assert_eq!(repo.get_file("0xabc/operator.yaml").unwrap(), b"operator");
+    assert!(dir.path().join("0xabc").join("operator.yaml").exists());
+
+    assert!(repo.get_file("layout.yaml").is_err());
+    assert!(repo.put_file("../outside.yaml", b"no").is_err());
+    assert!(repo.put_file("/tmp/outside.yaml", b"no").is_err());
+    assert!(LocalRepo::new(dir.path().join("missing")).is_err());
+}
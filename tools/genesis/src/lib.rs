@@ -1,16 +1,23 @@
 //! genesis
+pub mod audit;
 pub mod cli;
 pub mod compare;
+pub mod custom_testnet;
 
 pub mod genesis;
 pub mod genesis_builder;
 pub mod genesis_functions;
+pub mod genesis_manifest;
 pub mod genesis_reader;
 pub mod genesis_registration;
+pub mod genesis_repo;
 pub mod github_extensions;
+pub mod migration_rules;
 pub mod parse_json;
 pub mod process_comm_wallet;
+pub mod recovery_from_snapshot;
 pub mod supply;
 pub mod testnet_setup;
+pub mod verify_genesis;
 pub mod vm;
 pub mod wizard;
@@ -0,0 +1,368 @@
+//! Declarative edits of the recovery file before a migration genesis.
+//! A rules file lists the edits, instead of changing code for every fork:
+//!
+//! ```yaml
+//! rules:
+//!   - drop: { account: "0x1234" }
+//!   - merge: { from: "0x1234", into: "0x5678" }
+//!   - reassign_balance: { from: "0x1234", to: "0x5678", amount: 1000 }
+//!   - set_slow_wallet: { account: "0x1234", unlocked: 500 }
+//!   - donor_voice_to_slow: { account: "0x1234" }
+//!   - tombstone: { account: "0x1234" }
+//! ```
+//!
+//! Every rule applied is recorded in a report, with the balance and unlocked
+//! amount the account should have in genesis.
+
+use anyhow::{bail, Context, Result};
+use lotus_types::{
+    exports::{AccountAddress, AuthenticationKey},
+    legacy_types::{
+        legacy_recovery_v6::{AccountRole, LegacyRecoveryV6},
+        wallet::SlowWalletResource,
+    },
+    move_resource::cumulative_deposits::LegacyBalanceResource,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+pub const RULES_REPORT_FILE: &str = "migration_rules_report.json";
+
+/// auth key of accounts which nobody can sign for
+const TOMBSTONE_AUTH_KEY: [u8; 32] = [9u8; 32];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// the account is not migrated
+    Drop { account: AccountAddress },
+    /// move the balance of `from` to `into`, and drop `from`
+    Merge {
+        from: AccountAddress,
+        into: AccountAddress,
+    },
+    /// move an amount of coins, or all of them, between two accounts
+    ReassignBalance {
+        from: AccountAddress,
+        to: AccountAddress,
+        amount: Option<u64>,
+    },
+    /// make the account a slow wallet with these amounts
+    SetSlowWallet {
+        account: AccountAddress,
+        unlocked: u64,
+        #[serde(default)]
+        transferred: u64,
+    },
+    /// a donor voice wallet becomes a slow wallet with nothing unlocked
+    DonorVoiceToSlow { account: AccountAddress },
+    /// the account keeps its coins, but nobody can sign for it
+    Tombstone { account: AccountAddress },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRules {
+    pub rules: Vec<Rule>,
+}
+
+impl MigrationRules {
+    pub fn read(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("could not read rules file {}", path.display()))?;
+        serde_yaml::from_str(&s)
+            .with_context(|| format!("could not parse rules file {}", path.display()))
+    }
+}
+
+/// The state an account should have in genesis after a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedChange {
+    /// index of the rule in the rules file
+    pub rule_index: usize,
+    pub rule: String,
+    pub account: AccountAddress,
+    pub dropped: bool,
+    pub balance: u64,
+    pub slow_unlocked: Option<u64>,
+}
+
+/// Every change the rules made to the recovery file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeReport {
+    pub changes: Vec<AppliedChange>,
+}
+
+impl ChangeReport {
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let p = dir.join(RULES_REPORT_FILE);
+        fs::write(&p, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("could not write {}", p.display()))
+    }
+
+    /// the last expected state of each account, since later rules override earlier ones
+    pub fn final_states(&self) -> Vec<&AppliedChange> {
+        let mut last: Vec<&AppliedChange> = vec![];
+        for c in &self.changes {
+            last.retain(|l| l.account != c.account);
+            last.push(c);
+        }
+        last
+    }
+}
+
+/// Apply the rules in order. Fails on the first rule which cannot apply, so
+/// that a typo in an address doesn't silently skip an edit.
+pub fn apply_rules(r: &mut [LegacyRecoveryV6], rules: &MigrationRules) -> Result<ChangeReport> {
+    let mut report = ChangeReport::default();
+    for (i, rule) in rules.rules.iter().enumerate() {
+        let touched = apply_rule(r, rule).with_context(|| format!("rule {}: {:?}", i, rule))?;
+        for account in touched {
+            let e = find(r, &account)?;
+            report.changes.push(AppliedChange {
+                rule_index: i,
+                rule: format!("{:?}", rule),
+                account,
+                dropped: e.role == AccountRole::Drop,
+                balance: balance(e),
+                slow_unlocked: e.slow_wallet.as_ref().map(|s| s.unlocked),
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// apply one rule, and return the accounts it changed
+fn apply_rule(r: &mut [LegacyRecoveryV6], rule: &Rule) -> Result<Vec<AccountAddress>> {
+    match rule {
+        Rule::Drop { account } => {
+            drop_account(find_mut(r, account)?);
+            Ok(vec![*account])
+        }
+        Rule::Merge { from, into } => {
+            if from == into {
+                bail!("cannot merge {} into itself", from);
+            }
+            let (from_balance, from_unlocked) = {
+                let f = find(r, from)?;
+                let b = balance(f);
+                // coins of a normal account are all unlocked
+                (b, f.slow_wallet.as_ref().map(|s| s.unlocked).unwrap_or(b))
+            };
+            let i = find_mut(r, into)?;
+            let merged = balance(i) + from_balance;
+            set_balance(i, merged);
+            if let Some(s) = i.slow_wallet.as_mut() {
+                s.unlocked += from_unlocked;
+            }
+            drop_account(find_mut(r, from)?);
+            Ok(vec![*from, *into])
+        }
+        Rule::ReassignBalance { from, to, amount } => {
+            if from == to {
+                bail!("cannot reassign the balance of {} to itself", from);
+            }
+            let f = find_mut(r, from)?;
+            let available = balance(f);
+            let amount = amount.unwrap_or(available);
+            if amount > available {
+                bail!(
+                    "{} has a balance of {}, cannot move {}",
+                    from,
+                    available,
+                    amount
+                );
+            }
+            set_balance(f, available - amount);
+            if let Some(s) = f.slow_wallet.as_mut() {
+                s.unlocked = s.unlocked.min(available - amount);
+            }
+            // like a transfer, the coins arrive locked in a slow wallet
+            let t = find_mut(r, to)?;
+            let received = balance(t) + amount;
+            set_balance(t, received);
+            Ok(vec![*from, *to])
+        }
+        Rule::SetSlowWallet {
+            account,
+            unlocked,
+            transferred,
+        } => {
+            let e = find_mut(r, account)?;
+            if *unlocked > balance(e) {
+                bail!(
+                    "{} has a balance of {}, it cannot have {} unlocked",
+                    account,
+                    balance(e),
+                    unlocked
+                );
+            }
+            e.slow_wallet = Some(SlowWalletResource {
+                unlocked: *unlocked,
+                transferred: *transferred,
+            });
+            add_to_slow_list(r, account);
+            Ok(vec![*account])
+        }
+        Rule::DonorVoiceToSlow { account } => {
+            let e = find_mut(r, account)?;
+            if e.cumulative_deposits.is_none() && e.tx_schedule.is_none() {
+                bail!("{} is not a donor voice wallet", account);
+            }
+            e.cumulative_deposits = None;
+            e.tx_schedule = None;
+            e.slow_wallet = Some(SlowWalletResource {
+                unlocked: 0,
+                transferred: 0,
+            });
+            // and out of the system lists of donor voice and community wallets
+            for s in r.iter_mut() {
+                if let Some(reg) = s.donor_voice_registry.as_mut() {
+                    reg.list.retain(|a| a != account);
+                }
+                if let Some(cw) = s.comm_wallet.as_mut() {
+                    cw.list.retain(|a| a != account);
+                }
+            }
+            add_to_slow_list(r, account);
+            Ok(vec![*account])
+        }
+        Rule::Tombstone { account } => {
+            find_mut(r, account)?.auth_key = Some(AuthenticationKey::new(TOMBSTONE_AUTH_KEY));
+            Ok(vec![*account])
+        }
+    }
+}
+
+fn find<'a>(r: &'a [LegacyRecoveryV6], account: &AccountAddress) -> Result<&'a LegacyRecoveryV6> {
+    r.iter()
+        .find(|e| e.account.as_ref() == Some(account))
+        .with_context(|| format!("{} is not in the recovery file", account))
+}
+
+fn find_mut<'a>(
+    r: &'a mut [LegacyRecoveryV6],
+    account: &AccountAddress,
+) -> Result<&'a mut LegacyRecoveryV6> {
+    r.iter_mut()
+        .find(|e| e.account.as_ref() == Some(account))
+        .with_context(|| format!("{} is not in the recovery file", account))
+}
+
+fn balance(e: &LegacyRecoveryV6) -> u64 {
+    e.balance.as_ref().map(|b| b.coin).unwrap_or(0)
+}
+
+fn set_balance(e: &mut LegacyRecoveryV6, coin: u64) {
+    e.balance = Some(LegacyBalanceResource { coin });
+}
+
+/// same as an account in the drop list
+fn drop_account(e: &mut LegacyRecoveryV6) {
+    *e = LegacyRecoveryV6 {
+        account: e.account,
+        auth_key: Some(AuthenticationKey::new(TOMBSTONE_AUTH_KEY)),
+        role: AccountRole::Drop,
+        ..Default::default()
+    };
+}
+
+fn add_to_slow_list(r: &mut [LegacyRecoveryV6], account: &AccountAddress) {
+    for s in r.iter_mut() {
+        if let Some(l) = s.slow_wallet_list.as_mut() {
+            if !l.list.contains(account) {
+                l.list.push(*account);
+            }
+        }
+    }
+}
+
+#[test]
+fn apply_migration_rules() {
+    let a = AccountAddress::from_hex_literal("0xa").unwrap();
+    let b = AccountAddress::from_hex_literal("0xb").unwrap();
+    let c = AccountAddress::from_hex_literal("0xc").unwrap();
+    let account = |addr: AccountAddress, coin: u64, unlocked: Option<u64>| LegacyRecoveryV6 {
+        account: Some(addr),
+        balance: Some(LegacyBalanceResource { coin }),
+        slow_wallet: unlocked.map(|unlocked| SlowWalletResource {
+            unlocked,
+            transferred: 0,
+        }),
+        ..Default::default()
+    };
+    let mut r = vec![
+        account(a, 100, None),
+        account(b, 50, Some(10)),
+        account(c, 7, None),
+    ];
+
+    let rules: MigrationRules = serde_yaml::from_str(
+        r#"
+rules:
+  - reassign_balance: { from: "0xa", to: "0xb", amount: 40 }
+  - merge: { from: "0xa", into: "0xb" }
+  - set_slow_wallet: { account: "0xc", unlocked: 5 }
+  - tombstone: { account: "0xc" }
+"#,
+    )
+    .unwrap();
+    let report = apply_rules(&mut r, &rules).unwrap();
+
+    assert_eq!(r[0].role, AccountRole::Drop);
+    assert_eq!(balance(&r[1]), 150);
+    // 60 coins of a normal account arrive unlocked in the merge
+    assert_eq!(r[1].slow_wallet.as_ref().unwrap().unlocked, 70);
+    assert_eq!(r[2].slow_wallet.as_ref().unwrap().unlocked, 5);
+    assert_eq!(
+        r[2].auth_key,
+        Some(AuthenticationKey::new(TOMBSTONE_AUTH_KEY))
+    );
+
+    assert_eq!(report.changes.len(), 6);
+    let last = report.final_states();
+    assert_eq!(last.len(), 3);
+    assert!(last.iter().any(|c| c.account == b && c.balance == 150));
+
+    // a rule which cannot apply is an error
+    let bad: MigrationRules =
+        serde_yaml::from_str("rules:\n  - drop: { account: \"0xd\" }\n").unwrap();
+    assert!(apply_rules(&mut r, &bad).is_err());
+}
+
+#[test]
+fn merge_into_itself() {
+    let a = AccountAddress::from_hex_literal("0xa").unwrap();
+    let mut r = vec![LegacyRecoveryV6 {
+        account: Some(a),
+        balance: Some(LegacyBalanceResource { coin: 100 }),
+        ..Default::default()
+    }];
+    // would double the balance and then drop the account
+    let rules: MigrationRules =
+        serde_yaml::from_str("rules:\n  - merge: { from: \"0xa\", into: \"0xa\" }\n").unwrap();
+    assert!(apply_rules(&mut r, &rules).is_err());
+    assert_eq!(r[0].role, AccountRole::EndUser);
+    assert_eq!(balance(&r[0]), 100);
+}
+
+#[test]
+fn reassign_balance_to_itself() {
+    let a = AccountAddress::from_hex_literal("0xa").unwrap();
+    let mut r = vec![LegacyRecoveryV6 {
+        account: Some(a),
+        balance: Some(LegacyBalanceResource { coin: 100 }),
+        slow_wallet: Some(SlowWalletResource {
+            unlocked: 100,
+            transferred: 0,
+        }),
+        ..Default::default()
+    }];
+    // would lock the coins which were unlocked
+    let rules: MigrationRules = serde_yaml::from_str(
+        "rules:\n  - reassign_balance: { from: \"0xa\", to: \"0xa\", amount: 40 }\n",
+    )
+    .unwrap();
+    assert!(apply_rules(&mut r, &rules).is_err());
+    assert_eq!(balance(&r[0]), 100);
+    assert_eq!(r[0].slow_wallet.as_ref().unwrap().unlocked, 100);
+}
@@ -0,0 +1,146 @@
+//! Build the recovery file for a migration genesis from a state snapshot backup
+
+use crate::supply::{self, Supply};
+use anyhow::Context;
+use diem_types::{account_state::AccountState, account_view::AccountView};
+use lotus_types::legacy_types::legacy_recovery_v6::{get_legacy_recovery, LegacyRecoveryV6};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use storage::read_snapshot::{for_each_account, load_snapshot_manifest};
+
+/// An account which could not be converted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversionError {
+    /// the account address, if the state has one
+    pub account: Option<String>,
+    pub message: String,
+}
+
+/// The recovery file of a snapshot, and the accounts which could not be converted
+pub struct SnapshotRecovery {
+    pub recovery: Vec<LegacyRecoveryV6>,
+    pub errors: Vec<ConversionError>,
+    pub supply: Supply,
+}
+
+/// Read every account of a state snapshot backup into the recovery format.
+/// An account which fails to convert is collected in the errors, so one bad
+/// account doesn't stop the whole export. The accounts are sorted in the
+/// scratch dir, which defaults to next to the backup dir.
+pub async fn recovery_from_snapshot(
+    manifest_path: &Path,
+    scratch_dir: Option<&Path>,
+) -> anyhow::Result<SnapshotRecovery> {
+    let manifest = load_snapshot_manifest(&manifest_path.to_path_buf())
+        .with_context(|| format!("could not read manifest {}", manifest_path.display()))?;
+    // the file handles in the manifest are relative to the parent of the backup dir
+    let archive_path = manifest_path
+        .parent()
+        .context("the manifest is not in a backup dir")?;
+
+    // one account at a time, a full snapshot does not fit in memory
+    let mut recovery = vec![];
+    let mut errors = vec![];
+    for_each_account(&manifest, archive_path, scratch_dir, |state| {
+        convert_account(&state, &mut recovery, &mut errors);
+        Ok(())
+    })
+    .await?;
+
+    let supply = supply::populate_supply_stats_from_legacy(&recovery)?;
+    Ok(SnapshotRecovery {
+        recovery,
+        errors,
+        supply,
+    })
+}
+
+fn convert_account(
+    state: &AccountState,
+    recovery: &mut Vec<LegacyRecoveryV6>,
+    errors: &mut Vec<ConversionError>,
+) {
+    match get_legacy_recovery(state) {
+        Ok(r) => recovery.push(r),
+        Err(e) => errors.push(ConversionError {
+            account: state
+                .get_account_address()
+                .ok()
+                .flatten()
+                .map(|a| a.to_hex_literal()),
+            message: format!("{:#}", e),
+        }),
+    }
+}
+
+impl SnapshotRecovery {
+    /// write the recovery json, and the conversion errors next to it if any
+    pub fn save(&self, output: &Path) -> anyhow::Result<()> {
+        fs::write(output, serde_json::to_string(&self.recovery)?)
+            .with_context(|| format!("could not write {}", output.display()))?;
+        if !self.errors.is_empty() {
+            fs::write(
+                output.with_extension("errors.json"),
+                serde_json::to_string_pretty(&self.errors)?,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn collect_conversion_errors() {
+    use crate::genesis_reader::make_access_path;
+    use diem_types::state_store::{state_key::StateKey, state_value::StateValue};
+    use lotus_types::exports::AccountAddress;
+    use std::collections::HashMap;
+
+    let state = |address: AccountAddress, module: &str, name: &str, bytes: Vec<u8>| {
+        let key = StateKey::access_path(make_access_path(address, module, name).unwrap());
+        let values = HashMap::from([(key, StateValue::from(bytes))]);
+        AccountState::from_access_paths_and_values(address, &values)
+            .unwrap()
+            .unwrap()
+    };
+    let bad = AccountAddress::from_hex_literal("0xbad").unwrap();
+    let good = AccountAddress::from_hex_literal("0x900d").unwrap();
+
+    let mut recovery = vec![];
+    let mut errors = vec![];
+    // an account resource which does not deserialize
+    convert_account(
+        &state(bad, "account", "Account", vec![1, 2, 3]),
+        &mut recovery,
+        &mut errors,
+    );
+    // the accounts after it are still converted
+    convert_account(
+        &state(good, "slow_wallet", "SlowWalletList", vec![0]),
+        &mut recovery,
+        &mut errors,
+    );
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].account, Some(bad.to_hex_literal()));
+    assert_eq!(recovery.len(), 1);
+    assert_eq!(recovery[0].account, Some(good));
+}
+
+#[tokio::test]
+async fn supply_of_converted_accounts() {
+    let manifest_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../storage/fixtures/state_epoch_79_ver_33217173.795d/state.manifest");
+    let r = recovery_from_snapshot(&manifest_path, None)
+        .await
+        .expect("could not read snapshot");
+
+    assert_eq!(r.recovery.len() + r.errors.len(), 23634);
+    let balances: u64 = r
+        .recovery
+        .iter()
+        .filter_map(|a| a.balance.as_ref())
+        .map(|b| b.coin)
+        .sum();
+    assert!(balances > 0);
+    assert_eq!(r.supply.total, balances as f64);
+}
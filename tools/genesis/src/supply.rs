@@ -24,6 +24,18 @@ pub struct Supply {
 }
 
 impl Supply {
+    /// print the supply breakdown
+    pub fn print(&self) {
+        println!("  total: {}", self.total);
+        println!("  normal: {}", self.normal);
+        println!("  validator: {}", self.validator);
+        println!("  slow total: {}", self.slow_total);
+        println!("  slow locked: {}", self.slow_locked);
+        println!("  slow validator locked: {}", self.slow_validator_locked);
+        println!("  slow unlocked: {}", self.slow_unlocked);
+        println!("  donor voice: {}", self.donor_voice);
+    }
+
     fn inc_supply(&mut self, r: &LegacyRecoveryV6) -> &mut Self {
         // get balances
         let user_total: f64 = match &r.balance {
@@ -87,9 +87,7 @@ pub async fn setup(
 
     // Builds the genesis block with the specified configurations.
     genesis_builder::build(
-        "none".to_string(), // when is testnet is ignored
-        "none".to_string(),
-        "none".to_string(),
+        None, // testnet validators don't register in a genesis repo
         data_path,
         true,
         &mut recovery,
@@ -0,0 +1,180 @@
+//! Check that a genesis blob and the waypoints which are supposed to match it agree.
+//! The blob is executed in a temporary db to compute its waypoint, so none of the
+//! sources need to be trusted, and the check can run offline.
+
+use crate::genesis_reader::{bootstrap_db_reader_from_gen_tx, read_blob_to_tx};
+use anyhow::{bail, Context};
+use diem_backup_cli::utils::{
+    backup_service_client::BackupServiceClient, read_record_bytes::ReadRecordBytes,
+};
+use diem_config::config::{InitialSafetyRulesConfig, NodeConfig, WaypointConfig};
+use diem_types::ledger_info::LedgerInfoWithSignatures;
+use lotus_types::exports::Waypoint;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use url::Url;
+
+pub const GENESIS_BLOB_FILENAME: &str = "genesis.blob";
+pub const WAYPOINT_FILENAME: &str = "waypoint.txt";
+
+/// The files and node to check the genesis blob against
+pub struct GenesisVerification {
+    /// path of the genesis.blob
+    pub genesis_blob: PathBuf,
+    /// a waypoint.txt, such as the one downloaded with the blob
+    pub waypoint_file: Option<PathBuf>,
+    /// node configs (fullnode.yaml, validator.yaml) whose waypoints should match
+    pub node_configs: Vec<PathBuf>,
+    /// the backup service of a trusted node, whose epoch 0 should match
+    pub upstream: Option<Url>,
+}
+
+impl GenesisVerification {
+    /// the genesis files and node configs in the usual places of a home dir
+    pub fn from_home(home: &Path) -> Self {
+        let genesis_dir = home.join("genesis");
+        let node_configs = ["fullnode.yaml", "validator.yaml", "vfn.yaml"]
+            .iter()
+            .map(|f| home.join(f))
+            .filter(|p| p.exists())
+            .collect();
+
+        Self {
+            genesis_blob: genesis_dir.join(GENESIS_BLOB_FILENAME),
+            waypoint_file: Some(genesis_dir.join(WAYPOINT_FILENAME)).filter(|p| p.exists()),
+            node_configs,
+            upstream: None,
+        }
+    }
+
+    /// Compute the waypoint of the blob and compare every source to it.
+    /// Returns the waypoint, or an error listing every mismatch.
+    pub async fn run(&self) -> anyhow::Result<Waypoint> {
+        let gen_tx = read_blob_to_tx(self.genesis_blob.clone())?;
+        let (_db_rw, waypoint) = bootstrap_db_reader_from_gen_tx(&gen_tx)?;
+        println!("genesis blob {}", self.genesis_blob.display());
+        println!("computed waypoint: {}", waypoint);
+
+        let mut mismatches = vec![];
+
+        if let Some(p) = &self.waypoint_file {
+            let found = read_waypoint_file(p)?;
+            check(&mut mismatches, &p.display().to_string(), waypoint, found);
+        }
+
+        for p in &self.node_configs {
+            for (field, found) in node_config_waypoints(p)? {
+                let source = format!("{} ({})", p.display(), field);
+                check(&mut mismatches, &source, waypoint, found);
+            }
+        }
+
+        if let Some(url) = &self.upstream {
+            let found = upstream_genesis_waypoint(url).await?;
+            check(
+                &mut mismatches,
+                &format!("{} (epoch 0)", url),
+                waypoint,
+                found,
+            );
+        }
+
+        if !mismatches.is_empty() {
+            for m in &mismatches {
+                println!("MISMATCH: {}", m);
+            }
+            bail!(
+                "the genesis blob does not match {} of the sources checked, do not start a node with these files",
+                mismatches.len()
+            );
+        }
+        Ok(waypoint)
+    }
+}
+
+fn check(mismatches: &mut Vec<String>, source: &str, expected: Waypoint, found: Waypoint) {
+    if found == expected {
+        println!("OK: {}", source);
+    } else {
+        mismatches.push(format!("{}: waypoint is {}", source, found));
+    }
+}
+
+/// The waypoint of the ledger info which ended epoch 0, from the backup service
+/// of a node. Unlike the genesis transaction, epoch ending ledger infos are not
+/// pruned, so any node can be used.
+async fn upstream_genesis_waypoint(url: &Url) -> anyhow::Result<Waypoint> {
+    let client = BackupServiceClient::new(url.as_str().trim_end_matches('/').to_owned());
+    let mut records = client
+        .get_epoch_ending_ledger_infos(0, 1)
+        .await
+        .with_context(|| format!("could not reach the backup service at {}", url))?;
+    let bytes = records
+        .read_record_bytes()
+        .await?
+        .with_context(|| format!("{} has no ledger info for epoch 0", url))?;
+    let li: LedgerInfoWithSignatures = bcs::from_bytes(&bytes)?;
+    Waypoint::new_epoch_boundary(li.ledger_info())
+}
+
+fn read_waypoint_file(path: &Path) -> anyhow::Result<Waypoint> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("could not read waypoint file {}", path.display()))?;
+    Waypoint::from_str(s.trim())
+        .with_context(|| format!("could not parse waypoint in {}", path.display()))
+}
+
+/// the waypoints a node config will use when starting from genesis
+fn node_config_waypoints(path: &Path) -> anyhow::Result<Vec<(&'static str, Waypoint)>> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("could not read node config {}", path.display()))?;
+    let config: NodeConfig = serde_yaml::from_str(&s)
+        .with_context(|| format!("could not parse node config {}", path.display()))?;
+
+    let mut found = vec![];
+    if let Some(w) = waypoint_from_config(&config.base.waypoint)? {
+        found.push(("base.waypoint", w));
+    }
+    if let InitialSafetyRulesConfig::FromFile { waypoint, .. } =
+        &config.consensus.safety_rules.initial_safety_rules_config
+    {
+        if let Some(w) = waypoint_from_config(waypoint)? {
+            found.push(("consensus.safety_rules.initial_safety_rules_config", w));
+        }
+    }
+    Ok(found)
+}
+
+/// the waypoint set in a node config, if it is not in secure storage
+pub fn waypoint_from_config(config: &WaypointConfig) -> anyhow::Result<Option<Waypoint>> {
+    match config {
+        WaypointConfig::FromConfig(w) => Ok(Some(*w)),
+        WaypointConfig::FromFile(p) => read_waypoint_file(p).map(Some),
+        // stored waypoints are managed by the node
+        _ => Ok(None),
+    }
+}
+
+#[test]
+fn waypoint_config_sources() {
+    let w =
+        Waypoint::from_str("0:95023f4d6a7e24cac3e52cad29697184db260214210b57aef3f1031ad4d8c02c")
+            .unwrap();
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let wp_path = dir.path().join(WAYPOINT_FILENAME);
+    fs::write(&wp_path, format!("{}\n", w)).unwrap();
+
+    assert_eq!(
+        waypoint_from_config(&WaypointConfig::FromFile(wp_path)).unwrap(),
+        Some(w)
+    );
+    assert_eq!(
+        waypoint_from_config(&WaypointConfig::FromConfig(w)).unwrap(),
+        Some(w)
+    );
+    assert_eq!(waypoint_from_config(&WaypointConfig::None).unwrap(), None);
+}
@@ -2,7 +2,11 @@
 //! instead of using many CLI tools.
 //! genesis wizard
 
-use crate::{genesis_builder, parse_json};
+use crate::{
+    genesis_builder,
+    genesis_repo::{GenesisRepo, GithubRepo, LocalRepo},
+    parse_json,
+};
 ///////
 // TODO: import from libra
 use crate::genesis_registration;
@@ -42,6 +46,8 @@ pub struct GenesisWizard {
     pub github_username: String,
     /// the registrant's github api token.
     pub github_token: String,
+    /// a local genesis repo, e.g. a git checkout, used instead of GitHub
+    pub local_repo: Option<PathBuf>,
     /// the home path of the user
     pub data_path: PathBuf,
     // TODO: remove
@@ -67,6 +73,7 @@ impl GenesisWizard {
             repo_name,
             github_username: "".to_string(),
             github_token: "".to_string(),
+            local_repo: None,
             data_path,
             epoch: None,
             chain, // defaults to testing.
@@ -89,16 +96,17 @@ impl GenesisWizard {
             std::fs::create_dir_all(&self.data_path)?;
         }
         // check the git token is as expected, and set it.
-        self.git_token_check(github_token_path_opt)?;
+        if self.local_repo.is_none() {
+            self.git_token_check(github_token_path_opt)?;
+        }
+        let github_username = if self.local_repo.is_none() {
+            Some(self.github_username.as_str())
+        } else {
+            None
+        };
 
         // Initialize validators' configuration
-        match validator_dialogue(
-            &self.data_path,
-            Some(&self.github_username),
-            Some(self.chain),
-        )
-        .await
-        {
+        match validator_dialogue(&self.data_path, github_username, Some(self.chain)).await {
             Ok(_) => {
                 println!("Validators' config initialized!");
             }
@@ -123,14 +131,27 @@ impl GenesisWizard {
                     VALIDATOR_FILE
                 ))?
                 .to_hex_literal();
-            // check if the user has the github auth token, and that
-            // there is a forked repo on their account.
-            // Fork the repo, if it doesn't exist
-            self.git_setup()?;
+            if let Some(p) = &self.local_repo {
+                let repo = LocalRepo::new(p.to_owned())?;
+                genesis_registration::register(
+                    self.validator_address.clone(),
+                    &repo,
+                    self.data_path.clone(),
+                )?;
+                OLProgress::complete(&format!(
+                    "Configs written to {}, share them with the other validators",
+                    repo.location()
+                ));
+            } else {
+                // check if the user has the github auth token, and that
+                // there is a forked repo on their account.
+                // Fork the repo, if it doesn't exist
+                self.git_setup()?;
 
-            self.genesis_registration_github()?;
+                self.genesis_registration_github()?;
 
-            self.make_pull_request()?;
+                self.make_pull_request()?;
+            }
         }
 
         let ready = if do_genesis {
@@ -150,10 +171,17 @@ impl GenesisWizard {
                 vec![]
             };
 
+            let repo: Box<dyn GenesisRepo> = match &self.local_repo {
+                Some(p) => Box::new(LocalRepo::new(p.to_owned())?),
+                None => Box::new(GithubRepo::new(
+                    self.genesis_repo_org.clone(),
+                    self.repo_name.clone(),
+                    self.github_token.clone(),
+                )),
+            };
+
             genesis_builder::build(
-                self.genesis_repo_org.clone(),
-                self.repo_name.clone(),
-                self.github_token.clone(),
+                Some(repo.as_ref()),
                 self.data_path.clone(),
                 use_local_framework,
                 &mut legacy_recovery,
@@ -300,11 +328,15 @@ impl GenesisWizard {
         let pb = ProgressBar::new(1000).with_style(OLProgress::spinner());
         pb.enable_steady_tick(Duration::from_millis(100));
 
-        genesis_registration::register(
-            self.validator_address.clone(),
-            self.github_username.clone(), // Do the registration on the fork.
+        // Do the registration on the fork.
+        let fork = GithubRepo::new(
+            self.github_username.clone(),
             self.repo_name.clone(),
             self.github_token.clone(),
+        );
+        genesis_registration::register(
+            self.validator_address.clone(),
+            &fork,
             self.data_path.clone(),
         )?;
 
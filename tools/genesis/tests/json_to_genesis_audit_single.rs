@@ -5,8 +5,8 @@ use diem_storage_interface::state_view::LatestDbStateCheckpointView;
 use diem_types::{account_view::AccountView, chain_id::NamedChain};
 use lotus_framework::head_release_bundle;
 use lotus_genesis_tools::{
-    compare, genesis::make_recovery_genesis_from_vec_legacy_recovery, genesis_reader, parse_json,
-    supply, vm::lotus_genesis_default,
+    audit, compare, genesis::make_recovery_genesis_from_vec_legacy_recovery, genesis_reader,
+    parse_json, supply, vm::lotus_genesis_default,
 };
 use lotus_types::{
     exports::{AccountAddress, ChainId},
@@ -194,3 +194,40 @@ fn test_drop() {
     //     .to_string()
     //     .contains("46a7a744b"));
 }
+
+#[test]
+// the standalone audit of a blob finds the migrated account and the validators
+fn test_audit_genesis_blob() {
+    let genesis_vals = test_vals::get_test_valset(1);
+
+    let json = json_path().parent().unwrap().join("single.json");
+
+    let mut user_accounts: Vec<LegacyRecoveryV6> =
+        parse_json::recovery_file_parse(json.clone()).unwrap();
+
+    let gen_tx = make_recovery_genesis_from_vec_legacy_recovery(
+        &mut user_accounts,
+        &genesis_vals,
+        &head_release_bundle(),
+        ChainId::test(),
+        &lotus_genesis_default(NamedChain::TESTING),
+    )
+    .unwrap();
+
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let blob = dir.path().join("genesis.blob");
+    std::fs::write(&blob, bcs::to_bytes(&gen_tx).unwrap()).unwrap();
+
+    let vals_list: Vec<AccountAddress> =
+        genesis_vals.into_iter().map(|v| v.owner_address).collect();
+
+    let audit = audit::audit_genesis_blob(blob.clone(), Some(json), &vals_list).unwrap();
+    assert!(audit.passed(), "{:#?}", audit.discrepancies);
+    assert_eq!(audit.validators.len(), 1);
+    assert!(audit.accounts > 1);
+
+    // a validator which is not in the set is reported
+    let audit = audit::audit_genesis_blob(blob, None, &[AccountAddress::random()]).unwrap();
+    assert!(!audit.passed());
+}
@@ -1,12 +1,17 @@
 //! Helper functions for querying account-related data using the Diem SDK client.
 
+use anyhow::Context;
 use diem_sdk::{
     rest_client::{
         diem_api_types::{Transaction, VersionedEvent, ViewRequest},
         Client,
     },
-    types::{account_address::AccountAddress, validator_config::ValidatorConfig},
+    types::{
+        account_address::AccountAddress, transaction::MultisigTransactionPayload,
+        validator_config::ValidatorConfig,
+    },
 };
+use lotus_framework::entry_args::{decode_entry_args, function_id};
 use lotus_types::{
     move_resource::{gas_coin::SlowWalletBalance, txschedule::TxSchedule},
     type_extensions::client_ext::{entry_function_id, ClientExt},
@@ -39,6 +44,18 @@ pub async fn get_val_config(
     client.get_move_resource::<ValidatorConfig>(account).await
 }
 
+/// Retrieves the addresses of the validators in the current epoch.
+pub async fn get_current_validators(client: &Client) -> anyhow::Result<Vec<AccountAddress>> {
+    let request = ViewRequest {
+        function: entry_function_id("stake", "get_current_validators")?,
+        type_arguments: vec![],
+        arguments: vec![],
+    };
+    let res = client.view(&request, None).await?.into_inner();
+    let vals = res.first().context("no validators returned")?.to_owned();
+    Ok(serde_json::from_value(vals)?)
+}
+
 /// Retrieves events associated with a given account.
 pub async fn get_events(
     client: &Client,
@@ -134,3 +151,234 @@ pub async fn multi_auth_ballots(
 
     Ok(r.data)
 }
+
+/// Calls a view function of the vendor multisig_account module.
+async fn multisig_view(
+    client: &Client,
+    function_name: &str,
+    arguments: Vec<Value>,
+) -> anyhow::Result<Vec<Value>> {
+    let request = ViewRequest {
+        function: entry_function_id("multisig_account", function_name)?,
+        type_arguments: vec![],
+        arguments,
+    };
+
+    Ok(client.view(&request, None).await?.into_inner())
+}
+
+/// Reads a u64 returned by a view function, which the API encodes as a string.
+fn view_u64(res: &[Value]) -> anyhow::Result<u64> {
+    res.first()
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse().ok())
+        .context("view function did not return a u64")
+}
+
+/// Retrieves the owners of a multisig_account.
+pub async fn multisig_owners(
+    client: &Client,
+    multisig_account: AccountAddress,
+) -> anyhow::Result<Vec<AccountAddress>> {
+    let res = multisig_view(client, "owners", vec![multisig_account.to_string().into()]).await?;
+    let owners = res.first().context("no owners returned")?.to_owned();
+    Ok(serde_json::from_value(owners)?)
+}
+
+/// Retrieves the number of approvals a multisig_account transaction needs.
+pub async fn multisig_num_signatures_required(
+    client: &Client,
+    multisig_account: AccountAddress,
+) -> anyhow::Result<u64> {
+    let res = multisig_view(
+        client,
+        "num_signatures_required",
+        vec![multisig_account.to_string().into()],
+    )
+    .await?;
+    view_u64(&res)
+}
+
+/// Retrieves the sequence number of the last executed or rejected multisig_account transaction.
+pub async fn multisig_last_resolved_sequence_number(
+    client: &Client,
+    multisig_account: AccountAddress,
+) -> anyhow::Result<u64> {
+    let res = multisig_view(
+        client,
+        "last_resolved_sequence_number",
+        vec![multisig_account.to_string().into()],
+    )
+    .await?;
+    view_u64(&res)
+}
+
+/// Retrieves the sequence number the next proposed multisig_account transaction will get.
+pub async fn multisig_next_sequence_number(
+    client: &Client,
+    multisig_account: AccountAddress,
+) -> anyhow::Result<u64> {
+    let res = multisig_view(
+        client,
+        "next_sequence_number",
+        vec![multisig_account.to_string().into()],
+    )
+    .await?;
+    view_u64(&res)
+}
+
+/// The address of the next multisig_account created by this creator.
+pub async fn multisig_next_account_address(
+    client: &Client,
+    creator: AccountAddress,
+) -> anyhow::Result<AccountAddress> {
+    let res = multisig_view(
+        client,
+        "get_next_multisig_account_address",
+        vec![creator.to_string().into()],
+    )
+    .await?;
+    let addr = res
+        .first()
+        .and_then(|v| v.as_str())
+        .context("no address returned")?;
+    Ok(addr.parse()?)
+}
+
+/// Checks if the next multisig_account transaction has enough approvals to be executed.
+pub async fn multisig_next_can_be_executed(
+    client: &Client,
+    multisig_account: AccountAddress,
+) -> anyhow::Result<bool> {
+    let next = multisig_last_resolved_sequence_number(client, multisig_account).await? + 1;
+    let pending = multisig_view(
+        client,
+        "get_pending_transactions",
+        vec![multisig_account.to_string().into()],
+    )
+    .await?;
+
+    // nothing pending, the view function would abort
+    if pending
+        .first()
+        .and_then(|p| p.as_array())
+        .map_or(true, |p| p.is_empty())
+    {
+        return Ok(false);
+    }
+
+    let res = multisig_view(
+        client,
+        "can_be_executed",
+        vec![multisig_account.to_string().into(), next.to_string().into()],
+    )
+    .await?;
+    Ok(res.first().and_then(|v| v.as_bool()).unwrap_or(false))
+}
+
+/// Retrieves the owners, threshold and pending transactions of a multisig_account.
+/// The payloads are decoded and the votes of current owners counted.
+pub async fn multisig_account_state(
+    client: &Client,
+    multisig_account: AccountAddress,
+) -> anyhow::Result<Value> {
+    let owners = multisig_owners(client, multisig_account).await?;
+    let threshold = multisig_num_signatures_required(client, multisig_account).await?;
+    let last_resolved = multisig_last_resolved_sequence_number(client, multisig_account).await?;
+
+    let res = multisig_view(
+        client,
+        "get_pending_transactions",
+        vec![multisig_account.to_string().into()],
+    )
+    .await?;
+    let pending = res
+        .first()
+        .and_then(|p| p.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let pending: Vec<Value> = pending
+        .iter()
+        .enumerate()
+        .map(|(i, tx)| {
+            // votes is a SimpleMap, only current owners are counted on chain
+            let votes: Vec<(AccountAddress, bool)> = tx["votes"]["data"]
+                .as_array()
+                .map(|v| {
+                    v.iter()
+                        .filter_map(|e| {
+                            let voter = e["key"].as_str()?.parse().ok()?;
+                            Some((voter, e["value"].as_bool()?))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let approvals = votes
+                .iter()
+                .filter(|(voter, yes)| *yes && owners.contains(voter))
+                .count();
+            let rejections = votes
+                .iter()
+                .filter(|(voter, yes)| !*yes && owners.contains(voter))
+                .count();
+
+            let sequence_number = last_resolved + 1 + i as u64;
+            let payload = match tx["payload"]["vec"][0].as_str() {
+                Some(p) => decode_multisig_payload(p),
+                None => json!({ "payload_hash": tx["payload_hash"]["vec"][0] }),
+            };
+
+            json!({
+                "sequence_number": sequence_number,
+                "creator": tx["creator"],
+                "creation_time_secs": tx["creation_time_secs"],
+                "payload": payload,
+                "approvals": approvals,
+                "rejections": rejections,
+                "votes": votes.iter().map(|(voter, yes)| json!({ "owner": voter, "approved": yes })).collect::<Vec<_>>(),
+                // only the next transaction in line can be executed
+                "can_be_executed": i == 0 && approvals as u64 >= threshold,
+                "can_be_rejected": i == 0 && rejections as u64 >= threshold,
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "owners": owners,
+        "num_signatures_required": threshold,
+        "last_resolved_sequence_number": last_resolved,
+        "pending_transactions": pending,
+    }))
+}
+
+/// Decodes a multisig_account transaction payload, a bcs MultisigTransactionPayload.
+/// Framework calls are shown with the type and value of each argument,
+/// anything else with the raw bcs arguments and why they could not be decoded.
+fn decode_multisig_payload(hex_payload: &str) -> Value {
+    let decoded = hex::decode(hex_payload.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bcs::from_bytes::<MultisigTransactionPayload>(&bytes).ok());
+
+    match decoded {
+        Some(MultisigTransactionPayload::EntryFunction(entry)) => {
+            let mut call = json!({
+                "function": function_id(&entry),
+                "type_args": entry.ty_args().iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+            });
+            match decode_entry_args(&entry) {
+                Ok(args) => call["args"] = args,
+                Err(e) => {
+                    call["raw_args"] = json!(entry
+                        .args()
+                        .iter()
+                        .map(|a| format!("0x{}", hex::encode(a)))
+                        .collect::<Vec<_>>());
+                    call["decode_error"] = json!(format!("{:#}", e));
+                }
+            }
+            call
+        }
+        None => json!({ "undecoded": hex_payload }),
+    }
+}
@@ -1,7 +1,10 @@
 use crate::query_type::QueryType;
 use anyhow::Result;
 use clap::Parser;
+use diem_sdk::rest_client::Client;
+use lotus_types::core_types::app_cfg::AppCfg;
 use serde_json;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[clap(name = env!("CARGO_PKG_NAME"), author, version, about, long_about = None, arg_required_else_help = true)]
@@ -10,13 +13,40 @@ pub struct QueryCli {
     #[clap(subcommand)]
     /// what to query
     subcommand: QueryType,
+
+    /// optional, path to the config file
+    #[clap(short, long)]
+    config_path: Option<PathBuf>,
+
+    /// optional, nickname or address of a profile in lotus-cli-config.yaml.
+    /// Queries go to a node of the profile's chain
+    #[clap(long)]
+    profile: Option<String>,
 }
 
 impl QueryCli {
     pub async fn run(&self) -> Result<()> {
-        // TODO: get client from configs
+        let app_cfg = if self.profile.is_some() || self.subcommand.has_contact() {
+            Some(AppCfg::load(self.config_path.clone())?)
+        } else {
+            None
+        };
+
+        let client = match (&self.profile, &app_cfg) {
+            (Some(p), Some(cfg)) => {
+                let chain = cfg.profile_chain(cfg.find_profile(p)?);
+                Some(Client::new(cfg.pick_url(Some(chain))?))
+            }
+            _ => None,
+        };
+
+        // contacts resolve against the --config-path
+        let subcommand = match &app_cfg {
+            Some(cfg) => self.subcommand.resolve(cfg)?,
+            None => self.subcommand.clone(),
+        };
 
-        let res = self.subcommand.query_to_json(None).await?;
+        let res = subcommand.query_to_json(client).await?;
         let pretty_json = serde_json::to_string_pretty(&res)?;
         println!("{}", pretty_json);
 
@@ -2,7 +2,7 @@ use crate::{
     account_queries::{
         community_wallet_scheduled_transactions, community_wallet_signers,
         get_account_balance_lotus, get_events, get_transactions, get_val_config,
-        is_community_wallet_migrated,
+        is_community_wallet_migrated, multisig_account_state,
     },
     chain_queries::{get_epoch, get_height},
     query_view::get_view,
@@ -10,29 +10,39 @@ use crate::{
 use anyhow::{bail, Context, Result};
 use diem_api_types::Transaction;
 use diem_debugger::DiemDebugger;
-use diem_sdk::{rest_client::Client, types::account_address::AccountAddress};
+use diem_sdk::rest_client::Client;
 use indoc::indoc;
-use lotus_types::{exports::AuthenticationKey, type_extensions::client_ext::ClientExt};
+use lotus_types::{
+    core_types::{
+        address_book::{parse_address_arg, AddressArg},
+        app_cfg::AppCfg,
+    },
+    exports::AuthenticationKey,
+    type_extensions::client_ext::ClientExt,
+};
 use serde_json::json;
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Clone, Debug, clap::Subcommand)]
 pub enum QueryType {
     /// Account balance
     Balance {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
     },
     /// A validator's on-chain configuration
     ValConfig {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
     },
     /// Epoch and waypoint
     Epoch,
     /// Query any account resource by access path string
     Resource {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
         #[clap(short, long)]
         /// the path of the resource, such as 0x1::slow_wallet::SlowWallet
         resource_path_string: String,
@@ -85,8 +95,9 @@ pub enum QueryType {
     SyncDelay,
     /// Get events
     Events {
-        /// account to query events
-        account: AccountAddress,
+        /// account to query events, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
         #[clap(short, long)]
         /// switch for withdrawn or deposited events.
         withdrawn_or_deposited: bool,
@@ -96,8 +107,9 @@ pub enum QueryType {
     },
     /// Get transaction history
     Txs {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
         #[clap(long)]
         /// get transactions after this height
         txs_height: Option<u64>,
@@ -110,24 +122,68 @@ pub enum QueryType {
     },
     /// Is the community wallet migrated
     ComWalletMigrated {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
     },
     /// Signers of the community wallet
     ComWalletSigners {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
     },
     /// Get the community wallet's pending transactions
     ComWalletPendTransactions {
-        /// account to query txs of
-        account: AccountAddress,
+        /// account to query txs of, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
+    },
+    /// Owners, threshold and pending transactions of a multisig account
+    Multisig {
+        /// the multisig account, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
     },
     /// Display all account structs
-    Annotate { account: AccountAddress },
+    Annotate {
+        /// the account, or @name of a contact
+        #[clap(value_parser = parse_address_arg)]
+        account: AddressArg,
+    },
 }
 
 impl QueryType {
+    /// the account the query is about, if it takes one
+    fn account_mut(&mut self) -> Option<&mut AddressArg> {
+        match self {
+            QueryType::Balance { account }
+            | QueryType::ValConfig { account }
+            | QueryType::Resource { account, .. }
+            | QueryType::Events { account, .. }
+            | QueryType::Txs { account, .. }
+            | QueryType::ComWalletMigrated { account }
+            | QueryType::ComWalletSigners { account }
+            | QueryType::ComWalletPendTransactions { account }
+            | QueryType::Multisig { account }
+            | QueryType::Annotate { account } => Some(account),
+            _ => None,
+        }
+    }
+
+    /// does the query name a contact, which needs the config file to resolve
+    pub fn has_contact(&self) -> bool {
+        matches!(self.clone().account_mut(), Some(AddressArg::Contact(_)))
+    }
+
+    /// the same query, with a contact replaced by its address
+    pub fn resolve(&self, app_cfg: &AppCfg) -> Result<QueryType> {
+        let mut query = self.clone();
+        if let Some(account) = query.account_mut() {
+            *account = account.resolve(app_cfg)?.into();
+        }
+        Ok(query)
+    }
+
     pub async fn query_to_json(&self, client_opt: Option<Client>) -> Result<serde_json::Value> {
         let client = match client_opt {
             Some(c) => c,
@@ -136,7 +192,8 @@ impl QueryType {
 
         match self {
             QueryType::Balance { account } => {
-                let res = get_account_balance_lotus(&client, *account).await?;
+                let account = account.address()?;
+                let res = get_account_balance_lotus(&client, account).await?;
                 Ok(json!(res.scaled()))
             }
             QueryType::View {
@@ -167,8 +224,9 @@ impl QueryType {
                 account,
                 resource_path_string,
             } => {
+                let account = account.address()?;
                 let res = client
-                    .get_account_resource(*account, resource_path_string)
+                    .get_account_resource(account, resource_path_string)
                     .await?;
 
                 if let Some(r) = res.inner() {
@@ -178,7 +236,7 @@ impl QueryType {
                 }
             }
             QueryType::ValConfig { account } => {
-                let res = get_val_config(&client, *account).await?;
+                let res = get_val_config(&client, account.address()?).await?;
 
                 // make this readable, turn the network address into a string
                 Ok(json!({
@@ -197,8 +255,13 @@ impl QueryType {
                 withdrawn_or_deposited,
                 seq_start,
             } => {
-                let res =
-                    get_events(&client, *account, *withdrawn_or_deposited, *seq_start).await?;
+                let res = get_events(
+                    &client,
+                    account.address()?,
+                    *withdrawn_or_deposited,
+                    *seq_start,
+                )
+                .await?;
                 Ok(json!({ "events": res }))
             }
             QueryType::Txs {
@@ -209,7 +272,7 @@ impl QueryType {
             } => {
                 let res: Vec<Transaction> = get_transactions(
                     &client,
-                    *account,
+                    account.address()?,
                     *txs_height,
                     *txs_count,
                     txs_type.to_owned(),
@@ -228,24 +291,29 @@ impl QueryType {
                 Ok(json!({ "transactions": prune_res }))
             }
             QueryType::ComWalletMigrated { account } => {
-                let res = is_community_wallet_migrated(&client, *account).await?;
+                let res = is_community_wallet_migrated(&client, account.address()?).await?;
                 Ok(json!({ "migrated": res }))
             }
             QueryType::ComWalletSigners { account } => {
                 // Wont work at the moment as there is no community wallet that with governace structure
-                let _res = community_wallet_signers(&client, *account).await?;
+                let _res = community_wallet_signers(&client, account.address()?).await?;
                 Ok(json!({ "signers": "None"}))
             }
             QueryType::ComWalletPendTransactions { account } => {
                 // Wont work at the moment as there is no community wallet migrated
-                let _res = community_wallet_scheduled_transactions(&client, *account).await?;
+                let _res =
+                    community_wallet_scheduled_transactions(&client, account.address()?).await?;
                 Ok(json!({ "pending_transactions": "None" }))
             }
+            QueryType::Multisig { account } => {
+                multisig_account_state(&client, account.address()?).await
+            }
             QueryType::Annotate { account } => {
+                let account = account.address()?;
                 let dbgger = DiemDebugger::rest_client(client)?;
                 let version = dbgger.get_latest_version().await?;
                 let blob = dbgger
-                    .annotate_account_state_at_version(account.to_owned(), version)
+                    .annotate_account_state_at_version(account, version)
                     .await?;
                 if blob.is_none() {
                     bail!("cannot find account state at {}", account)
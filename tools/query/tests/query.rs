@@ -12,7 +12,9 @@ async fn lotus_query_test() {
 
     let c = s.client();
 
-    let q = QueryType::Balance { account: val_acct };
+    let q = QueryType::Balance {
+        account: val_acct.into(),
+    };
     match q.query_to_json(Some(c.to_owned())).await {
         Ok(v) => {
             println!("v: {:?}", v);
@@ -37,7 +39,9 @@ async fn account_annotate_test() {
 
     let c = s.client();
 
-    let q = QueryType::Annotate { account: val_acct };
+    let q = QueryType::Annotate {
+        account: val_acct.into(),
+    };
     let res = q.query_to_json(Some(c)).await.unwrap();
     println!("{:#}", &res.as_str().unwrap());
     assert!(res.as_str().unwrap().contains("drop"));
@@ -0,0 +1,164 @@
+//! Diagnostics of a node's db, for incident runbooks.
+//! The db is opened read only, so it's safe next to a stopped node.
+
+use crate::session_tools::open_db;
+use diem_framework::natives::code::PackageRegistry;
+use diem_state_view::account_with_state_view::AsAccountWithStateView;
+use diem_storage_interface::{state_view::LatestDbStateCheckpointView, DbReader};
+use diem_types::{
+    account_address::AccountAddress, account_view::AccountView, transaction::Version,
+    waypoint::Waypoint,
+};
+use lotus_types::move_resource::recovery_mode::RecoveryModeResource;
+use move_core_types::language_storage::CORE_CODE_ADDRESS;
+use rocksdb::{Options, DB};
+use serde::Serialize;
+use std::{fs, path::Path};
+
+/// rocksdb property with the size of the sst files of a column family
+const SST_SIZE_PROPERTY: &str = "rocksdb.total-sst-files-size";
+
+#[derive(Debug, Serialize)]
+pub struct LedgerInfoSummary {
+    pub epoch: u64,
+    pub version: Version,
+    pub timestamp_usecs: u64,
+    pub ends_epoch: bool,
+    pub consensus_data_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageSummary {
+    pub name: String,
+    pub upgrade_number: u64,
+    pub source_digest: String,
+}
+
+/// the size of a column family of one of the rocksdb instances
+#[derive(Debug, Serialize)]
+pub struct ColumnFamilySize {
+    pub db: String,
+    pub column_family: String,
+    pub sst_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DbInfo {
+    pub latest_version: Version,
+    pub num_transactions: u64,
+    pub root_hash: String,
+    pub epoch: u64,
+    pub last_ledger_info: LedgerInfoSummary,
+    pub waypoint: Waypoint,
+    pub chain_id: Option<u8>,
+    pub validators: Vec<AccountAddress>,
+    pub framework_packages: Vec<PackageSummary>,
+    pub recovery_mode: bool,
+    /// oldest transaction readable, as recorded by the node's ledger pruner.
+    /// The prune windows are in the node config, not in the db.
+    pub min_readable_txn_version: Option<Version>,
+    /// oldest write set readable, as recorded by the node's pruner
+    pub min_readable_write_set_version: Option<Version>,
+    pub column_families: Vec<ColumnFamilySize>,
+}
+
+/// Everything we check about a db during an incident
+pub fn db_info(db_dir: &Path) -> anyhow::Result<DbInfo> {
+    let db = open_db(db_dir, true /* readonly */)?;
+
+    let executed_trees = db.get_latest_executed_trees()?;
+    let li = db.get_latest_ledger_info()?;
+    let ledger_info = li.ledger_info();
+
+    let view = db.latest_state_checkpoint_view()?;
+    let root = view.as_account_with_state_view(&CORE_CODE_ADDRESS);
+    let validators = root
+        .get_validator_set()?
+        .map(|set| set.payload().map(|v| *v.account_address()).collect())
+        .unwrap_or_default();
+    let framework_packages = root
+        .get_move_resource::<PackageRegistry>()?
+        .map(|r| {
+            r.packages
+                .iter()
+                .map(|p| PackageSummary {
+                    name: p.name.clone(),
+                    upgrade_number: p.upgrade_number,
+                    source_digest: p.source_digest.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let info = DbInfo {
+        latest_version: db.get_latest_version()?,
+        num_transactions: executed_trees.num_transactions(),
+        root_hash: executed_trees.txn_accumulator().root_hash.to_hex(),
+        epoch: root
+            .get_configuration_resource()?
+            .map(|c| c.epoch())
+            .unwrap_or(ledger_info.epoch()),
+        last_ledger_info: LedgerInfoSummary {
+            epoch: ledger_info.epoch(),
+            version: ledger_info.version(),
+            timestamp_usecs: ledger_info.timestamp_usecs(),
+            ends_epoch: ledger_info.ends_epoch(),
+            consensus_data_hash: ledger_info.consensus_data_hash().to_hex(),
+        },
+        waypoint: Waypoint::new_any(ledger_info),
+        chain_id: root.get_chain_id_resource()?.map(|c| c.chain_id().id()),
+        validators,
+        framework_packages,
+        recovery_mode: root
+            .get_move_resource::<RecoveryModeResource>()?
+            .map(|r| r.on)
+            .unwrap_or(false),
+        min_readable_txn_version: db.get_first_txn_version()?,
+        min_readable_write_set_version: db.get_first_write_set_version()?,
+        column_families: vec![],
+    };
+    // let go of the db before opening its rocksdb instances one by one
+    drop(root);
+    drop(view);
+    drop(db);
+
+    Ok(DbInfo {
+        column_families: column_family_sizes(db_dir)?,
+        ..info
+    })
+}
+
+/// the db dir and its sub dirs can each be a rocksdb instance
+fn column_family_sizes(db_dir: &Path) -> anyhow::Result<Vec<ColumnFamilySize>> {
+    let mut dirs = vec![db_dir.to_path_buf()];
+    for entry in fs::read_dir(db_dir)? {
+        let p = entry?.path();
+        if p.is_dir() {
+            dirs.push(p);
+        }
+    }
+    dirs.sort();
+
+    let mut sizes = vec![];
+    for dir in dirs.iter().filter(|d| d.join("CURRENT").exists()) {
+        let name = dir
+            .strip_prefix(db_dir)
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let opts = Options::default();
+        let cfs = DB::list_cf(&opts, dir)?;
+        let rocks = DB::open_cf_for_read_only(&opts, dir, &cfs, false)?;
+        for cf in &cfs {
+            let sst_bytes = match rocks.cf_handle(cf) {
+                Some(h) => rocks.property_int_value_cf(h, SST_SIZE_PROPERTY)?,
+                None => None,
+            };
+            sizes.push(ColumnFamilySize {
+                db: name.clone(),
+                column_family: cf.clone(),
+                sst_bytes,
+            });
+        }
+    }
+    Ok(sizes)
+}
@@ -4,13 +4,9 @@
 
 // refer to vendor: /root/diem/execution/db-bootstrapper/src/bin/diem-db-bootstrapper.rs
 
+use crate::{db_info::db_info, session_tools::open_db};
 use anyhow::{ensure, format_err, Context, Result};
 use clap::Parser;
-use diem_config::config::{
-    RocksdbConfigs, BUFFERED_STATE_TARGET_ITEMS, DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
-    NO_OP_STORAGE_PRUNER_CONFIG,
-};
-use diem_db::DiemDB;
 use diem_executor::db_bootstrapper::calculate_genesis;
 use diem_storage_interface::DbReaderWriter;
 use diem_types::{transaction::Transaction, waypoint::Waypoint};
@@ -31,9 +27,9 @@ pub struct BootstrapOpts {
     /// DB directory
     pub db_dir: PathBuf,
 
-    #[clap(short, long, value_parser)]
-    /// path to genesis tx file
-    pub genesis_txn_file: PathBuf,
+    #[clap(short, long, value_parser, required_unless_present = "info")]
+    /// path to genesis tx file, not needed with --info
+    pub genesis_txn_file: Option<PathBuf>,
 
     #[clap(short, long)]
     /// waypoint expected
@@ -44,13 +40,23 @@ pub struct BootstrapOpts {
     pub commit: bool,
 
     #[clap(long)]
-    /// get info on DB and exit
+    /// print a json report on the DB and exit, the DB is opened read only
     pub info: bool,
 }
 
 impl BootstrapOpts {
     pub fn run(&self) -> Result<Option<Waypoint>> {
-        let genesis_txn = load_genesis_txn(&self.genesis_txn_file)
+        if self.info {
+            let info = db_info(&self.db_dir)?;
+            println!("{}", serde_json::to_string_pretty(&info)?);
+            return Ok(None);
+        }
+
+        let genesis_txn_file = self
+            .genesis_txn_file
+            .as_ref()
+            .context("--genesis-txn-file is required")?;
+        let genesis_txn = load_genesis_txn(genesis_txn_file)
             .with_context(|| format_err!("Failed loading genesis txn."))?;
         assert!(
             matches!(genesis_txn, Transaction::GenesisTransaction(_)),
@@ -61,16 +67,7 @@ impl BootstrapOpts {
 
         // Opening the DB exclusively, it's not allowed to run this tool alongside a running node which
         // operates on the same DB.
-        let db = DiemDB::open(
-            &self.db_dir,
-            false,
-            NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
-            RocksdbConfigs::default(),
-            false, /* indexer */
-            BUFFERED_STATE_TARGET_ITEMS,
-            DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
-        )
-        .expect("Failed to open DB.");
+        let db = open_db(&self.db_dir, false).expect("Failed to open DB.");
 
         let db_rw = DbReaderWriter::new(db);
 
@@ -86,21 +83,6 @@ impl BootstrapOpts {
             executed_trees.txn_accumulator().root_hash
         );
 
-        if self.info {
-            return Ok(None);
-        }
-
-        if self.info {
-            println!("num txs: {:?}", executed_trees.num_transactions());
-            println!("version: {:?}", executed_trees.version());
-            println!(
-                "root hash: {:?}",
-                executed_trees.txn_accumulator().root_hash
-            );
-
-            return Ok(None);
-        }
-
         if let Some(waypoint) = self.waypoint_to_verify {
             ensure!(
                 waypoint.version() == executed_trees.num_transactions(),
@@ -141,7 +123,7 @@ impl BootstrapOpts {
     }
 }
 
-fn load_genesis_txn(path: &Path) -> Result<Transaction> {
+pub(crate) fn load_genesis_txn(path: &Path) -> Result<Transaction> {
     let mut file = File::open(path)?;
     let mut buffer = vec![];
     file.read_to_end(&mut buffer)?;
@@ -168,7 +150,7 @@ fn test_bootstrap_db() -> anyhow::Result<()> {
 
     let r = BootstrapOpts {
         db_dir: db_root_path.path().to_owned(),
-        genesis_txn_file: blob_path,
+        genesis_txn_file: Some(blob_path),
         waypoint_to_verify: None,
         commit: true,
         info: false,
@@ -0,0 +1,289 @@
+//! Preview the next epoch boundary.
+//! The epoch boundary runs in a throwaway VM session on the db, and the
+//! outcome is read from the writeset, which is never committed.
+
+use crate::{
+    session_tools::{self, lotus_execute_session_function},
+    twin_network::TwinNetwork,
+};
+use anyhow::{bail, Context};
+use clap::Parser;
+use diem_state_view::account_with_state_view::AsAccountWithStateView;
+use diem_storage_interface::{state_view::LatestDbStateCheckpointView, DbReaderWriter};
+use diem_types::{
+    access_path::Path as AccessPathKind, account_address::AccountAddress,
+    account_view::AccountView, state_store::state_key::StateKeyInner, transaction::ChangeSet,
+};
+use lotus_types::move_resource::{
+    epoch_boundary::BoundaryStatusResource, gas_coin::SlowWalletBalance, jail::JailResource,
+    proof_of_fee::ConsensusRewardResource,
+};
+use move_core_types::{
+    language_storage::CORE_CODE_ADDRESS, move_resource::MoveStructType, value::MoveValue,
+};
+use serde::Serialize;
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+/// Simulate the next epoch boundary on a local db, without committing anything
+pub struct EpochPreviewOpts {
+    #[clap(short, long, required_unless_present = "twin")]
+    /// the db of a node
+    pub db_dir: Option<PathBuf>,
+    #[clap(short, long, conflicts_with = "db_dir")]
+    /// or the output dir of a twin, which uses the db of its first node
+    pub twin: Option<PathBuf>,
+    #[clap(long)]
+    /// also save the preview as json to this file
+    pub json: Option<PathBuf>,
+}
+
+/// A slow wallet which gets coins unlocked
+#[derive(Debug, Serialize)]
+pub struct SlowUnlock {
+    pub account: AccountAddress,
+    pub unlocked_before: u64,
+    pub unlocked_after: u64,
+}
+
+/// The outcome of the next epoch boundary
+#[derive(Debug, Serialize)]
+pub struct EpochPreview {
+    pub closing_epoch: u64,
+    pub validators_now: Vec<AccountAddress>,
+    pub next_validators: Vec<AccountAddress>,
+    pub compliant_validators: Vec<AccountAddress>,
+    pub seats_offered: u64,
+    pub all_bidders: Vec<AccountAddress>,
+    pub qualified_bidders: Vec<AccountAddress>,
+    pub auction_winners: Vec<AccountAddress>,
+    pub clearing_bid: Option<u64>,
+    pub entry_fee: Option<u64>,
+    pub validators_missing_configs: Vec<AccountAddress>,
+    pub validators_paid: Vec<AccountAddress>,
+    pub reward_per_validator: u64,
+    pub total_reward: u64,
+    pub burned: u64,
+    /// validators jailed by this boundary
+    pub jailed: Vec<AccountAddress>,
+    pub slow_wallet_drip: u64,
+    pub slow_unlocks: Vec<SlowUnlock>,
+    pub reconfig_success: bool,
+}
+
+impl EpochPreview {
+    pub fn print(&self) {
+        println!("closing epoch: {}", self.closing_epoch);
+        println!("current validators: {}", self.validators_now.len());
+        println!(
+            "compliant validators: {}, seats offered: {}",
+            self.compliant_validators.len(),
+            self.seats_offered
+        );
+        println!(
+            "bidders: {}, qualified: {}, winners: {}",
+            self.all_bidders.len(),
+            self.qualified_bidders.len(),
+            self.auction_winners.len()
+        );
+        println!(
+            "clearing bid: {}, entry fee: {}",
+            self.clearing_bid
+                .map(|b| b.to_string())
+                .unwrap_or_else(|| "unchanged".to_string()),
+            self.entry_fee
+                .map(|f| f.to_string())
+                .unwrap_or_else(|| "unchanged".to_string())
+        );
+        println!("next validator set: {}", self.next_validators.len());
+        for v in &self.next_validators {
+            let tag = if self.validators_now.contains(v) {
+                ""
+            } else {
+                " (new)"
+            };
+            println!("  {}{}", v, tag);
+        }
+        for v in &self.validators_missing_configs {
+            println!("  {} won a seat but has no validator config", v);
+        }
+        println!(
+            "rewards: {} validators paid {} each, {} total",
+            self.validators_paid.len(),
+            self.reward_per_validator,
+            self.total_reward
+        );
+        println!("burned: {}", self.burned);
+        println!("jailed: {}", self.jailed.len());
+        for j in &self.jailed {
+            println!("  {}", j);
+        }
+        println!(
+            "slow wallet drip: {} to {} wallets",
+            self.slow_wallet_drip,
+            self.slow_unlocks.len()
+        );
+        if !self.reconfig_success {
+            println!("WARN: the reconfiguration would fail, the validator set would not change");
+        }
+    }
+}
+
+impl EpochPreviewOpts {
+    pub fn run(&self) -> anyhow::Result<EpochPreview> {
+        let db_dir = match (&self.db_dir, &self.twin) {
+            (Some(d), _) => d.to_owned(),
+            (None, Some(t)) => {
+                let twin = TwinNetwork::read(t)?;
+                twin.nodes
+                    .first()
+                    .context("the twin has no nodes")?
+                    .home
+                    .join("db")
+            }
+            (None, None) => bail!("need a --db-dir or a --twin"),
+        };
+        let preview = preview_epoch_boundary(db_dir)?;
+        preview.print();
+        if let Some(p) = &self.json {
+            fs::write(p, serde_json::to_string_pretty(&preview)?)?;
+        }
+        Ok(preview)
+    }
+}
+
+/// Run the epoch boundary in a session, and compare the writeset to the db
+pub fn preview_epoch_boundary(db_dir: PathBuf) -> anyhow::Result<EpochPreview> {
+    let (closing_epoch, validators_now) = {
+        // read only, so it can be used while a node runs
+        let db_rw = DbReaderWriter::new(session_tools::open_db(&db_dir, true)?);
+        let view = db_rw.reader.latest_state_checkpoint_view()?;
+        let root = view.as_account_with_state_view(&CORE_CODE_ADDRESS);
+        let epoch = root
+            .get_configuration_resource()?
+            .context("no configuration resource")?
+            .epoch();
+        let vals: Vec<AccountAddress> = root
+            .get_validator_set()?
+            .context("no validator set")?
+            .payload()
+            .map(|v| *v.account_address())
+            .collect();
+        (epoch, vals)
+    };
+
+    let framework_sig = MoveValue::Signer(AccountAddress::ONE);
+    let vmc = session_tools::lotus_run_session(
+        db_dir.clone(),
+        |session| {
+            lotus_execute_session_function(
+                session,
+                "0x1::epoch_boundary::epoch_boundary",
+                vec![
+                    &framework_sig,
+                    &MoveValue::U64(closing_epoch),
+                    &MoveValue::U64(0),
+                ],
+            )?;
+            Ok(())
+        },
+        None,
+        None,
+    )?;
+    let cs = session_tools::unpack_changeset(vmc)?;
+
+    let written = read_write_set(&cs)?;
+    let status = written
+        .status
+        .context("the epoch boundary did not write a BoundaryStatus")?;
+
+    // compare to the state before the boundary
+    let db_rw = DbReaderWriter::new(session_tools::open_db(&db_dir, true)?);
+    let view = db_rw.reader.latest_state_checkpoint_view()?;
+
+    let mut jailed = vec![];
+    for (account, jail) in written.jails {
+        let before = view
+            .as_account_with_state_view(&account)
+            .get_move_resource::<JailResource>()?;
+        if jail.is_jailed && !before.map(|j| j.is_jailed).unwrap_or(false) {
+            jailed.push(account);
+        }
+    }
+
+    let mut slow_unlocks = vec![];
+    for (account, slow) in written.slow_wallets {
+        let before = view
+            .as_account_with_state_view(&account)
+            .get_move_resource::<SlowWalletBalance>()?
+            .map(|s| s.unlocked)
+            .unwrap_or(0);
+        if slow.unlocked > before {
+            slow_unlocks.push(SlowUnlock {
+                account,
+                unlocked_before: before,
+                unlocked_after: slow.unlocked,
+            });
+        }
+    }
+
+    Ok(EpochPreview {
+        closing_epoch,
+        validators_now,
+        next_validators: status.incoming_actual_vals,
+        compliant_validators: status.incoming_compliant,
+        seats_offered: status.incoming_seats_offered,
+        all_bidders: status.incoming_all_bidders,
+        qualified_bidders: status.incoming_only_qualified_bidders,
+        auction_winners: status.incoming_auction_winners,
+        clearing_bid: written.reward.as_ref().map(|r| r.clearing_bid),
+        entry_fee: written.reward.as_ref().map(|r| r.entry_fee),
+        validators_missing_configs: status.incoming_vals_missing_configs,
+        validators_paid: status.outgoing_vals_paid,
+        reward_per_validator: status.outgoing_nominal_reward_to_vals,
+        total_reward: status.outgoing_total_reward,
+        burned: status.epoch_burn_fees,
+        jailed,
+        slow_wallet_drip: status.slow_wallet_drip_amount,
+        slow_unlocks,
+        reconfig_success: status.incoming_reconfig_success,
+    })
+}
+
+/// the resources of the writeset which the preview reports on
+#[derive(Default)]
+struct Written {
+    status: Option<BoundaryStatusResource>,
+    reward: Option<ConsensusRewardResource>,
+    jails: Vec<(AccountAddress, JailResource)>,
+    slow_wallets: Vec<(AccountAddress, SlowWalletBalance)>,
+}
+
+fn read_write_set(cs: &ChangeSet) -> anyhow::Result<Written> {
+    let mut w = Written::default();
+    for (key, op) in cs.write_set().iter() {
+        let ap = match key.inner() {
+            StateKeyInner::AccessPath(ap) => ap,
+            _ => continue,
+        };
+        let bytes = match op.bytes() {
+            Some(b) => b,
+            None => continue,
+        };
+        let tag = match ap.get_path() {
+            AccessPathKind::Resource(tag) => tag,
+            _ => continue,
+        };
+
+        if tag == BoundaryStatusResource::struct_tag() {
+            w.status = Some(bcs::from_bytes(bytes)?);
+        } else if tag == ConsensusRewardResource::struct_tag() {
+            w.reward = Some(bcs::from_bytes(bytes)?);
+        } else if tag == JailResource::struct_tag() {
+            w.jails.push((ap.address, bcs::from_bytes(bytes)?));
+        } else if tag == SlowWalletBalance::struct_tag() {
+            w.slow_wallets.push((ap.address, bcs::from_bytes(bytes)?));
+        }
+    }
+    Ok(w)
+}
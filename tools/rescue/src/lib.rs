@@ -1,5 +1,11 @@
+pub mod db_info;
 pub mod diem_db_bootstrapper;
+pub mod epoch_preview;
+pub mod rescue_plan;
 pub mod rescue_tx;
+pub mod rescue_verify;
 pub mod session_tools;
+pub mod simulate;
 pub mod twin;
+pub mod twin_network;
 pub use twin::{Twin, TwinSetup};
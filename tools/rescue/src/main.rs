@@ -2,7 +2,10 @@
 //! database bootstrapping, and debugging twin states.
 
 use clap::{Parser, Subcommand};
-use rescue::{diem_db_bootstrapper::BootstrapOpts, rescue_tx::RescueTxOpts, twin::TwinOpts};
+use rescue::{
+    diem_db_bootstrapper::BootstrapOpts, epoch_preview::EpochPreviewOpts, rescue_tx::RescueTxOpts,
+    rescue_verify::VerifyOpts, simulate::SimulateOpts, twin::TwinOpts,
+};
 use std::time::Duration;
 
 #[derive(Parser)]
@@ -17,7 +20,11 @@ struct RescueCli {
 enum Sub {
     RescueTx(RescueTxOpts),
     Bootstrap(BootstrapOpts),
-    Debug(TwinOpts),
+    Verify(VerifyOpts),
+    EpochPreview(EpochPreviewOpts),
+    Simulate(SimulateOpts),
+    #[clap(alias = "debug")]
+    Twin(TwinOpts),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -28,7 +35,7 @@ fn main() -> anyhow::Result<()> {
 
             let b = BootstrapOpts {
                 db_dir: mission.data_path,
-                genesis_txn_file: blob_path,
+                genesis_txn_file: Some(blob_path),
                 waypoint_to_verify: None,
                 commit: false,
                 info: false,
@@ -38,7 +45,16 @@ fn main() -> anyhow::Result<()> {
         Some(Sub::Bootstrap(bootstrap)) => {
             bootstrap.run()?;
         }
-        Some(Sub::Debug(twin)) => {
+        Some(Sub::Verify(verify)) => {
+            verify.run()?;
+        }
+        Some(Sub::EpochPreview(preview)) => {
+            preview.run()?;
+        }
+        Some(Sub::Simulate(sim)) => {
+            sim.run()?;
+        }
+        Some(Sub::Twin(twin)) => {
             twin.run()?;
         }
         _ => {} // prints help
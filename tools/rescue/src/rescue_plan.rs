@@ -0,0 +1,223 @@
+//! A rescue plan lists the operations of a rescue in a yaml file, instead of
+//! writing a new script or Rust function for every incident. All the steps
+//! run in order in one VM session, and produce one writeset.
+//!
+//! ```yaml
+//! steps:
+//!   - upgrade_framework
+//!   - set_validators: { validators: ["0x1234", "0x5678"] }
+//!   - set_epoch_interval: { microsecs: 600000000 }
+//!   - feature_flags: { enable: [5], disable: [] }
+//!   - recovery_mode: { on: true }
+//!   - call:
+//!       function: "0x1::stake::remove_validators"
+//!       args: [{ signer: "0x1" }, { vector_address: ["0x1234"] }]
+//!   - reconfigure
+//! ```
+
+use crate::session_tools::{self, lotus_execute_session_function};
+use anyhow::{bail, Context};
+use diem_types::{account_address::AccountAddress, transaction::ChangeSet};
+use diem_vm::move_vm_ext::SessionExt;
+use move_core_types::{
+    language_storage::{StructTag, CORE_CODE_ADDRESS},
+    value::MoveValue,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// An argument of a function call, with its Move type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanArg {
+    Signer(AccountAddress),
+    Address(AccountAddress),
+    Bool(bool),
+    U8(u8),
+    U64(u64),
+    /// hex encoded bytes
+    VectorU8(String),
+    VectorU64(Vec<u64>),
+    VectorAddress(Vec<AccountAddress>),
+}
+
+impl PlanArg {
+    pub(crate) fn to_move_value(&self) -> anyhow::Result<MoveValue> {
+        Ok(match self {
+            PlanArg::Signer(a) => MoveValue::Signer(*a),
+            PlanArg::Address(a) => MoveValue::Address(*a),
+            PlanArg::Bool(b) => MoveValue::Bool(*b),
+            PlanArg::U8(n) => MoveValue::U8(*n),
+            PlanArg::U64(n) => MoveValue::U64(*n),
+            PlanArg::VectorU8(h) => MoveValue::vector_u8(hex::decode(h.trim_start_matches("0x"))?),
+            PlanArg::VectorU64(v) => {
+                MoveValue::Vector(v.iter().map(|n| MoveValue::U64(*n)).collect())
+            }
+            PlanArg::VectorAddress(v) => MoveValue::vector_address(v.to_owned()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RescueStep {
+    /// publish the head framework release
+    UpgradeFramework,
+    /// replace the validator set. They must already have valid configurations on chain.
+    SetValidators {
+        validators: Vec<AccountAddress>,
+    },
+    SetEpochInterval {
+        microsecs: u64,
+    },
+    FeatureFlags {
+        #[serde(default)]
+        enable: Vec<u64>,
+        #[serde(default)]
+        disable: Vec<u64>,
+    },
+    /// stop or restart the validator selection
+    RecoveryMode {
+        on: bool,
+    },
+    /// call any function of 0x1, ignoring its visibility
+    Call {
+        function: String,
+        #[serde(default)]
+        args: Vec<PlanArg>,
+    },
+    /// new epoch with the writeset block event, usually the last step
+    Reconfigure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RescuePlan {
+    pub steps: Vec<RescueStep>,
+}
+
+impl RescuePlan {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("could not read rescue plan {}", path.display()))?;
+        let plan: Self = serde_yaml::from_str(&s)
+            .with_context(|| format!("could not parse rescue plan {}", path.display()))?;
+        plan.check()?;
+        Ok(plan)
+    }
+
+    /// catch mistakes before opening the db
+    fn check(&self) -> anyhow::Result<()> {
+        if self.steps.is_empty() {
+            bail!("the rescue plan has no steps");
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            if let RescueStep::Call { function, args } = step {
+                let tag: StructTag = function
+                    .parse()
+                    .with_context(|| format!("step {}: {} is not a function", i, function))?;
+                if tag.address != CORE_CODE_ADDRESS {
+                    bail!("step {}: only functions of 0x1 can be called", i);
+                }
+                for a in args {
+                    a.to_move_value()
+                        .with_context(|| format!("step {}: bad argument {:?}", i, a))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// run every step in the session, in order
+    pub fn execute(&self, session: &mut SessionExt) -> anyhow::Result<()> {
+        for (i, step) in self.steps.iter().enumerate() {
+            println!("rescue plan step {}: {:?}", i, step);
+            execute_step(session, step).with_context(|| format!("step {}: {:?}", i, step))?;
+        }
+        Ok(())
+    }
+}
+
+fn execute_step(session: &mut SessionExt, step: &RescueStep) -> anyhow::Result<()> {
+    let framework_sig = MoveValue::Signer(AccountAddress::ONE);
+    match step {
+        RescueStep::UpgradeFramework => session_tools::upgrade_framework(session)?,
+        RescueStep::SetValidators { validators } => {
+            let vals = MoveValue::vector_address(validators.to_owned());
+            lotus_execute_session_function(
+                session,
+                "0x1::diem_governance::set_validators",
+                vec![&framework_sig, &vals],
+            )?;
+        }
+        RescueStep::SetEpochInterval { microsecs } => {
+            lotus_execute_session_function(
+                session,
+                "0x1::block::update_epoch_interval_microsecs",
+                vec![&framework_sig, &MoveValue::U64(*microsecs)],
+            )?;
+        }
+        RescueStep::FeatureFlags { enable, disable } => {
+            let enable = PlanArg::VectorU64(enable.to_owned()).to_move_value()?;
+            let disable = PlanArg::VectorU64(disable.to_owned()).to_move_value()?;
+            lotus_execute_session_function(
+                session,
+                "0x1::features::change_feature_flags",
+                vec![&framework_sig, &enable, &disable],
+            )?;
+        }
+        RescueStep::RecoveryMode { on } => {
+            lotus_execute_session_function(
+                session,
+                "0x1::recovery_mode::set_recovery_mode",
+                vec![&framework_sig, &MoveValue::Bool(*on)],
+            )?;
+        }
+        RescueStep::Call { function, args } => {
+            let values = args
+                .iter()
+                .map(|a| a.to_move_value())
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            lotus_execute_session_function(session, function, values.iter().collect())?;
+        }
+        RescueStep::Reconfigure => session_tools::writeset_voodoo_events(session)?,
+    }
+    Ok(())
+}
+
+/// Run the whole plan against the db in one session
+pub fn plan_changeset(db_path: &Path, plan: &RescuePlan) -> anyhow::Result<ChangeSet> {
+    let vmc = session_tools::lotus_run_session(
+        db_path.to_path_buf(),
+        |session| plan.execute(session),
+        None,
+        None,
+    )?;
+    session_tools::unpack_changeset(vmc)
+}
+
+#[test]
+fn parse_rescue_plan() {
+    let plan: RescuePlan = serde_yaml::from_str(
+        r#"
+steps:
+  - upgrade_framework
+  - set_validators: { validators: ["0xa", "0xb"] }
+  - set_epoch_interval: { microsecs: 600000000 }
+  - feature_flags: { enable: [5] }
+  - recovery_mode: { on: true }
+  - call:
+      function: "0x1::stake::remove_validators"
+      args: [{ signer: "0x1" }, { vector_address: ["0xa"] }, { vector_u8: "0x0102" }]
+  - reconfigure
+"#,
+    )
+    .unwrap();
+    assert_eq!(plan.steps.len(), 7);
+    plan.check().unwrap();
+    assert!(matches!(plan.steps[0], RescueStep::UpgradeFramework));
+    assert!(matches!(plan.steps[6], RescueStep::Reconfigure));
+
+    let not_framework: RescuePlan =
+        serde_yaml::from_str("steps:\n  - call: { function: \"0x2::coin::mint\" }\n").unwrap();
+    assert!(not_framework.check().is_err());
+}
@@ -1,4 +1,7 @@
-use crate::session_tools;
+use crate::{
+    rescue_plan::{self, RescuePlan},
+    session_tools,
+};
 use clap::Parser;
 use diem_types::{
     account_address::AccountAddress,
@@ -27,16 +30,20 @@ pub struct RescueTxOpts {
     /// Replace validator set with these addresses. They must
     /// already have valid configurations on chain.
     pub debug_vals: Option<Vec<AccountAddress>>,
+    #[clap(long, conflicts_with_all = &["script_path", "framework_upgrade", "debug_vals"])]
+    /// yaml file with the steps of the rescue, which all run in one writeset
+    pub plan: Option<PathBuf>,
 }
 
 impl RescueTxOpts {
     pub fn run(&self) -> anyhow::Result<PathBuf> {
         let db_path = self.data_path.clone();
 
-        // There are two options:
+        // There are three options:
         // 1. upgrade the framework because the source in db is a brick.
         // 2. the framework in DB is usable, and we need to execute an admin
         //    transaction from a .move source
+        // 3. a rescue plan of several steps, which may include both
 
         let gen_tx = if let Some(p) = &self.script_path {
             // let payload = custom_script(p, None, Some(5));
@@ -48,12 +55,18 @@ impl RescueTxOpts {
             };
 
             Transaction::GenesisTransaction(wp)
+        } else if let Some(p) = &self.plan {
+            let plan = RescuePlan::read(p)?;
+            let cs = rescue_plan::plan_changeset(&db_path, &plan)?;
+            Transaction::GenesisTransaction(WriteSetPayload::Direct(cs))
         } else if self.framework_upgrade {
             let cs =
                 session_tools::publish_current_framework(&db_path, self.debug_vals.to_owned())?;
             Transaction::GenesisTransaction(WriteSetPayload::Direct(cs))
         } else {
-            anyhow::bail!("no options provided, need a --framework-upgrade or a --script-path");
+            anyhow::bail!(
+                "no options provided, need a --framework-upgrade, a --script-path or a --plan"
+            );
         };
 
         let mut output = self.blob_path.clone().unwrap_or(db_path);
@@ -90,6 +103,7 @@ fn test_create_blob() -> anyhow::Result<()> {
         script_path: Some(script_path),
         framework_upgrade: false,
         debug_vals: None,
+        plan: None,
     };
     r.run()?;
 
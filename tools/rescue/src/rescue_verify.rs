@@ -0,0 +1,390 @@
+//! Dry run a rescue blob before applying it to a node's db.
+//! A RocksDB checkpoint of the db is made next to it, the rescue is committed
+//! to the checkpoint only, and the state before and after are compared.
+
+use crate::{diem_db_bootstrapper::load_genesis_txn, session_tools::open_db};
+use anyhow::{bail, format_err, Context};
+use clap::Parser;
+use diem_crypto::HashValue;
+use diem_db::DiemDB;
+use diem_executor::db_bootstrapper::calculate_genesis;
+use diem_state_view::account_with_state_view::AsAccountWithStateView;
+use diem_storage_interface::{state_view::DbStateViewAtVersion, DbReader, DbReaderWriter};
+use diem_temppath::TempPath;
+use diem_types::{
+    access_path::Path as AccessPathKind,
+    account_address::AccountAddress,
+    account_view::AccountView,
+    state_store::state_key::{StateKey, StateKeyInner},
+    transaction::{Transaction, Version},
+};
+use diem_vm::DiemVM;
+use fs_extra::dir;
+use lotus_types::move_resource::{
+    coin_info::GasCoinInfoResource, recovery_mode::RecoveryModeResource,
+};
+use move_core_types::language_storage::CORE_CODE_ADDRESS;
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Arc};
+
+#[derive(Parser)]
+/// Dry run a rescue blob on a copy of the db, and report what it changes
+pub struct VerifyOpts {
+    #[clap(short, long)]
+    /// the db of the node, which is not modified
+    pub db_dir: PathBuf,
+    #[clap(short, long)]
+    /// the rescue.blob to check
+    pub blob_path: PathBuf,
+    #[clap(long)]
+    /// the rescue is meant to leave the chain in recovery mode
+    pub recovery_mode: bool,
+    #[clap(long)]
+    /// also save the report as json to this file
+    pub report: Option<PathBuf>,
+    #[clap(long)]
+    /// copy the whole db instead of making a checkpoint, for when the
+    /// checkpoint can't hard link the db files, e.g. on a read only mount
+    pub full_copy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// A resource or module which the rescue writes
+#[derive(Debug, Clone, Serialize)]
+pub struct StateChange {
+    pub path: String,
+    pub change: ChangeKind,
+}
+
+/// hash of a framework module before and after the rescue
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleHash {
+    pub module: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// What a rescue does to the chain
+#[derive(Debug, Default, Serialize)]
+pub struct RescueReport {
+    pub waypoint: String,
+    pub epoch_before: u64,
+    pub epoch_after: u64,
+    pub validators_before: Vec<AccountAddress>,
+    pub validators_after: Vec<AccountAddress>,
+    pub supply_before: Option<u128>,
+    pub supply_after: Option<u128>,
+    pub recovery_mode: bool,
+    /// changes to the state of each account
+    pub accounts: BTreeMap<AccountAddress, Vec<StateChange>>,
+    /// changes which don't belong to an account, e.g. table items
+    pub other_changes: Vec<StateChange>,
+    /// framework modules changed by the rescue
+    pub modules: Vec<ModuleHash>,
+    /// sanity checks which failed
+    pub failures: Vec<String>,
+}
+
+impl RescueReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn print(&self) {
+        println!("waypoint: {}", self.waypoint);
+        println!("epoch: {} -> {}", self.epoch_before, self.epoch_after);
+        println!("validators before: {}", self.validators_before.len());
+        println!("validators after: {}", self.validators_after.len());
+        for v in &self.validators_after {
+            println!("  {}", v);
+        }
+        println!(
+            "total supply: {:?} -> {:?}",
+            self.supply_before, self.supply_after
+        );
+        println!("recovery mode: {}", self.recovery_mode);
+
+        println!("accounts changed: {}", self.accounts.len());
+        for (account, changes) in &self.accounts {
+            println!("  {}", account);
+            for c in changes {
+                println!("    {:?} {}", c.change, c.path);
+            }
+        }
+        for c in &self.other_changes {
+            println!("  {:?} {}", c.change, c.path);
+        }
+
+        println!("framework modules changed: {}", self.modules.len());
+        for m in &self.modules {
+            println!(
+                "  {} {} -> {}",
+                m.module,
+                m.before.as_deref().unwrap_or("none"),
+                m.after.as_deref().unwrap_or("none")
+            );
+        }
+
+        if self.passed() {
+            println!("all checks passed");
+        } else {
+            println!("{} checks failed:", self.failures.len());
+            for f in &self.failures {
+                println!("  {}", f);
+            }
+        }
+    }
+}
+
+impl VerifyOpts {
+    pub fn run(&self) -> anyhow::Result<RescueReport> {
+        let report = self.verify()?;
+        report.print();
+        if let Some(p) = &self.report {
+            fs::write(p, serde_json::to_string_pretty(&report)?)?;
+        }
+        if !report.passed() {
+            bail!("rescue blob failed verification");
+        }
+        Ok(report)
+    }
+
+    /// apply the blob to a checkpoint of the db and compare the state
+    pub fn verify(&self) -> anyhow::Result<RescueReport> {
+        let genesis_txn = load_genesis_txn(&self.blob_path)
+            .with_context(|| format_err!("Failed loading genesis txn."))?;
+        if !matches!(genesis_txn, Transaction::GenesisTransaction(_)) {
+            bail!("{} is not a GenesisTransaction", self.blob_path.display());
+        }
+
+        // never write to the node's db
+        let checkpoint = self.checkpoint()?;
+        let db = open_db(checkpoint.path(), false)?;
+        let db_rw = DbReaderWriter::new(db);
+
+        let before = db_rw.reader.get_latest_version()?;
+        let executed_trees = db_rw.reader.get_latest_executed_trees()?;
+        let committer = calculate_genesis::<DiemVM>(&db_rw, executed_trees, &genesis_txn)
+            .with_context(|| format_err!("Failed to calculate genesis."))?;
+        let waypoint = committer.waypoint();
+        committer.commit()?;
+        let after = db_rw.reader.get_latest_version()?;
+
+        let mut report = RescueReport {
+            waypoint: waypoint.to_string(),
+            ..Default::default()
+        };
+        diff_write_set(&db_rw.reader, before, after, &mut report)?;
+        system_state(&db_rw.reader, before, after, &mut report)?;
+        self.sanity_checks(&db_rw.reader, after, &mut report)?;
+        Ok(report)
+    }
+
+    /// A checkpoint hard links the db files, so it is made on the same disk as
+    /// the db and takes almost no space. A full copy needs as much free space
+    /// as the db.
+    fn checkpoint(&self) -> anyhow::Result<TempPath> {
+        let parent = self
+            .db_dir
+            .parent()
+            .context("the db dir has no parent to make the checkpoint in")?;
+        let checkpoint = TempPath::new_with_temp_dir(parent.to_path_buf());
+        if self.full_copy {
+            checkpoint.create_as_dir()?;
+            let mut options = dir::CopyOptions::new();
+            options.content_only = true;
+            dir::copy(&self.db_dir, checkpoint.path(), &options)
+                .with_context(|| format!("could not copy {}", self.db_dir.display()))?;
+        } else {
+            DiemDB::create_checkpoint(&self.db_dir, checkpoint.path()).with_context(|| {
+                format!(
+                    "could not make a checkpoint of {}, try --full-copy",
+                    self.db_dir.display()
+                )
+            })?;
+        }
+        Ok(checkpoint)
+    }
+
+    fn sanity_checks(
+        &self,
+        db: &Arc<dyn DbReader>,
+        after: Version,
+        report: &mut RescueReport,
+    ) -> anyhow::Result<()> {
+        if report.supply_before != report.supply_after {
+            report.failures.push(format!(
+                "total supply changed from {:?} to {:?}",
+                report.supply_before, report.supply_after
+            ));
+        }
+        if report.epoch_after <= report.epoch_before {
+            report
+                .failures
+                .push("the epoch did not change, the rescue does not reconfigure".to_string());
+        }
+        if report.validators_after.is_empty() {
+            report
+                .failures
+                .push("the validator set is empty".to_string());
+        }
+
+        let view = db.state_view_at_version(Some(after))?;
+        for v in &report.validators_after {
+            let account = view.as_account_with_state_view(v);
+            if account.get_validator_config_resource()?.is_none() {
+                report
+                    .failures
+                    .push(format!("{}: validator has no ValidatorConfig", v));
+            }
+        }
+
+        if report.recovery_mode != self.recovery_mode {
+            report.failures.push(format!(
+                "the chain is left with recovery mode {}, expected {}",
+                report.recovery_mode, self.recovery_mode
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// every state key of the rescue, compared before and after
+fn diff_write_set(
+    db: &Arc<dyn DbReader>,
+    before: Version,
+    after: Version,
+    report: &mut RescueReport,
+) -> anyhow::Result<()> {
+    let outputs = db.get_transaction_outputs(after, 1, after)?;
+    let (_, output) = outputs
+        .transactions_and_outputs
+        .first()
+        .context("the rescue transaction was not committed")?;
+
+    for (key, _) in output.write_set().iter() {
+        let old = state_bytes(db, key, before)?;
+        let new = state_bytes(db, key, after)?;
+        let change = match (&old, &new) {
+            (None, Some(_)) => ChangeKind::Created,
+            (Some(_), None) => ChangeKind::Deleted,
+            (Some(a), Some(b)) if a != b => ChangeKind::Modified,
+            _ => continue,
+        };
+
+        match key.inner() {
+            StateKeyInner::AccessPath(ap) => {
+                let path = match ap.get_path() {
+                    AccessPathKind::Code(m) => {
+                        if ap.address == CORE_CODE_ADDRESS {
+                            report.modules.push(ModuleHash {
+                                module: m.name().to_string(),
+                                before: old.as_deref().map(module_hash),
+                                after: new.as_deref().map(module_hash),
+                            });
+                        }
+                        format!("module {}", m.name())
+                    }
+                    AccessPathKind::Resource(tag) => tag.to_string(),
+                    AccessPathKind::ResourceGroup(tag) => format!("resource group {}", tag),
+                };
+                report
+                    .accounts
+                    .entry(ap.address)
+                    .or_default()
+                    .push(StateChange { path, change });
+            }
+            other => report.other_changes.push(StateChange {
+                path: format!("{:?}", other),
+                change,
+            }),
+        }
+    }
+    Ok(())
+}
+
+/// epoch, validator set, supply and recovery mode before and after
+fn system_state(
+    db: &Arc<dyn DbReader>,
+    before: Version,
+    after: Version,
+    report: &mut RescueReport,
+) -> anyhow::Result<()> {
+    report.epoch_before = epoch_at(db, before)?;
+    report.validators_before = validators_at(db, before)?;
+    report.supply_before = supply_at(db, before)?;
+
+    report.epoch_after = epoch_at(db, after)?;
+    report.validators_after = validators_at(db, after)?;
+    report.supply_after = supply_at(db, after)?;
+
+    let view = db.state_view_at_version(Some(after))?;
+    report.recovery_mode = view
+        .as_account_with_state_view(&CORE_CODE_ADDRESS)
+        .get_move_resource::<RecoveryModeResource>()?
+        .map(|r| r.on)
+        .unwrap_or(false);
+    Ok(())
+}
+
+fn epoch_at(db: &Arc<dyn DbReader>, version: Version) -> anyhow::Result<u64> {
+    let view = db.state_view_at_version(Some(version))?;
+    let root = view.as_account_with_state_view(&CORE_CODE_ADDRESS);
+    Ok(root
+        .get_configuration_resource()?
+        .context("no configuration resource")?
+        .epoch())
+}
+
+fn validators_at(db: &Arc<dyn DbReader>, version: Version) -> anyhow::Result<Vec<AccountAddress>> {
+    let view = db.state_view_at_version(Some(version))?;
+    let root = view.as_account_with_state_view(&CORE_CODE_ADDRESS);
+    Ok(root
+        .get_validator_set()?
+        .context("no validator set")?
+        .payload()
+        .map(|v| *v.account_address())
+        .collect())
+}
+
+/// same as the genesis tools' total supply, at any version
+fn supply_at(db: &Arc<dyn DbReader>, version: Version) -> anyhow::Result<Option<u128>> {
+    let view = db.state_view_at_version(Some(version))?;
+    let root = view.as_account_with_state_view(&CORE_CODE_ADDRESS);
+    let coin_info = match root.get_move_resource::<GasCoinInfoResource>()? {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+    let supply = match coin_info.supply() {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    match supply.aggregator.as_ref() {
+        Some(aggregator) => match state_bytes(db, &aggregator.state_key(), version)? {
+            Some(bytes) => Ok(Some(bcs::from_bytes(&bytes)?)),
+            None => Ok(None),
+        },
+        None => Ok(supply.integer.as_ref().map(|i| i.value)),
+    }
+}
+
+fn state_bytes(
+    db: &Arc<dyn DbReader>,
+    key: &StateKey,
+    version: Version,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    Ok(db
+        .get_state_value_by_version(key, version)?
+        .map(|v| v.bytes().to_vec()))
+}
+
+fn module_hash(bytes: &[u8]) -> String {
+    HashValue::sha3_256_of(bytes).to_hex()
+}
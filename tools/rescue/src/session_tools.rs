@@ -28,6 +28,21 @@ pub struct ValCredentials {
     pub fullnode_addresses: Vec<u8>,
 }
 
+/// Open a db with the settings every rescue tool uses. A read only db can be
+/// opened next to a running node.
+pub fn open_db(path: &Path, readonly: bool) -> anyhow::Result<DiemDB> {
+    DiemDB::open(
+        path,
+        readonly,
+        NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
+        RocksdbConfigs::default(),
+        false, /* indexer */
+        BUFFERED_STATE_TARGET_ITEMS,
+        DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+    )
+    .with_context(|| format!("Failed to open DB at {}", path.display()))
+}
+
 // Run a VM session with a dirty database
 // NOTE: there are several implementations of this elsewhere in Diem
 // Some are buggy, some don't have exports or APIs needed (DiemDbBootstrapper). Some have issues with async and db locks (DiemDbDebugger).
@@ -41,17 +56,7 @@ pub fn lotus_run_session<F>(
 where
     F: FnOnce(&mut SessionExt) -> anyhow::Result<()>,
 {
-    let db = DiemDB::open(
-        dir,
-        true,
-        NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
-        RocksdbConfigs::default(),
-        false, /* indexer */
-        BUFFERED_STATE_TARGET_ITEMS,
-        DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
-    )
-    .context("Failed to open DB.")
-    .unwrap();
+    let db = open_db(&dir, true)?;
     let db_rw = DbReaderWriter::new(db);
     let v = db_rw.reader.get_latest_version().unwrap();
     let view = db_rw.reader.state_view_at_version(Some(v)).unwrap();
@@ -333,16 +338,7 @@ fn test_base() {
 // testing we can open a database from fixtures, and produce a VM session
 fn meta_test_open_db_sync() -> anyhow::Result<()> {
     let dir = Path::new("/root/dbarchive/data_bak_2023-12-11/db");
-    let db = DiemDB::open(
-        dir,
-        true,
-        NO_OP_STORAGE_PRUNER_CONFIG, /* pruner */
-        RocksdbConfigs::default(),
-        false, /* indexer */
-        BUFFERED_STATE_TARGET_ITEMS,
-        DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
-    )
-    .expect("Failed to open DB.");
+    let db = open_db(dir, true).expect("Failed to open DB.");
 
     let db_rw = DbReaderWriter::new(db);
 
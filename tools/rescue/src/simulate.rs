@@ -0,0 +1,414 @@
+//! Simulate transactions against a db, as any sender and without signatures.
+//! The transactions are function calls or scripts in a VM session on the db,
+//! like the rescue sessions, so nothing is committed.
+//!
+//! ```yaml
+//! steps:
+//!   - entry_function:
+//!       sender: "0x1234"
+//!       function: "0x1::ol_account::transfer"
+//!       args: [{ address: "0x5678" }, { u64: 100 }]
+//!   - advance_time: { secs: 3600 }
+//!   - new_epoch
+//!   - script: { sender: "0x1", path: "./my_script", args: [] }
+//! ```
+//!
+//! Step N is reported from a session which runs steps 0 to N, so each step
+//! has its own events and writeset. A chain stops at the first abort.
+
+use crate::{
+    rescue_plan::PlanArg,
+    session_tools::{self, lotus_execute_session_function},
+};
+use anyhow::{bail, Context};
+use clap::Parser;
+use diem_gas::{
+    DiemGasMeter, DiemGasParameters, StandardGasMeter, StorageGasParameters,
+    LATEST_GAS_FEATURE_VERSION,
+};
+use diem_types::{
+    access_path::Path as AccessPathKind,
+    account_address::AccountAddress,
+    state_store::state_key::{StateKey, StateKeyInner},
+    transaction::ChangeSet,
+    write_set::WriteOp,
+};
+use diem_vm::move_vm_ext::SessionExt;
+use lotus_framework::builder::framework_generate_upgrade_proposal::lotus_compile_script;
+use move_binary_format::errors::VMError;
+use move_core_types::{
+    language_storage::{StructTag, CORE_CODE_ADDRESS},
+    value::{serialize_values, MoveValue},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// max gas units of one simulated transaction
+const MAX_GAS_UNITS: u64 = 2_000_000;
+
+#[derive(Parser)]
+/// Simulate transactions on a db as any sender, without committing
+pub struct SimulateOpts {
+    #[clap(short, long)]
+    /// the db of a node, which is not modified
+    pub db_dir: PathBuf,
+    #[clap(short, long)]
+    /// yaml file with the transactions and time changes to simulate
+    pub txs: PathBuf,
+    #[clap(long)]
+    /// also save the results as json to this file
+    pub json: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimStep {
+    /// call an entry function, the sender's signer is the first argument
+    EntryFunction {
+        sender: AccountAddress,
+        /// with its type arguments, e.g. 0x1::coin::transfer<0x1::lotus_coin::LotusCoin>
+        function: String,
+        #[serde(default)]
+        args: Vec<PlanArg>,
+    },
+    /// compile and run a script package, the sender's signer is the first argument
+    Script {
+        sender: AccountAddress,
+        path: PathBuf,
+        #[serde(default)]
+        args: Vec<PlanArg>,
+    },
+    /// move the chain clock forward
+    AdvanceTime { secs: u64 },
+    /// run the epoch boundary and reconfigure
+    NewEpoch,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimTxs {
+    pub steps: Vec<SimStep>,
+}
+
+impl SimTxs {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        let txs: Self = serde_yaml::from_str(&s)
+            .with_context(|| format!("could not parse {}", path.display()))?;
+        if txs.steps.is_empty() {
+            bail!("no steps to simulate in {}", path.display());
+        }
+        txs.check()
+            .with_context(|| format!("invalid steps in {}", path.display()))?;
+        Ok(txs)
+    }
+
+    /// reject steps which would do nothing, or not what they say
+    pub fn check(&self) -> anyhow::Result<()> {
+        for (i, step) in self.steps.iter().enumerate() {
+            if let SimStep::AdvanceTime { secs: 0 } = step {
+                bail!("step {}: advance_time needs secs greater than 0", i);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimEvent {
+    pub type_tag: String,
+    /// bcs of the event, hex encoded
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimWrite {
+    pub key: String,
+    pub deleted: bool,
+}
+
+/// The outcome of one step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub step: String,
+    pub success: bool,
+    /// execution gas, in internal gas units
+    pub gas_used: u64,
+    pub abort: Option<String>,
+    pub events: Vec<SimEvent>,
+    pub write_set: Vec<SimWrite>,
+}
+
+impl StepReport {
+    pub fn print(&self, i: usize) {
+        println!("step {}: {}", i, self.step);
+        match &self.abort {
+            Some(a) => println!("  FAILED: {}", a),
+            None => println!("  success"),
+        }
+        println!("  gas used: {}", self.gas_used);
+        println!("  events: {}", self.events.len());
+        for e in &self.events {
+            println!("    {} {}", e.type_tag, e.data);
+        }
+        println!("  writes: {}", self.write_set.len());
+        for w in &self.write_set {
+            let op = if w.deleted { "delete" } else { "write" };
+            println!("    {} {}", op, w.key);
+        }
+    }
+}
+
+impl SimulateOpts {
+    pub fn run(&self) -> anyhow::Result<Vec<StepReport>> {
+        let txs = SimTxs::read(&self.txs)?;
+        let reports = simulate(&self.db_dir, &txs)?;
+        for (i, r) in reports.iter().enumerate() {
+            r.print(i);
+        }
+        if let Some(p) = &self.json {
+            fs::write(p, serde_json::to_string_pretty(&reports)?)?;
+        }
+        Ok(reports)
+    }
+}
+
+/// what happened to the last step of a session
+#[derive(Default)]
+struct LastStep {
+    gas_used: u64,
+    abort: Option<String>,
+}
+
+/// Simulate the steps in order, stopping at the first abort
+pub fn simulate(db_dir: &Path, txs: &SimTxs) -> anyhow::Result<Vec<StepReport>> {
+    let mut reports = vec![];
+    let mut prev_events = 0;
+    let mut prev_writes: BTreeMap<StateKey, WriteOp> = BTreeMap::new();
+
+    for k in 0..txs.steps.len() {
+        let mut last = LastStep::default();
+        let vmc = session_tools::lotus_run_session(
+            db_dir.to_path_buf(),
+            |session| {
+                for step in &txs.steps[..k] {
+                    let (_, abort) = run_step(session, step)?;
+                    if let Some(a) = abort {
+                        bail!("an earlier step aborted: {}", a);
+                    }
+                }
+                let (gas_used, abort) = run_step(session, &txs.steps[k])?;
+                last = LastStep { gas_used, abort };
+                Ok(())
+            },
+            None,
+            None,
+        )?;
+        let cs = session_tools::unpack_changeset(vmc)?;
+
+        let success = last.abort.is_none();
+        let (events, writes) = if success {
+            step_changes(&cs, prev_events, &prev_writes)
+        } else {
+            (vec![], vec![])
+        };
+        reports.push(StepReport {
+            step: format!("{:?}", txs.steps[k]),
+            success,
+            gas_used: last.gas_used,
+            abort: last.abort,
+            events,
+            write_set: writes,
+        });
+        if !success {
+            break;
+        }
+
+        prev_events = cs.events().len();
+        prev_writes = cs
+            .write_set()
+            .iter()
+            .map(|(key, op)| (key.clone(), op.clone()))
+            .collect();
+    }
+    Ok(reports)
+}
+
+/// the events and writes of the last step, which are not in the session
+/// of the steps before it
+fn step_changes(
+    cs: &ChangeSet,
+    prev_events: usize,
+    prev_writes: &BTreeMap<StateKey, WriteOp>,
+) -> (Vec<SimEvent>, Vec<SimWrite>) {
+    let events = cs
+        .events()
+        .iter()
+        .skip(prev_events)
+        .map(|e| SimEvent {
+            type_tag: e.type_tag().to_string(),
+            data: hex::encode(e.event_data()),
+        })
+        .collect();
+    let writes = cs
+        .write_set()
+        .iter()
+        .filter(|(k, op)| prev_writes.get(*k) != Some(*op))
+        .map(|(k, op)| SimWrite {
+            key: describe_key(k),
+            deleted: op.bytes().is_none(),
+        })
+        .collect();
+    (events, writes)
+}
+
+fn describe_key(key: &StateKey) -> String {
+    match key.inner() {
+        StateKeyInner::AccessPath(ap) => match ap.get_path() {
+            AccessPathKind::Code(m) => format!("{} module {}", ap.address, m.name()),
+            AccessPathKind::Resource(tag) => format!("{} {}", ap.address, tag),
+            AccessPathKind::ResourceGroup(tag) => {
+                format!("{} resource group {}", ap.address, tag)
+            }
+        },
+        other => format!("{:?}", other),
+    }
+}
+
+/// Run a step. A Move abort is returned with the gas used, and other errors fail
+fn run_step(session: &mut SessionExt, step: &SimStep) -> anyhow::Result<(u64, Option<String>)> {
+    match step {
+        SimStep::EntryFunction {
+            sender,
+            function,
+            args,
+        } => {
+            let tag: StructTag = function
+                .parse()
+                .with_context(|| format!("{} is not a function", function))?;
+            let mut values = vec![MoveValue::Signer(*sender)];
+            for a in args {
+                values.push(a.to_move_value()?);
+            }
+            let mut meter = gas_meter();
+            let start = u64::from(meter.balance());
+            let res = session.execute_function_bypass_visibility(
+                &tag.module_id(),
+                tag.name.as_ident_str(),
+                tag.type_params,
+                serialize_values(&values),
+                &mut meter,
+            );
+            let used = start - u64::from(meter.balance());
+            Ok((used, res.err().map(|e| abort_info(&e))))
+        }
+        SimStep::Script { sender, path, args } => {
+            let (code, _hash) = lotus_compile_script(path, false)?;
+            let mut values = vec![MoveValue::Signer(*sender)];
+            for a in args {
+                values.push(a.to_move_value()?);
+            }
+            let mut meter = gas_meter();
+            let start = u64::from(meter.balance());
+            let res = session.execute_script(code, vec![], serialize_values(&values), &mut meter);
+            let used = start - u64::from(meter.balance());
+            Ok((used, res.err().map(|e| abort_info(&e))))
+        }
+        SimStep::AdvanceTime { secs } => {
+            let now = now_microseconds(session)?;
+            lotus_execute_session_function(
+                session,
+                "0x1::timestamp::update_global_time",
+                vec![
+                    &MoveValue::Signer(AccountAddress::ZERO),
+                    // any proposer but the vm, so that the time can change
+                    &MoveValue::Address(CORE_CODE_ADDRESS),
+                    &MoveValue::U64(now + secs * 1_000_000),
+                ],
+            )?;
+            Ok((0, None))
+        }
+        SimStep::NewEpoch => {
+            // reconfiguration only happens if the time changed
+            run_step(session, &SimStep::AdvanceTime { secs: 1 })?;
+            let ret = lotus_execute_session_function(
+                session,
+                "0x1::reconfiguration::get_current_epoch",
+                vec![],
+            )?;
+            let epoch: u64 =
+                bcs::from_bytes(&ret.return_values.first().context("no epoch returned")?.0)?;
+            lotus_execute_session_function(
+                session,
+                "0x1::epoch_boundary::epoch_boundary",
+                vec![
+                    &MoveValue::Signer(AccountAddress::ONE),
+                    &MoveValue::U64(epoch),
+                    &MoveValue::U64(0),
+                ],
+            )?;
+            Ok((0, None))
+        }
+    }
+}
+
+fn now_microseconds(session: &mut SessionExt) -> anyhow::Result<u64> {
+    let ret = lotus_execute_session_function(session, "0x1::timestamp::now_microseconds", vec![])?;
+    Ok(bcs::from_bytes(
+        &ret.return_values.first().context("no time returned")?.0,
+    )?)
+}
+
+fn gas_meter() -> StandardGasMeter {
+    StandardGasMeter::new(
+        LATEST_GAS_FEATURE_VERSION,
+        DiemGasParameters::initial(),
+        StorageGasParameters::free_and_unlimited(),
+        MAX_GAS_UNITS,
+    )
+}
+
+/// the status, abort code and location of a failed call
+fn abort_info(e: &VMError) -> String {
+    format!(
+        "{:?}, abort code {:?}, in {:?}",
+        e.major_status(),
+        e.sub_status(),
+        e.location()
+    )
+}
+
+#[test]
+fn parse_sim_txs() {
+    let txs: SimTxs = serde_yaml::from_str(
+        r#"
+steps:
+  - entry_function:
+      sender: "0xabc"
+      function: "0x1::ol_account::transfer"
+      args: [{ address: "0xdef" }, { u64: 100 }]
+  - advance_time: { secs: 3600 }
+  - new_epoch
+  - script: { sender: "0x1", path: "./script" }
+"#,
+    )
+    .unwrap();
+    assert_eq!(txs.steps.len(), 4);
+    assert!(matches!(txs.steps[2], SimStep::NewEpoch));
+    assert!(matches!(
+        &txs.steps[0],
+        SimStep::EntryFunction { args, .. } if args.len() == 2
+    ));
+}
+
+#[test]
+fn reject_zero_time_step() {
+    let txs: SimTxs = serde_yaml::from_str("steps:\n  - advance_time: { secs: 0 }\n").unwrap();
+    assert!(txs.check().is_err());
+    let txs: SimTxs = serde_yaml::from_str("steps:\n  - advance_time: { secs: 1 }\n").unwrap();
+    assert!(txs.check().is_ok());
+}
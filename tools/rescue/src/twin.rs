@@ -1,8 +1,9 @@
 #![allow(unused)]
+use crate::twin_network::TwinNetwork;
 use crate::{diem_db_bootstrapper::BootstrapOpts, session_tools::session_add_validators};
 use anyhow::{bail, Context};
 use async_trait::async_trait;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use diem_config::config::{NodeConfig, WaypointConfig};
 use diem_forge::{Swarm, SwarmExt, Validator};
 use diem_temppath::TempPath;
@@ -32,7 +33,7 @@ use std::{
 use tokio::process::Command;
 
 use lotus_txs::txs_cli::{TxsCli, TxsSub::Transfer};
-use lotus_types::core_types::app_cfg::TxCost;
+use lotus_types::core_types::app_cfg::{AppCfg, TxCost};
 
 use crate::{
     rescue_tx::RescueTxOpts,
@@ -63,70 +64,91 @@ use serde::Deserialize;
 use std::{fs, mem::ManuallyDrop, path::Path};
 
 #[derive(Parser)]
-
 /// '''
-/// Set up a twin of the network, with a synced db
+/// Set up a twin of the network, with a synced db, and run it locally
 /// '''
-///  *** TO DO: Functionality to be added
 pub struct TwinOpts {
-    // path of snapshot db we want marlon to drive
-    #[clap(value_parser)]
-    pub db_dir: PathBuf,
-    /// The operator.yaml file which contains registration information
-    #[clap(value_parser)]
-    pub oper_file: Option<PathBuf>,
-    /// provide info about the DB state, e.g. version
-    #[clap(value_parser)]
-    pub info: bool,
+    #[clap(subcommand)]
+    pub command: TwinSub,
+}
+
+#[derive(Subcommand)]
+pub enum TwinSub {
+    /// make a twin of a db snapshot with new local validators, and start it
+    Create {
+        /// path of the snapshot db the twin starts from
+        #[clap(short, long)]
+        db_dir: PathBuf,
+        /// where the node configs, keys, cli config and twin.json are written
+        #[clap(short, long)]
+        output_dir: PathBuf,
+        /// number of validators
+        #[clap(short('n'), long, default_value = "3")]
+        count: u8,
+        /// seconds per epoch, to rehearse epoch changes faster
+        #[clap(short, long)]
+        epoch_interval_secs: Option<u64>,
+        /// the diem-node binary, defaults to DIEM_FORGE_NODE_BIN_PATH
+        #[clap(long)]
+        node_bin: Option<PathBuf>,
+    },
+    /// start the nodes of a twin
+    Start {
+        #[clap(short, long)]
+        output_dir: PathBuf,
+    },
+    /// stop the nodes of a twin
+    Stop {
+        #[clap(short, long)]
+        output_dir: PathBuf,
+    },
+    /// show which nodes of a twin are running, and their epoch and version
+    Status {
+        #[clap(short, long)]
+        output_dir: PathBuf,
+    },
 }
 
 impl TwinOpts {
     pub fn run(&self) -> anyhow::Result<(), anyhow::Error> {
-        let db_path = &self.db_dir;
-        let num_val = 3_u8;
-        let twin = Twin {
-            db_dir: db_path.to_path_buf(),
-            oper_file: self.oper_file.clone(),
-            info: self.info,
-        };
-        twin.run()
+        let runtime = tokio::runtime::Runtime::new()?;
+        match &self.command {
+            TwinSub::Create {
+                db_dir,
+                output_dir,
+                count,
+                epoch_interval_secs,
+                node_bin,
+            } => {
+                let twin = runtime.block_on(TwinNetwork::create(
+                    db_dir.to_owned(),
+                    output_dir.to_owned(),
+                    *count,
+                    *epoch_interval_secs,
+                    node_bin.to_owned(),
+                ))?;
+                runtime.block_on(twin.status())?;
+            }
+            TwinSub::Start { output_dir } => {
+                let mut twin = TwinNetwork::read(output_dir)?;
+                runtime.block_on(twin.start())?;
+                runtime.block_on(twin.status())?;
+            }
+            TwinSub::Stop { output_dir } => {
+                TwinNetwork::read(output_dir)?.stop()?;
+            }
+            TwinSub::Status { output_dir } => {
+                runtime.block_on(TwinNetwork::read(output_dir)?.status())?;
+            }
+        }
+        Ok(())
     }
 }
 
 /// '''
 /// Twin of the network
 /// '''
-pub struct Twin {
-    pub db_dir: PathBuf,
-    pub oper_file: Option<PathBuf>,
-    pub info: bool,
-}
-
-/// '''
-/// Runner for the twin
-/// '''
-trait TwinRunner {
-    /// Take the twin and run it
-    fn run(&self) -> anyhow::Result<(), anyhow::Error>;
-}
-
-impl TwinRunner for Twin
-where
-    Twin: TwinSetup,
-{
-    fn run(&self) -> anyhow::Result<(), anyhow::Error> {
-        let db_path = &self.db_dir;
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        let num_validators = 3_u8;
-        runtime.block_on(Twin::apply_with_rando_e2e(
-            db_path.to_path_buf(),
-            num_validators,
-        ));
-        println!("Twins are running!");
-        std::thread::park();
-        Ok(())
-    }
-}
+pub struct Twin;
 
 /// '''
 /// Twin setup
@@ -141,6 +163,7 @@ pub trait TwinSetup {
     async fn make_rescue_twin_blob(
         db_path: &Path,
         creds: Vec<&ValCredentials>,
+        epoch_interval_secs: Option<u64>,
     ) -> anyhow::Result<PathBuf>;
     fn update_node_config_restart(
         validator: &mut LocalNode,
@@ -149,6 +172,8 @@ pub trait TwinSetup {
     async fn apply_with_rando_e2e(
         prod_db: PathBuf,
         num_validators: u8,
+        epoch_interval_secs: Option<u64>,
+        diem_node_proxy: Option<PathBuf>,
     ) -> anyhow::Result<(LotusSmoke, TempPath), anyhow::Error>;
     async fn extract_credentials(marlon_node: &LocalNode) -> anyhow::Result<ValCredentials>;
     fn clone_db(prod_db: &Path, swarm_db: &Path) -> anyhow::Result<()>;
@@ -177,7 +202,8 @@ impl TwinSetup for Twin {
         let tx = ValidatorTxs::Register {
             operator_file: Some(file),
         }
-        .make_payload()?
+        // registration has no contacts to resolve
+        .make_payload(&AppCfg::default())?
         .encode();
         if let diem_types::transaction::TransactionPayload::Script(s) = tx {
             return Ok(s);
@@ -192,14 +218,14 @@ impl TwinSetup for Twin {
     async fn make_rescue_twin_blob(
         db_path: &Path,
         creds: Vec<&ValCredentials>,
+        epoch_interval_secs: Option<u64>,
     ) -> anyhow::Result<PathBuf> {
         println!("run session to create validator onboarding tx (rescue.blob)");
-        let epoch_interval = 100000_u64;
         let vmc = lotus_run_session(
             db_path.to_path_buf(),
             |session| session_add_validators(session, creds),
             None,
-            None,
+            epoch_interval_secs.map(|s| s * 1_000_000),
         )?;
 
         let cs = session_tools::unpack_changeset(vmc)?;
@@ -232,18 +258,18 @@ impl TwinSetup for Twin {
     async fn apply_with_rando_e2e(
         prod_db: PathBuf,
         num_validators: u8,
+        epoch_interval_secs: Option<u64>,
+        diem_node_proxy: Option<PathBuf>,
     ) -> anyhow::Result<(LotusSmoke, TempPath), anyhow::Error> {
-        //The diem-node should be compiled externally to avoid any potential conflicts with the current build
-        //get the current path
+        // The diem-node can be compiled externally from a proxy crate to avoid
+        // any potential conflicts with the current build. Otherwise the binary
+        // of DIEM_FORGE_NODE_BIN_PATH is used.
 
         let start_upgrade = Instant::now();
 
-        let current_path = std::env::current_dir()?;
-        //path to diem-node binary
-        let diem_node_path = current_path.join("tests/diem-proxy");
         // 1. Create a new validator set with new accounts
         println!("1. Create a new validator set with new accounts");
-        let mut smoke = LotusSmoke::new(Some(num_validators), Some(diem_node_path)).await?;
+        let mut smoke = LotusSmoke::new(Some(num_validators), diem_node_proxy).await?;
         //due to borrowing issues
         let client = smoke.client().clone();
         //Get the credentials of all the nodes
@@ -278,7 +304,8 @@ impl TwinSetup for Twin {
         println!("3. Create a rescue blob with the new validator");
         let first_val = smoke.swarm.validators().next().unwrap().peer_id();
         let genesis_blob_path =
-            Self::make_rescue_twin_blob(&swarm_db_paths[0], creds.to_owned()).await?;
+            Self::make_rescue_twin_blob(&swarm_db_paths[0], creds.to_owned(), epoch_interval_secs)
+                .await?;
         let mut genesis_blob_paths = Vec::new();
         genesis_blob_paths.push(genesis_blob_path.clone());
         // 4. Apply the rescue blob to the swarm db
@@ -299,7 +326,7 @@ impl TwinSetup for Twin {
         for (i, p) in swarm_db_paths.iter().enumerate() {
             let bootstrap = BootstrapOpts {
                 db_dir: p.clone(),
-                genesis_txn_file: genesis_blob_paths[i].clone(),
+                genesis_txn_file: Some(genesis_blob_paths[i].clone()),
                 waypoint_to_verify: None,
                 commit: false, // NOT APPLYING THE TX
                 info: false,
@@ -313,7 +340,7 @@ impl TwinSetup for Twin {
 
             let bootstrap = BootstrapOpts {
                 db_dir: p.clone(),
-                genesis_txn_file: genesis_blob_paths[i].clone(),
+                genesis_txn_file: Some(genesis_blob_paths[i].clone()),
                 waypoint_to_verify: None,
                 commit: true, // APPLY THE TX
                 info: false,
@@ -370,7 +397,7 @@ impl TwinSetup for Twin {
         let config_path = d.path().to_owned().join("lotus-cli-config.yaml");
         let cli = TxsCli {
             subcommand: Some(Transfer {
-                to_account: recipient,
+                to_account: recipient.into(),
                 amount: 1.0,
             }),
             mnemonic: None,
@@ -382,13 +409,17 @@ impl TwinSetup for Twin {
             tx_cost: Some(TxCost::prod_baseline_cost()),
             estimate_only: false,
             legacy_address: false,
+            multi_key_file: None,
+            profile: None,
         };
         cli.run()
             .await
             .expect("cli could not send to existing account");
         let bal_curr = get_lotus_balance(&client, recipient).await?;
         // 8. Check that the balance has changed
-        assert!(bal_curr.total > bal_old.total, "balance should change");
+        if bal_curr.total <= bal_old.total {
+            bail!("the twin does not process transactions, balance should change");
+        }
 
         let duration_upgrade = start_upgrade.elapsed();
         println!(">>> Time to prepare swarm: {:?}", duration_upgrade);
@@ -511,12 +542,23 @@ impl TwinSetup for Twin {
 fn test_twin_cl() -> anyhow::Result<()> {
     //use any db
     let prod_db_to_clone = PathBuf::from("/root/.lotus/db");
+    let output_dir = diem_temppath::TempPath::new();
     let twin = TwinOpts {
-        db_dir: prod_db_to_clone,
-        oper_file: None,
-        info: false,
+        command: TwinSub::Create {
+            db_dir: prod_db_to_clone,
+            output_dir: output_dir.path().to_owned(),
+            count: 3,
+            epoch_interval_secs: Some(120),
+            node_bin: None,
+        },
+    };
+    twin.run()?;
+    let stop = TwinOpts {
+        command: TwinSub::Stop {
+            output_dir: output_dir.path().to_owned(),
+        },
     };
-    twin.run();
+    stop.run()?;
     Ok(())
 }
 #[ignore]
@@ -524,7 +566,8 @@ fn test_twin_cl() -> anyhow::Result<()> {
 async fn test_twin_random() -> anyhow::Result<()> {
     //use any db
     let prod_db_to_clone = PathBuf::from("/root/.lotus/db");
-    Twin::apply_with_rando_e2e(prod_db_to_clone, 3)
+    let proxy = std::env::current_dir()?.join("tests/diem-proxy");
+    Twin::apply_with_rando_e2e(prod_db_to_clone, 3, None, Some(proxy))
         .await
         .unwrap();
     Ok(())
@@ -0,0 +1,298 @@
+//! A twin which outlives the process that made it.
+//! The nodes of the twin swarm are moved to an output dir, and run as
+//! separate `diem-node` processes, which can be stopped and started again.
+
+use crate::twin::{Twin, TwinSetup};
+use anyhow::{bail, Context};
+use diem_forge::{Node, Swarm};
+use fs_extra::dir;
+use lotus_smoke_tests::configure_validator;
+use lotus_types::exports::{AccountAddress, Client};
+use lotus_wallet::utils::write_to_user_only_file;
+use serde::{Deserialize, Serialize};
+use smoke_test::test_utils::MAX_HEALTHY_WAIT_SECS;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+pub const TWIN_FILE: &str = "twin.json";
+const CLI_CONFIG_FILE: &str = "lotus-cli-config.yaml";
+const ACCOUNT_KEY_FILE: &str = "account.key";
+const NODE_LOG_FILE: &str = "node.log";
+const NODE_BIN_ENV: &str = "DIEM_FORGE_NODE_BIN_PATH";
+
+/// One validator of the twin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwinNode {
+    pub name: String,
+    pub account: AccountAddress,
+    /// the node's dir, with its config, keys and db
+    pub home: PathBuf,
+    pub config: PathBuf,
+    pub api_url: String,
+    /// the diem-node process, while it runs
+    pub pid: Option<u32>,
+}
+
+/// Everything about a twin, saved as twin.json in its output dir
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwinNetwork {
+    pub output_dir: PathBuf,
+    pub node_bin: PathBuf,
+    pub cli_config: PathBuf,
+    pub nodes: Vec<TwinNode>,
+}
+
+impl TwinNetwork {
+    /// Make a twin of the snapshot db with new validators, move its nodes
+    /// to the output dir and start them
+    pub async fn create(
+        db_dir: PathBuf,
+        output_dir: PathBuf,
+        count: u8,
+        epoch_interval_secs: Option<u64>,
+        node_bin: Option<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        if output_dir.join(TWIN_FILE).exists() {
+            bail!(
+                "there is already a twin in {}, use `twin start`",
+                output_dir.display()
+            );
+        }
+        if count == 0 {
+            bail!("a twin needs at least one validator");
+        }
+        // the swarm and the twin use the same binary
+        let node_bin = resolve_node_bin(node_bin)?;
+        std::env::set_var(NODE_BIN_ENV, &node_bin);
+        fs::create_dir_all(&output_dir)?;
+
+        let (mut smoke, _) =
+            Twin::apply_with_rando_e2e(db_dir, count, epoch_interval_secs, None).await?;
+
+        let (_, mut app_cfg) =
+            configure_validator::init_val_config_files(&mut smoke.swarm, 0, output_dir.clone())
+                .await?;
+        app_cfg.save_file()?;
+
+        let keys = smoke.validator_private_keys.clone();
+        let mut nodes = vec![];
+        for (i, n) in smoke.swarm.validators_mut().enumerate() {
+            n.stop();
+            let name = format!("val_{}", i);
+            let home = output_dir.join(&name);
+            let old_home = n
+                .config_path()
+                .parent()
+                .context("node config has no dir")?
+                .to_owned();
+            let config_file = n
+                .config_path()
+                .file_name()
+                .context("node config has no file name")?
+                .to_owned();
+
+            fs::create_dir_all(&home)?;
+            let mut options = dir::CopyOptions::new();
+            options.content_only = true;
+            dir::move_dir(&old_home, &home, &options)
+                .with_context(|| format!("could not move {}", old_home.display()))?;
+
+            // every path of the node config was in the swarm's dir
+            let config = home.join(config_file);
+            let yaml = fs::read_to_string(&config)?;
+            fs::write(
+                &config,
+                yaml.replace(&old_home.display().to_string(), &home.display().to_string()),
+            )?;
+
+            if let Some(k) = keys.get(i) {
+                write_to_user_only_file(
+                    &home.join(ACCOUNT_KEY_FILE),
+                    ACCOUNT_KEY_FILE,
+                    k.as_bytes(),
+                )?;
+            }
+
+            nodes.push(TwinNode {
+                name,
+                account: n.peer_id(),
+                home,
+                config,
+                api_url: n.rest_api_endpoint().to_string(),
+                pid: None,
+            });
+        }
+        // the swarm's temp dir goes away, the nodes are in the output dir now
+        drop(smoke);
+
+        let mut twin = Self {
+            cli_config: output_dir.join(CLI_CONFIG_FILE),
+            output_dir,
+            node_bin,
+            nodes,
+        };
+        twin.save()?;
+        twin.start().await?;
+        Ok(twin)
+    }
+
+    pub fn read(output_dir: &Path) -> anyhow::Result<Self> {
+        let p = output_dir.join(TWIN_FILE);
+        let s = fs::read_to_string(&p)
+            .with_context(|| format!("no twin found in {}", output_dir.display()))?;
+        serde_json::from_str(&s).with_context(|| format!("could not parse {}", p.display()))
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        fs::write(
+            self.output_dir.join(TWIN_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// start the nodes which are not running, and wait for their APIs
+    pub async fn start(&mut self) -> anyhow::Result<()> {
+        for n in self.nodes.iter_mut() {
+            if n.is_running(&self.node_bin) {
+                println!("{} is already running", n.name);
+                continue;
+            }
+            let log = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(n.home.join(NODE_LOG_FILE))?;
+            let child = Command::new(&self.node_bin)
+                .arg("-f")
+                .arg(&n.config)
+                .stdout(Stdio::from(log.try_clone()?))
+                .stderr(Stdio::from(log))
+                .spawn()
+                .with_context(|| format!("could not run {}", self.node_bin.display()))?;
+            n.pid = Some(child.id());
+            println!("started {} with pid {}", n.name, child.id());
+        }
+        self.save()?;
+
+        let deadline = Instant::now() + Duration::from_secs(MAX_HEALTHY_WAIT_SECS);
+        for n in &self.nodes {
+            let client = Client::new(n.api_url.parse()?);
+            while client.get_index().await.is_err() {
+                if Instant::now() > deadline {
+                    bail!(
+                        "{} api is not up, see {}",
+                        n.name,
+                        n.home.join(NODE_LOG_FILE).display()
+                    );
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// stop every node, and wait for the processes to exit
+    pub fn stop(&mut self) -> anyhow::Result<()> {
+        for n in self.nodes.iter_mut() {
+            let pid = match n.pid {
+                Some(p) => p,
+                None => continue,
+            };
+            // after a reboot the pid can belong to another process
+            if n.is_running(&self.node_bin) {
+                Command::new("kill").arg(pid.to_string()).status()?;
+                let deadline = Instant::now() + Duration::from_secs(30);
+                while n.is_running(&self.node_bin) {
+                    if Instant::now() > deadline {
+                        bail!("{} with pid {} did not stop", n.name, pid);
+                    }
+                    std::thread::sleep(Duration::from_millis(200));
+                }
+            }
+            println!("stopped {}", n.name);
+            n.pid = None;
+        }
+        self.save()
+    }
+
+    pub async fn status(&self) -> anyhow::Result<()> {
+        println!("twin in {}", self.output_dir.display());
+        println!("cli config: {}", self.cli_config.display());
+        for n in &self.nodes {
+            let running = n.is_running(&self.node_bin);
+            let ledger = match Client::new(n.api_url.parse()?).get_index().await {
+                Ok(r) => {
+                    let i = r.into_inner();
+                    format!("epoch {} version {}", i.epoch, i.ledger_version)
+                }
+                Err(_) => "api not responding".to_string(),
+            };
+            println!(
+                "{} {} {} {}: {}",
+                n.name,
+                n.account,
+                n.api_url,
+                if running { "running" } else { "stopped" },
+                ledger
+            );
+        }
+        Ok(())
+    }
+}
+
+impl TwinNode {
+    /// the saved pid is still a node_bin process running this node's config
+    pub fn is_running(&self, node_bin: &Path) -> bool {
+        let pid = match self.pid {
+            Some(p) => p,
+            None => return false,
+        };
+        let out = match Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", "args="])
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(o) if o.status.success() => o,
+            _ => return false,
+        };
+        let args = String::from_utf8_lossy(&out.stdout);
+        let bin = node_bin
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        args.contains(&bin) && args.contains(&self.config.display().to_string())
+    }
+}
+
+/// The diem-node binary, from --node-bin or DIEM_FORGE_NODE_BIN_PATH. It is
+/// checked before the swarm starts, and saved as an absolute path since the
+/// twin is started again later, from anywhere.
+fn resolve_node_bin(node_bin: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    let path = match node_bin.or_else(|| std::env::var_os(NODE_BIN_ENV).map(PathBuf::from)) {
+        Some(p) => p,
+        None => bail!("no diem-node binary, set --node-bin or {}", NODE_BIN_ENV),
+    };
+    if !path.is_file() {
+        bail!("the diem-node binary {} does not exist", path.display());
+    }
+    path.canonicalize()
+        .with_context(|| format!("could not resolve {}", path.display()))
+}
+
+#[test]
+fn node_bin_must_exist() {
+    let dir = diem_temppath::TempPath::new();
+    dir.create_as_dir().unwrap();
+    let bin = dir.path().join("diem-node");
+    assert!(resolve_node_bin(Some(bin.clone())).is_err());
+
+    fs::write(&bin, "").unwrap();
+    assert_eq!(
+        resolve_node_bin(Some(bin.clone())).unwrap(),
+        bin.canonicalize().unwrap()
+    );
+}
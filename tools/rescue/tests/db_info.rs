@@ -0,0 +1,40 @@
+use diem_types::account_address::AccountAddress;
+use lotus_smoke_tests::lotus_smoke::LotusSmoke;
+use rescue::db_info::db_info;
+
+#[tokio::test]
+// Scenario: read the diagnostics of the db of a stopped swarm, and compare
+// them to what the api said before it stopped.
+async fn test_db_info() -> anyhow::Result<()> {
+    let mut s = LotusSmoke::new(Some(3), None)
+        .await
+        .expect("could not start lotus smoke");
+    let index = s.client().get_index().await?.into_inner();
+
+    let env = &mut s.swarm;
+    let mut validators: Vec<AccountAddress> = env.validators().map(|v| v.peer_id()).collect();
+    let val_db_path = env.validators().next().unwrap().config().storage.dir();
+    assert!(val_db_path.exists());
+
+    for node in env.validators_mut() {
+        node.stop();
+    }
+
+    let info = db_info(&val_db_path)?;
+    // the node can commit more after the api was read
+    assert!(info.latest_version >= index.ledger_version.0);
+    assert!(info.epoch >= index.epoch.0);
+    assert_eq!(info.chain_id, Some(index.chain_id));
+    assert_eq!(info.num_transactions, info.latest_version + 1);
+    assert_eq!(info.waypoint.version(), info.last_ledger_info.version);
+    assert!(!info.recovery_mode);
+    assert!(!info.framework_packages.is_empty());
+    assert!(!info.column_families.is_empty());
+
+    let mut in_db = info.validators.clone();
+    in_db.sort();
+    validators.sort();
+    assert_eq!(in_db, validators);
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use lotus_smoke_tests::lotus_smoke::LotusSmoke;
+use rescue::epoch_preview::EpochPreviewOpts;
+
+#[tokio::test]
+async fn test_epoch_preview() -> anyhow::Result<()> {
+    let mut s = LotusSmoke::new(Some(3), None)
+        .await
+        .expect("could not start lotus smoke");
+
+    let env = &mut s.swarm;
+
+    let val_db_path = env.validators().next().unwrap().config().storage.dir();
+    assert!(val_db_path.exists());
+
+    for node in env.validators_mut() {
+        node.stop();
+    }
+
+    let opts = EpochPreviewOpts {
+        db_dir: Some(val_db_path),
+        twin: None,
+        json: None,
+    };
+    let preview = opts.run()?;
+    assert_eq!(preview.validators_now.len(), 3);
+
+    // nothing was committed, so the same epoch closes again
+    let again = opts.run()?;
+    assert_eq!(preview.closing_epoch, again.closing_epoch);
+    assert_eq!(preview.next_validators, again.next_validators);
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
 mod support;
 
 use lotus_smoke_tests::lotus_smoke::LotusSmoke;
-use rescue::{diem_db_bootstrapper::BootstrapOpts, rescue_tx::RescueTxOpts};
+use rescue::{
+    diem_db_bootstrapper::BootstrapOpts, rescue_tx::RescueTxOpts, rescue_verify::VerifyOpts,
+};
 
 #[tokio::test]
 async fn test_valid_genesis() -> anyhow::Result<()> {
@@ -41,6 +43,7 @@ async fn test_valid_genesis() -> anyhow::Result<()> {
         script_path: Some(script_path),
         framework_upgrade: false,
         debug_vals: None,
+        plan: None,
     };
     r.run()?;
 
@@ -53,7 +56,7 @@ async fn test_valid_genesis() -> anyhow::Result<()> {
 
     let boot = BootstrapOpts {
         db_dir: val_db_path.clone(),
-        genesis_txn_file: file.clone(),
+        genesis_txn_file: Some(file.clone()),
         waypoint_to_verify: None,
         commit: false,
         info: false,
@@ -64,7 +67,7 @@ async fn test_valid_genesis() -> anyhow::Result<()> {
     println!("4. with the known waypoint confirm it, and apply the tx");
     let boot = BootstrapOpts {
         db_dir: val_db_path,
-        genesis_txn_file: file,
+        genesis_txn_file: Some(file),
         waypoint_to_verify: wp,
         commit: true,
         info: false,
@@ -115,6 +118,7 @@ async fn test_can_build_gov_rescue_script() -> anyhow::Result<()> {
         script_path: Some(script_path),
         framework_upgrade: false,
         debug_vals: None,
+        plan: None,
     };
     r.run()?;
 
@@ -162,6 +166,7 @@ async fn test_valid_waypoint() -> anyhow::Result<()> {
         script_path: Some(script_path),
         framework_upgrade: false,
         debug_vals: None,
+        plan: None,
     };
     r.run()?;
 
@@ -174,7 +179,7 @@ async fn test_valid_waypoint() -> anyhow::Result<()> {
 
     let boot = BootstrapOpts {
         db_dir: val_db_path,
-        genesis_txn_file: file,
+        genesis_txn_file: Some(file),
         waypoint_to_verify: None,
         commit: false,
         info: false,
@@ -184,3 +189,150 @@ async fn test_valid_waypoint() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_plan_blob() -> anyhow::Result<()> {
+    let mut s = LotusSmoke::new(Some(3), None)
+        .await
+        .expect("could not start lotus smoke");
+
+    let env = &mut s.swarm;
+
+    let val_db_path = env.validators().next().unwrap().config().storage.dir();
+    assert!(val_db_path.exists());
+
+    for node in env.validators_mut() {
+        node.stop();
+    }
+
+    println!("1. write a plan which removes a validator and changes the epoch interval");
+
+    let remove_first = env
+        .validators()
+        .next()
+        .unwrap()
+        .config()
+        .get_peer_id()
+        .unwrap();
+
+    let blob_path = diem_temppath::TempPath::new();
+    blob_path.create_as_dir()?;
+    let plan_path = blob_path.path().join("plan.yaml");
+    std::fs::write(
+        &plan_path,
+        format!(
+            r#"
+steps:
+  - set_epoch_interval: {{ microsecs: 600000000 }}
+  - call:
+      function: "0x1::stake::remove_validators"
+      args: [{{ signer: "0x1" }}, {{ vector_address: ["{}"] }}]
+  - reconfigure
+"#,
+            remove_first.to_hex_literal()
+        ),
+    )?;
+
+    println!("2. run the plan in one session");
+
+    let r = RescueTxOpts {
+        data_path: val_db_path.clone(),
+        blob_path: Some(blob_path.path().to_owned()),
+        script_path: None,
+        framework_upgrade: false,
+        debug_vals: None,
+        plan: Some(plan_path),
+    };
+    r.run()?;
+
+    let file = blob_path.path().join("rescue.blob");
+    assert!(file.exists());
+
+    println!("3. check we can apply the tx to existing db, and can get a waypoint");
+
+    let boot = BootstrapOpts {
+        db_dir: val_db_path,
+        genesis_txn_file: Some(file),
+        waypoint_to_verify: None,
+        commit: false,
+        info: false,
+    };
+
+    let wp = boot.run()?;
+    assert!(wp.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_blob() -> anyhow::Result<()> {
+    let mut s = LotusSmoke::new(Some(3), None)
+        .await
+        .expect("could not start lotus smoke");
+
+    let env = &mut s.swarm;
+
+    let val_db_path = env.validators().next().unwrap().config().storage.dir();
+    assert!(val_db_path.exists());
+
+    for node in env.validators_mut() {
+        node.stop();
+    }
+
+    println!("1. make a blob which removes a validator");
+
+    let remove_first = env
+        .validators()
+        .next()
+        .unwrap()
+        .config()
+        .get_peer_id()
+        .unwrap();
+
+    let blob_path = diem_temppath::TempPath::new();
+    blob_path.create_as_dir()?;
+
+    let r = RescueTxOpts {
+        data_path: val_db_path.clone(),
+        blob_path: Some(blob_path.path().to_owned()),
+        script_path: Some(support::make_script(remove_first)),
+        framework_upgrade: false,
+        debug_vals: None,
+        plan: None,
+    };
+    r.run()?;
+    let file = blob_path.path().join("rescue.blob");
+
+    println!("2. dry run the blob and check the report");
+
+    let v = VerifyOpts {
+        db_dir: val_db_path.clone(),
+        blob_path: file.clone(),
+        recovery_mode: false,
+        report: Some(blob_path.path().join("report.json")),
+        full_copy: false,
+    };
+    let report = v.run()?;
+
+    assert!(report.validators_before.contains(&remove_first));
+    assert!(!report.validators_after.contains(&remove_first));
+    assert_eq!(report.validators_after.len(), 2);
+    assert_eq!(report.supply_before, report.supply_after);
+    assert!(report.epoch_after > report.epoch_before);
+    assert!(!report.accounts.is_empty());
+    assert!(blob_path.path().join("report.json").exists());
+
+    println!("3. the db of the node was not changed, the blob still applies");
+
+    let boot = BootstrapOpts {
+        db_dir: val_db_path,
+        genesis_txn_file: Some(file),
+        waypoint_to_verify: None,
+        commit: false,
+        info: false,
+    };
+    let wp = boot.run()?;
+    assert_eq!(wp.map(|w| w.to_string()), Some(report.waypoint));
+
+    Ok(())
+}
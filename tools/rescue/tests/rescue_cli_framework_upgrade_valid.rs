@@ -29,6 +29,7 @@ async fn test_framework_upgrade_writeset() -> anyhow::Result<()> {
         script_path: None,
         framework_upgrade: true,
         debug_vals: None,
+        plan: None,
     };
     r.run()?;
 
@@ -41,7 +42,7 @@ async fn test_framework_upgrade_writeset() -> anyhow::Result<()> {
 
     let boot = BootstrapOpts {
         db_dir: val_db_path.clone(),
-        genesis_txn_file: file.clone(),
+        genesis_txn_file: Some(file.clone()),
         waypoint_to_verify: None,
         commit: false,
         info: false,
@@ -52,7 +53,7 @@ async fn test_framework_upgrade_writeset() -> anyhow::Result<()> {
     println!("4. with the known waypoint confirm it, and apply the tx");
     let boot = BootstrapOpts {
         db_dir: val_db_path,
-        genesis_txn_file: file,
+        genesis_txn_file: Some(file),
         waypoint_to_verify: wp,
         commit: true,
         info: false,
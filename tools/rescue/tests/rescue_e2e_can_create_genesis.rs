@@ -66,6 +66,7 @@ async fn test_create_e2e_rescue_tx() -> anyhow::Result<()> {
         script_path: Some(script_path),
         framework_upgrade: false,
         debug_vals: None,
+        plan: None,
     };
     let genesis_blob_path = rescue.run()?;
 
@@ -89,7 +90,7 @@ async fn test_create_e2e_rescue_tx() -> anyhow::Result<()> {
     println!("6. prepare the waypoint with the transaction");
     let bootstrap = BootstrapOpts {
         db_dir: val_db_path,
-        genesis_txn_file: genesis_blob_path,
+        genesis_txn_file: Some(genesis_blob_path),
         waypoint_to_verify: None,
         commit: false, // NOTE: the tests seem to work even when this is false
         info: false,
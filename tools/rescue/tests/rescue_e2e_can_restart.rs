@@ -66,6 +66,7 @@ async fn test_can_restart() -> anyhow::Result<()> {
         script_path: Some(script_path),
         framework_upgrade: false,
         debug_vals: None,
+        plan: None,
     };
     let genesis_blob_path = rescue.run().unwrap();
 
@@ -83,7 +84,7 @@ async fn test_can_restart() -> anyhow::Result<()> {
     println!("6. check we can get a waypoint generally");
     let bootstrap = BootstrapOpts {
         db_dir: val_db_path,
-        genesis_txn_file: genesis_blob_path.clone(),
+        genesis_txn_file: Some(genesis_blob_path.clone()),
         waypoint_to_verify: None,
         commit: false, // NOT APPLYING THE TX
         info: false,
@@ -106,7 +107,7 @@ async fn test_can_restart() -> anyhow::Result<()> {
         println!("7b. each validator db");
         let bootstrap = BootstrapOpts {
             db_dir: val_db_path,
-            genesis_txn_file: genesis_blob_path.clone(),
+            genesis_txn_file: Some(genesis_blob_path.clone()),
             waypoint_to_verify: None,
             commit: true, // APPLY THE TX
             info: false,
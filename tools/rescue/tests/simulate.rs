@@ -0,0 +1,81 @@
+use diem_types::account_address::AccountAddress;
+use lotus_smoke_tests::lotus_smoke::LotusSmoke;
+use rescue::{
+    rescue_plan::PlanArg,
+    simulate::{simulate, SimStep, SimTxs},
+};
+
+#[tokio::test]
+// Scenario: chain a transfer, a clock change and an epoch boundary on the db
+// of a stopped swarm, and check each step reports its own changes.
+async fn test_simulate_chain() -> anyhow::Result<()> {
+    let mut s = LotusSmoke::new(Some(3), None)
+        .await
+        .expect("could not start lotus smoke");
+
+    let env = &mut s.swarm;
+    let sender = env.validators().next().unwrap().peer_id();
+    let val_db_path = env.validators().next().unwrap().config().storage.dir();
+    assert!(val_db_path.exists());
+
+    for node in env.validators_mut() {
+        node.stop();
+    }
+
+    let recipient = AccountAddress::from_hex_literal("0x1234")?;
+    let txs = SimTxs {
+        steps: vec![
+            SimStep::EntryFunction {
+                sender,
+                function: "0x1::ol_account::transfer".to_string(),
+                args: vec![PlanArg::Address(recipient), PlanArg::U64(100)],
+            },
+            SimStep::AdvanceTime { secs: 3600 },
+            SimStep::NewEpoch,
+        ],
+    };
+    let reports = simulate(&val_db_path, &txs)?;
+    assert_eq!(reports.len(), 3);
+    assert!(reports.iter().all(|r| r.success), "{:?}", reports);
+
+    // the transfer creates the recipient and moves the coins
+    let transfer = &reports[0];
+    assert!(transfer.gas_used > 0);
+    assert!(!transfer.events.is_empty());
+    let recipient_key = recipient.to_string();
+    assert!(transfer
+        .write_set
+        .iter()
+        .any(|w| w.key.starts_with(&recipient_key)));
+    assert!(transfer
+        .write_set
+        .iter()
+        .any(|w| w.key.starts_with(&sender.to_string())));
+
+    // only the clock changes
+    let time = &reports[1];
+    assert!(time.events.is_empty());
+    assert!(time
+        .write_set
+        .iter()
+        .all(|w| w.key.contains("timestamp::CurrentTimeMicroseconds")));
+    assert!(!time.write_set.is_empty());
+
+    // the epoch boundary reconfigures
+    let epoch = &reports[2];
+    assert!(epoch
+        .events
+        .iter()
+        .any(|e| e.type_tag.contains("reconfiguration::NewEpochEvent")));
+    assert!(epoch
+        .write_set
+        .iter()
+        .any(|w| w.key.contains("reconfiguration::Configuration")));
+
+    // nothing was committed, the same chain runs again
+    let again = simulate(&val_db_path, &txs)?;
+    assert_eq!(again.len(), 3);
+    assert!(again.iter().all(|r| r.success));
+
+    Ok(())
+}
@@ -169,6 +169,8 @@ pub async fn upgrade_multiple_impl(
         tx_cost: Some(TxCost::prod_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli.run()
@@ -62,6 +62,7 @@ async fn test_twin() -> anyhow::Result<()> {
         script_path: None,
         framework_upgrade: true,
         debug_vals: Some(vec![first_validator_address]),
+        plan: None,
     };
     r.run()?;
 
@@ -76,7 +77,7 @@ async fn test_twin() -> anyhow::Result<()> {
     println!("3. get waypoint");
     let bootstrap = BootstrapOpts {
         db_dir: brick_db,
-        genesis_txn_file: file.clone(),
+        genesis_txn_file: Some(file.clone()),
         waypoint_to_verify: None,
         commit: false, // NOT APPLYING THE TX
         info: false,
@@ -98,7 +99,7 @@ async fn test_twin() -> anyhow::Result<()> {
         println!("7b. each validator db");
         let bootstrap = BootstrapOpts {
             db_dir: val_db_path,
-            genesis_txn_file: file.clone(),
+            genesis_txn_file: Some(file.clone()),
             waypoint_to_verify: None,
             commit: true, // APPLY THE TX
             info: false,
@@ -0,0 +1,69 @@
+use lotus_smoke_tests::lotus_smoke::LotusSmoke;
+use lotus_types::exports::Client;
+use rescue::twin_network::{TwinNetwork, TWIN_FILE};
+use std::path::PathBuf;
+
+#[tokio::test]
+// Scenario: make a twin network of a swarm db, then check it can be stopped
+// and started again from its output dir.
+async fn test_twin_network_lifecycle() -> anyhow::Result<()> {
+    // LotusSmoke unsets the binary's env var once the swarm is up
+    let node_bin = std::env::var_os("DIEM_FORGE_NODE_BIN_PATH")
+        .map(PathBuf::from)
+        .expect("set DIEM_FORGE_NODE_BIN_PATH to the diem-node binary");
+    let mut s = LotusSmoke::new(Some(3), None)
+        .await
+        .expect("could not start lotus smoke");
+
+    let env = &mut s.swarm;
+    let val_db_path = env.validators().next().unwrap().config().storage.dir();
+    assert!(val_db_path.exists());
+
+    for node in env.validators_mut() {
+        node.stop();
+    }
+
+    let output_dir = diem_temppath::TempPath::new();
+    output_dir.create_as_dir()?;
+
+    println!("1. create the twin, it starts the nodes");
+    let twin = TwinNetwork::create(
+        val_db_path,
+        output_dir.path().to_owned(),
+        2,
+        None,
+        Some(node_bin),
+    )
+    .await?;
+    assert!(output_dir.path().join(TWIN_FILE).exists());
+    assert_eq!(twin.nodes.len(), 2);
+    for n in &twin.nodes {
+        assert!(n.home.starts_with(output_dir.path()));
+        assert!(n.is_running(&twin.node_bin));
+        Client::new(n.api_url.parse()?).get_index().await?;
+    }
+
+    println!("2. status of the twin saved in the output dir");
+    let mut twin = TwinNetwork::read(output_dir.path())?;
+    twin.status().await?;
+
+    println!("3. stop the nodes");
+    twin.stop()?;
+    let stopped = TwinNetwork::read(output_dir.path())?;
+    for n in &stopped.nodes {
+        assert!(n.pid.is_none());
+        assert!(!n.is_running(&stopped.node_bin));
+        assert!(Client::new(n.api_url.parse()?).get_index().await.is_err());
+    }
+
+    println!("4. start them again");
+    let mut twin = TwinNetwork::read(output_dir.path())?;
+    twin.start().await?;
+    for n in &twin.nodes {
+        assert!(n.is_running(&twin.node_bin));
+        Client::new(n.api_url.parse()?).get_index().await?;
+    }
+
+    twin.stop()?;
+    Ok(())
+}
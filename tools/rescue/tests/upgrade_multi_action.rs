@@ -18,7 +18,8 @@ async fn upgrade_multi_action_on_twin_db() -> Result<(), anyhow::Error> {
     // TODO: Download the snapshot from github instead of using a local db copy
     let prod_db_to_clone = PathBuf::from(format!("{}/.lotus/data/db", home_dir));
     assert!(prod_db_to_clone.exists());
-    let (mut swarm, dir) = Twin::apply_with_rando_e2e(prod_db_to_clone, 4)
+    let proxy = env::current_dir()?.join("tests/diem-proxy");
+    let (mut swarm, dir) = Twin::apply_with_rando_e2e(prod_db_to_clone, 4, None, Some(proxy))
         .await
         .unwrap();
     let config_path = dir.path().to_owned().join("lotus-cli-config.yaml");
@@ -37,6 +38,8 @@ async fn upgrade_multi_action_on_twin_db() -> Result<(), anyhow::Error> {
         tx_cost: Some(TxCost::prod_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     // 1.1 create accounts
@@ -47,7 +50,7 @@ async fn upgrade_multi_action_on_twin_db() -> Result<(), anyhow::Error> {
         addresses.iter().zip(swarm.validator_private_keys.iter())
     {
         cli_scaffold.subcommand = Some(Transfer {
-            to_account: *address,
+            to_account: (*address).into(),
             amount: 500.0,
         });
         cli_scaffold.test_private_key = Some(validator_private_key.clone());
@@ -96,7 +99,10 @@ async fn upgrade_multi_action_on_twin_db() -> Result<(), anyhow::Error> {
 
     // 4. Init Community Wallet
     cli_scaffold.subcommand = Some(TxsSub::Community(CommunityTxs::GovInit(InitTx {
-        admins: cw_authorities_addresses.clone(),
+        admins: cw_authorities_addresses
+            .iter()
+            .map(|a| (*a).into())
+            .collect(),
         num_signers: 3,
     })));
     cli_scaffold.test_private_key = Some(cw_pk.clone());
@@ -110,7 +116,7 @@ async fn upgrade_multi_action_on_twin_db() -> Result<(), anyhow::Error> {
     // 5. Claim Offer
     for authority in cw_authorities {
         cli_scaffold.subcommand = Some(TxsSub::Community(CommunityTxs::GovClaim(ClaimTx {
-            community_wallet: cw_address,
+            community_wallet: cw_address.into(),
         })));
         cli_scaffold.test_private_key = Some(
             authority
@@ -1 +1,4 @@
+pub mod read_epochs;
 pub mod read_snapshot;
+pub mod read_transactions;
+pub mod snapshot_diff;
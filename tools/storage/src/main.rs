@@ -3,8 +3,14 @@ use clap::Parser;
 use diem_db_tool::DBTool;
 use diem_logger::{Level, Logger};
 use diem_push_metrics::MetricsPusher;
+use diem_types::{account_address::AccountAddress, transaction::Version, waypoint::Waypoint};
 use std::path::PathBuf;
-use storage::read_snapshot::manifest_to_json;
+use storage::{
+    read_epochs::{epochs_from_backup, write_epochs, EpochsFormat},
+    read_snapshot::manifest_to_json,
+    read_transactions::{export_history, FunctionId, HistoryFilter, HistoryFormat},
+    snapshot_diff::diff_snapshots,
+};
 
 #[derive(Parser)]
 #[clap(name = "lotus storage", author, version)]
@@ -17,6 +23,57 @@ enum StorageCli {
         manifest_path: PathBuf,
         #[clap(short, long)]
         out_path: Option<PathBuf>,
+        #[clap(long)]
+        /// where to sort the accounts, needs about the size of the snapshot
+        /// free. Defaults to next to the backup dir
+        scratch_dir: Option<PathBuf>,
+    },
+    /// List the epochs, waypoints and validator sets of an epoch ending backup
+    Epochs {
+        #[clap(short, long, alias = "manifest")]
+        manifest_path: PathBuf,
+        #[clap(short, long)]
+        /// verify the signatures from this waypoint, else the first epoch of the backup is trusted
+        trusted_waypoint: Option<Waypoint>,
+        #[clap(short, long, value_enum, default_value = "json")]
+        format: EpochsFormat,
+        #[clap(short, long)]
+        /// print to stdout if not set
+        out_path: Option<PathBuf>,
+    },
+    /// Export the transactions of a transaction backup, with their events
+    ExportTransactions {
+        #[clap(short, long, alias = "manifest")]
+        manifest_path: PathBuf,
+        #[clap(short, long)]
+        /// only the transactions sent by this account
+        sender: Option<AccountAddress>,
+        #[clap(long)]
+        /// only the calls to this entry function, e.g. 0x1::ol_account::transfer
+        function: Option<FunctionId>,
+        #[clap(long)]
+        start_version: Option<Version>,
+        #[clap(long)]
+        end_version: Option<Version>,
+        #[clap(short, long, value_enum, default_value = "ndjson")]
+        format: HistoryFormat,
+        #[clap(short, long)]
+        /// print to stdout if not set
+        out_path: Option<PathBuf>,
+    },
+    /// Diff the accounts of two state snapshots, one json line per changed account
+    Diff {
+        /// the state.manifest of the earlier snapshot
+        manifest_a: PathBuf,
+        /// the state.manifest of the later snapshot
+        manifest_b: PathBuf,
+        #[clap(short, long)]
+        /// print to stdout if not set
+        out_path: Option<PathBuf>,
+        #[clap(long)]
+        /// where to sort the accounts of both snapshots, needs about their
+        /// size free. Defaults to next to each backup dir
+        scratch_dir: Option<PathBuf>,
     },
 }
 
@@ -32,12 +89,48 @@ async fn main() -> Result<()> {
         StorageCli::ExportSnapshot {
             manifest_path,
             out_path,
+            scratch_dir,
         } => {
-            manifest_to_json(manifest_path, out_path).await;
+            manifest_to_json(manifest_path, out_path, scratch_dir).await;
+        }
+        StorageCli::Epochs {
+            manifest_path,
+            trusted_waypoint,
+            format,
+            out_path,
+        } => {
+            let records = epochs_from_backup(&manifest_path, trusted_waypoint).await?;
+            write_epochs(&records, format, out_path)?;
+        }
+        StorageCli::ExportTransactions {
+            manifest_path,
+            sender,
+            function,
+            start_version,
+            end_version,
+            format,
+            out_path,
+        } => {
+            let filter = HistoryFilter {
+                sender,
+                function,
+                start_version,
+                end_version,
+            };
+            let count = export_history(&manifest_path, &filter, format, out_path).await?;
+            eprintln!("exported {} transactions", count);
+        }
+        StorageCli::Diff {
+            manifest_a,
+            manifest_b,
+            out_path,
+            scratch_dir,
+        } => {
+            let summary =
+                diff_snapshots(&manifest_a, &manifest_b, out_path, scratch_dir.as_deref()).await?;
+            eprintln!("{}", serde_json::to_string_pretty(&summary)?);
         }
     }
 
-    DBTool::parse().run().await?;
-
     Ok(())
 }
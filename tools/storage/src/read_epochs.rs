@@ -0,0 +1,369 @@
+//! read the epoch ending ledger infos of an epoch ending backup
+use crate::read_snapshot::{load_epoch_manifest, open_for_read};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use diem_backup_cli::{storage::FileHandle, utils::read_record_bytes::ReadRecordBytes};
+use diem_types::{
+    account_address::AccountAddress, epoch_change::Verifier, epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures, transaction::Version, waypoint::Waypoint,
+};
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The output formats of `storage epochs`
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum EpochsFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidatorPower {
+    pub account: AccountAddress,
+    pub voting_power: u64,
+}
+
+/// One epoch ending ledger info of the backup
+#[derive(Debug, Clone, Serialize)]
+pub struct EpochRecord {
+    /// the epoch which this ledger info ends
+    pub epoch: u64,
+    pub version: Version,
+    pub timestamp_usecs: u64,
+    pub waypoint: Waypoint,
+    /// the validator set of the next epoch, which signs its ending ledger info
+    pub next_validators: Vec<ValidatorPower>,
+    /// the signatures chain back to the trusted waypoint
+    pub verified: bool,
+}
+
+/// the csv has one row per epoch, with the validators as `account:power;...`
+#[derive(Serialize)]
+struct EpochCsvRow {
+    epoch: u64,
+    version: Version,
+    timestamp_usecs: u64,
+    waypoint: String,
+    verified: bool,
+    next_validators: String,
+}
+
+/// Read the ledger infos of an epoch ending backup, and verify their signatures.
+/// The chain starts at the ledger info of the trusted waypoint, and each
+/// ledger info is verified by the validators of the one before it. With no
+/// waypoint the chain starts at the first ledger info of the backup, and no
+/// record is marked verified since nothing anchors it.
+pub async fn epochs_from_backup(
+    manifest_path: &Path,
+    trusted_waypoint: Option<Waypoint>,
+) -> Result<Vec<EpochRecord>> {
+    let manifest = load_epoch_manifest(manifest_path)
+        .with_context(|| format!("could not read manifest {}", manifest_path.display()))?;
+    // the file handles in the manifest are relative to the parent of the backup dir
+    let archive_path = manifest_path
+        .parent()
+        .context("the manifest is not in a backup dir")?;
+
+    let mut ledger_infos = vec![];
+    for chunk in manifest.chunks {
+        ledger_infos.extend(read_ledger_info_chunk(chunk.ledger_infos, archive_path).await?);
+    }
+    verify_epochs(&ledger_infos, &manifest.waypoints, trusted_waypoint)
+}
+
+/// check the ledger infos against the waypoints of the manifest, and their
+/// signatures from the trusted waypoint on
+fn verify_epochs(
+    ledger_infos: &[LedgerInfoWithSignatures],
+    waypoints: &[Waypoint],
+    trusted_waypoint: Option<Waypoint>,
+) -> Result<Vec<EpochRecord>> {
+    ensure!(
+        ledger_infos.len() == waypoints.len(),
+        "the manifest has {} waypoints but the backup has {} ledger infos",
+        waypoints.len(),
+        ledger_infos.len()
+    );
+
+    let anchor = match trusted_waypoint {
+        Some(w) => ledger_infos
+            .iter()
+            .position(|li| li.ledger_info().version() == w.version())
+            .map(|i| {
+                w.verify(ledger_infos[i].ledger_info())?;
+                Ok::<_, anyhow::Error>(i)
+            })
+            .transpose()?
+            .with_context(|| format!("no ledger info in the backup at waypoint {}", w))?,
+        None => {
+            eprintln!("WARN: no trusted waypoint, the epochs are checked against the first ledger info of the backup but not marked verified");
+            0
+        }
+    };
+
+    let mut records = vec![];
+    let mut prev_state: Option<&EpochState> = None;
+    for (i, (li, expected)) in ledger_infos.iter().zip(waypoints).enumerate() {
+        let ledger_info = li.ledger_info();
+        let waypoint = Waypoint::new_epoch_boundary(ledger_info)?;
+        ensure!(
+            waypoint == *expected,
+            "epoch {} has waypoint {} but the manifest says {}",
+            ledger_info.epoch(),
+            waypoint,
+            expected
+        );
+
+        if i > anchor {
+            let state = prev_state.context("the ledger info before has no next epoch state")?;
+            state.verify(li).map_err(|e| {
+                anyhow!(
+                    "signatures of epoch {} do not verify: {:?}",
+                    ledger_info.epoch(),
+                    e
+                )
+            })?;
+        }
+        prev_state = ledger_info.next_epoch_state();
+
+        let next_validators = match ledger_info.next_epoch_state() {
+            Some(s) => s
+                .verifier
+                .get_ordered_account_addresses_iter()
+                .map(|account| ValidatorPower {
+                    account,
+                    voting_power: s.verifier.get_voting_power(&account).unwrap_or(0),
+                })
+                .collect(),
+            None => bail!("epoch {} has no next epoch state", ledger_info.epoch()),
+        };
+
+        records.push(EpochRecord {
+            epoch: ledger_info.epoch(),
+            version: ledger_info.version(),
+            timestamp_usecs: ledger_info.timestamp_usecs(),
+            waypoint,
+            next_validators,
+            verified: trusted_waypoint.is_some() && i >= anchor,
+        });
+    }
+    Ok(records)
+}
+
+async fn read_ledger_info_chunk(
+    file_handle: FileHandle,
+    archive_path: &Path,
+) -> Result<Vec<LedgerInfoWithSignatures>> {
+    let full_handle = archive_path
+        .parent()
+        .context("could not read archive path")?
+        .join(file_handle);
+    let handle_str = full_handle.to_str().context("chunk path is not utf8")?;
+    let mut file = open_for_read(handle_str)
+        .await
+        .map_err(|e| anyhow!("epoch ending chunk {:?}, {:?}", &handle_str, e))?;
+
+    let mut chunk = vec![];
+    while let Some(record_bytes) = file.read_record_bytes().await? {
+        chunk.push(bcs::from_bytes(&record_bytes)?);
+    }
+    Ok(chunk)
+}
+
+/// write the records as json or csv, to the file or stdout
+pub fn write_epochs(
+    records: &[EpochRecord],
+    format: EpochsFormat,
+    out_path: Option<PathBuf>,
+) -> Result<()> {
+    let out: Box<dyn Write> = match out_path {
+        Some(p) => Box::new(fs::File::create(&p).with_context(|| format!("{}", p.display()))?),
+        None => Box::new(io::stdout()),
+    };
+    match format {
+        EpochsFormat::Json => serde_json::to_writer_pretty(out, records)?,
+        EpochsFormat::Csv => {
+            let mut w = csv::Writer::from_writer(out);
+            for r in records {
+                w.serialize(EpochCsvRow {
+                    epoch: r.epoch,
+                    version: r.version,
+                    timestamp_usecs: r.timestamp_usecs,
+                    waypoint: r.waypoint.to_string(),
+                    verified: r.verified,
+                    next_validators: r
+                        .next_validators
+                        .iter()
+                        .map(|v| format!("{}:{}", v.account, v.voting_power))
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                })?;
+            }
+            w.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_epochs() {
+    use std::str::FromStr;
+    let this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let manifest_path =
+        this_path.join("fixtures/epoch_ending_79-.166d/epoch_ending.manifest.unzip");
+    let waypoint = Waypoint::from_str(
+        "33217173:632e6be8f7c0e6abccd101deba8aa109ea8ce36186978b5ae4656a510eddc5ea",
+    )
+    .unwrap();
+
+    let records = epochs_from_backup(&manifest_path, Some(waypoint))
+        .await
+        .expect("could not read epochs");
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].epoch, 79);
+    assert_eq!(records[0].version, 33217173);
+    assert_eq!(records[0].waypoint, waypoint);
+    assert!(records[0].verified);
+    assert!(!records[0].next_validators.is_empty());
+}
+
+#[tokio::test]
+async fn test_read_epochs_without_waypoint() {
+    use std::str::FromStr;
+    let this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let manifest_path =
+        this_path.join("fixtures/epoch_ending_79-.166d/epoch_ending.manifest.unzip");
+
+    let records = epochs_from_backup(&manifest_path, None)
+        .await
+        .expect("could not read epochs");
+    assert_eq!(records.len(), 1);
+    assert!(records.iter().all(|r| !r.verified));
+
+    // a waypoint at the right version but of another ledger info
+    let wrong = Waypoint::from_str(
+        "33217173:0000000000000000000000000000000000000000000000000000000000000000",
+    )
+    .unwrap();
+    assert!(epochs_from_backup(&manifest_path, Some(wrong))
+        .await
+        .is_err());
+}
+
+/// ledger infos of epochs 1 to `count`, each signed by the validators of the
+/// one before it. The validators of epoch 3 on are the `rotated` set.
+#[cfg(test)]
+fn signed_epochs(count: u64, rotated: bool) -> Vec<LedgerInfoWithSignatures> {
+    use diem_crypto::{bls12381, hash::HashValue, SigningKey, Uniform};
+    use diem_types::{
+        aggregate_signature::PartialSignatures,
+        block_info::BlockInfo,
+        ledger_info::LedgerInfo,
+        validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::BTreeMap;
+
+    let validator_set = |seed: u8| {
+        (0..3u8)
+            .map(|i| {
+                let key = bls12381::PrivateKey::generate(&mut StdRng::from_seed([seed + i; 32]));
+                (AccountAddress::new([seed + i; 32]), key)
+            })
+            .collect::<Vec<_>>()
+    };
+    let verifier = |set: &[(AccountAddress, bls12381::PrivateKey)]| {
+        ValidatorVerifier::new(
+            set.iter()
+                .map(|(a, k)| ValidatorConsensusInfo::new(*a, bls12381::PublicKey::from(k), 1))
+                .collect(),
+        )
+    };
+    let first = validator_set(1);
+    let second = validator_set(10);
+
+    let mut signers = &first;
+    let mut ledger_infos = vec![];
+    for epoch in 1..=count {
+        let next = if rotated && epoch >= 2 {
+            &second
+        } else {
+            &first
+        };
+        let li = LedgerInfo::new(
+            BlockInfo::new(
+                epoch,
+                0,
+                HashValue::zero(),
+                HashValue::zero(),
+                epoch * 10,
+                epoch * 1_000_000,
+                Some(EpochState {
+                    epoch: epoch + 1,
+                    verifier: verifier(next),
+                }),
+            ),
+            HashValue::zero(),
+        );
+        let signatures: BTreeMap<_, _> = signers
+            .iter()
+            .map(|(a, k)| (*a, k.sign(&li).unwrap()))
+            .collect();
+        let aggregated = verifier(signers)
+            .aggregate_signatures(&PartialSignatures::new(signatures))
+            .unwrap();
+        ledger_infos.push(LedgerInfoWithSignatures::new(li, aggregated));
+        signers = next;
+    }
+    ledger_infos
+}
+
+#[cfg(test)]
+fn waypoints(ledger_infos: &[LedgerInfoWithSignatures]) -> Vec<Waypoint> {
+    ledger_infos
+        .iter()
+        .map(|li| Waypoint::new_epoch_boundary(li.ledger_info()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_verify_epoch_transitions() {
+    let ledger_infos = signed_epochs(4, true);
+    let wps = waypoints(&ledger_infos);
+
+    // every transition after the trusted waypoint is verified
+    let records = verify_epochs(&ledger_infos, &wps, Some(wps[0])).unwrap();
+    assert_eq!(records.len(), 4);
+    assert!(records.iter().all(|r| r.verified));
+    assert_eq!(records[3].epoch, 4);
+    assert_eq!(records[3].next_validators.len(), 3);
+
+    // the epochs before the trusted waypoint are listed but not verified
+    let records = verify_epochs(&ledger_infos, &wps, Some(wps[1])).unwrap();
+    assert!(!records[0].verified);
+    assert!(records[1..].iter().all(|r| r.verified));
+}
+
+#[test]
+fn test_verify_epochs_tampered() {
+    let ledger_infos = signed_epochs(4, true);
+    let wps = waypoints(&ledger_infos);
+
+    // epoch 3 signed by the validators of epoch 2 instead of the new set
+    let mut forged = ledger_infos.clone();
+    forged[2] = signed_epochs(4, false).remove(2);
+    let forged_wps = waypoints(&forged);
+    assert!(verify_epochs(&forged, &forged_wps, Some(forged_wps[0])).is_err());
+
+    // a waypoint of the manifest which does not match its ledger info
+    let mut bad_wps = wps.clone();
+    bad_wps.swap(1, 2);
+    assert!(verify_epochs(&ledger_infos, &bad_wps, Some(wps[0])).is_err());
+
+    // a trusted waypoint which is not in the backup
+    let other = waypoints(&signed_epochs(4, false));
+    assert!(verify_epochs(&ledger_infos, &wps, Some(other[3])).is_err());
+}
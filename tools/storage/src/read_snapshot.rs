@@ -7,6 +7,7 @@ use diem_backup_cli::{
     storage::{FileHandle, FileHandleRef},
     utils::read_record_bytes::ReadRecordBytes,
 };
+use diem_temppath::TempPath;
 use diem_types::{
     account_address::AccountAddress,
     account_state::AccountState,
@@ -16,14 +17,18 @@ use diem_types::{
     },
 };
 use lotus_types::legacy_types::legacy_recovery_v6;
-use serde_json::json;
 use std::{
     collections::HashMap,
-    fs,
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
     path::{Path, PathBuf},
 };
 use tokio::{fs::OpenOptions, io::AsyncRead};
 
+/// the accounts of a snapshot are grouped in this many files, by the first
+/// byte of their address
+pub const ACCOUNT_BUCKETS: usize = 256;
+
 #[cfg(test)]
 use lotus_types::legacy_types::legacy_recovery_v6::{get_legacy_recovery, AccountRole};
 
@@ -54,6 +59,24 @@ pub async fn read_account_state_chunk(
     file_handle: FileHandle,
     archive_path: &Path,
 ) -> Result<Vec<(StateKey, StateValue)>, Error> {
+    let mut chunk = vec![];
+    for_each_record_in_chunk(file_handle, archive_path, |key, value| {
+        chunk.push((key, value));
+        Ok(())
+    })
+    .await?;
+    Ok(chunk)
+}
+
+/// parse a chunk record by record, without holding the chunk in memory
+async fn for_each_record_in_chunk<F>(
+    file_handle: FileHandle,
+    archive_path: &Path,
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(StateKey, StateValue) -> Result<()>,
+{
     let full_handle = archive_path
         .parent()
         .expect("could not read archive path")
@@ -63,64 +86,168 @@ pub async fn read_account_state_chunk(
         .await
         .map_err(|e| anyhow!("snapshot chunk {:?}, {:?}", &handle_str, e))?;
 
-    let mut chunk = vec![];
-
     while let Some(record_bytes) = file.read_record_bytes().await? {
-        chunk.push(bcs::from_bytes(&record_bytes)?);
+        let (key, value) = bcs::from_bytes(&record_bytes)?;
+        f(key, value)?;
     }
-    Ok(chunk)
+    Ok(())
 }
 
-async fn open_for_read(file_handle: &FileHandleRef) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+pub(crate) async fn open_for_read(
+    file_handle: &FileHandleRef,
+) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
     let file = OpenOptions::new().read(true).open(file_handle).await?;
     Ok(Box::new(file))
 }
 
-/// Tokio async parsing of state snapshot into blob
-pub async fn accounts_from_snapshot_backup(
-    manifest: StateSnapshotBackup,
+/// Stream every state item of a snapshot, in the order of the chunks.
+/// Only one record is in memory at a time.
+pub async fn for_each_state_item<F>(
+    manifest: &StateSnapshotBackup,
     archive_path: &Path,
-) -> anyhow::Result<Vec<AccountState>> {
-    // parse AccountStateBlob from chunks of the archive
-    let mut account_states_map: HashMap<AccountAddress, Vec<(StateKey, StateValue)>> =
-        HashMap::new();
-    for chunk in manifest.chunks {
-        let blobs: Vec<(StateKey, StateValue)> =
-            read_account_state_chunk(chunk.blobs, archive_path).await?;
-
-        // Filter out the AccountState blobs
-        // and group by address
-        let account_states_map_chunk: HashMap<AccountAddress, Vec<(StateKey, StateValue)>> = blobs
-            .into_iter()
-            .fold(HashMap::new(), |mut acc, (key, blob)| {
-                if let StateKeyInner::AccessPath(access_path) = key.inner() {
-                    acc.entry(access_path.address)
-                        .or_default()
-                        .push((key, blob));
-                }
-                acc
-            });
-
-        // merge account_states_map_chunk into account_states_map
-        for (address, blobs) in account_states_map_chunk {
-            account_states_map.entry(address).or_default().extend(blobs);
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(StateKey, StateValue) -> Result<()>,
+{
+    for chunk in &manifest.chunks {
+        for_each_record_in_chunk(chunk.blobs.clone(), archive_path, &mut f).await?;
+    }
+    Ok(())
+}
+
+/// The accounts of a snapshot, split into bucket files in a scratch dir by the
+/// last byte of the address, see `bucket_of`.
+/// The chunks are sorted by the hash of the state key, so the resources of an
+/// account are spread over all the chunks. Once bucketed, every account is in
+/// one bucket, and the same account of two snapshots is in the same bucket.
+/// The buckets need about as much free disk as the uncompressed chunks of the
+/// snapshot, so by default they go next to the backup rather than in the
+/// system temp dir, which is often a small tmpfs. The files are deleted on drop.
+pub struct AccountBuckets {
+    temp: TempPath,
+}
+
+impl AccountBuckets {
+    /// stream the snapshot into the bucket files, in a temp dir inside the
+    /// scratch dir, or next to the backup dir if there is none
+    pub async fn from_snapshot(
+        manifest: &StateSnapshotBackup,
+        archive_path: &Path,
+        scratch_dir: Option<&Path>,
+    ) -> Result<Self> {
+        let scratch = scratch_dir
+            .or_else(|| archive_path.parent())
+            .unwrap_or(archive_path);
+        let temp = TempPath::new_with_temp_dir(scratch.to_path_buf());
+        temp.create_as_dir().map_err(|e| {
+            anyhow!(
+                "could not make a scratch dir in {}: {}",
+                scratch.display(),
+                e
+            )
+        })?;
+
+        let mut buckets = (0..ACCOUNT_BUCKETS)
+            .map(|i| Ok(BufWriter::new(File::create(bucket_path(temp.path(), i))?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        for_each_state_item(manifest, archive_path, |key, value| {
+            // only the AccessPath items belong to an account
+            let address = match key.inner() {
+                StateKeyInner::AccessPath(access_path) => access_path.address,
+                _ => return Ok(()),
+            };
+            let bytes = bcs::to_bytes(&(key, value))?;
+            let w = &mut buckets[bucket_of(&address)];
+            w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+            w.write_all(&bytes)?;
+            Ok(())
+        })
+        .await?;
+        for mut w in buckets {
+            w.flush()?;
         }
+        Ok(Self { temp })
     }
-    // materialize account state for each address
-    let mut account_states: Vec<AccountState> = Vec::new();
-    for (address, blobs) in account_states_map {
+
+    /// the accounts of one bucket, memory is bounded by the largest bucket,
+    /// about 1/256 of the snapshot
+    pub fn accounts(&self, bucket: usize) -> Result<HashMap<AccountAddress, AccountState>> {
         #[allow(clippy::mutable_key_type)]
-        let mut blobs_hash_table = HashMap::new();
-        blobs.into_iter().for_each(|(key, blob)| {
-            blobs_hash_table.insert(key, blob);
-        });
-        if let Some(a_state) =
-            AccountState::from_access_paths_and_values(address, &blobs_hash_table)?
-        {
-            account_states.push(a_state);
-        };
+        let mut items: HashMap<AccountAddress, HashMap<StateKey, StateValue>> = HashMap::new();
+        let mut reader = BufReader::new(File::open(bucket_path(self.temp.path(), bucket))?);
+        let mut len = [0u8; 4];
+        loop {
+            match reader.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            }
+            let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+            reader.read_exact(&mut bytes)?;
+            let (key, value): (StateKey, StateValue) = bcs::from_bytes(&bytes)?;
+            let address = match key.inner() {
+                StateKeyInner::AccessPath(access_path) => access_path.address,
+                _ => continue,
+            };
+            items.entry(address).or_default().insert(key, value);
+        }
+
+        // materialize account state for each address
+        let mut accounts = HashMap::new();
+        for (address, blobs) in items {
+            if let Some(a_state) = AccountState::from_access_paths_and_values(address, &blobs)? {
+                accounts.insert(address, a_state);
+            }
+        }
+        Ok(accounts)
     }
+}
 
+/// Stream every account of a snapshot, one bucket of accounts in memory at a
+/// time, see `AccountBuckets` for the disk it needs.
+pub async fn for_each_account<F>(
+    manifest: &StateSnapshotBackup,
+    archive_path: &Path,
+    scratch_dir: Option<&Path>,
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(AccountState) -> Result<()>,
+{
+    let buckets = AccountBuckets::from_snapshot(manifest, archive_path, scratch_dir).await?;
+    for i in 0..ACCOUNT_BUCKETS {
+        for a_state in buckets.accounts(i)?.into_values() {
+            f(a_state)?;
+        }
+    }
+    Ok(())
+}
+
+/// The bucket of an account. Legacy addresses are left-padded with zeros, so
+/// only the last bytes are spread evenly.
+pub fn bucket_of(address: &AccountAddress) -> usize {
+    address[AccountAddress::LENGTH - 1] as usize % ACCOUNT_BUCKETS
+}
+
+fn bucket_path(dir: &Path, i: usize) -> PathBuf {
+    dir.join(format!("{:03}.bucket", i))
+}
+
+/// Tokio async parsing of state snapshot into blob.
+/// This holds every account in memory, use `for_each_account` for large
+/// snapshots.
+pub async fn accounts_from_snapshot_backup(
+    manifest: StateSnapshotBackup,
+    archive_path: &Path,
+) -> anyhow::Result<Vec<AccountState>> {
+    let mut account_states: Vec<AccountState> = Vec::new();
+    for_each_account(&manifest, archive_path, None, |a_state| {
+        account_states.push(a_state);
+        Ok(())
+    })
+    .await?;
     Ok(account_states)
 }
 
@@ -133,23 +260,38 @@ fn test_parse_manifest() {
     // dbg!(&r.epoch);
 }
 
-pub async fn manifest_to_json(manifest_path: PathBuf, out_path: Option<PathBuf>) {
+/// write the recovery json of a snapshot one account at a time
+pub async fn manifest_to_json(
+    manifest_path: PathBuf,
+    out_path: Option<PathBuf>,
+    scratch_dir: Option<PathBuf>,
+) {
     let snapshot_manifest = load_snapshot_manifest(&manifest_path).expect("parse manifest");
     let archive_path = manifest_path.parent().unwrap();
-    let account_states = accounts_from_snapshot_backup(snapshot_manifest, archive_path)
-        .await
-        .expect("could not parse snapshot");
-    let mut legacy_recovery_vec = Vec::new();
-    for account_state in account_states.iter() {
-        let legacy_recovery = legacy_recovery_v6::get_legacy_recovery(account_state)
-            .expect("could not get legacy recovery");
-
-        legacy_recovery_vec.push(legacy_recovery);
-    }
-
-    let json = json!(&legacy_recovery_vec);
     let out = out_path.unwrap_or(manifest_path.parent().unwrap().join("migration.json"));
-    fs::write(out, json.to_string()).expect("could not save file");
+    let mut w = BufWriter::new(File::create(out).expect("could not save file"));
+
+    w.write_all(b"[").expect("could not save file");
+    let mut first = true;
+    for_each_account(
+        &snapshot_manifest,
+        archive_path,
+        scratch_dir.as_deref(),
+        |account_state| {
+            let legacy_recovery = legacy_recovery_v6::get_legacy_recovery(&account_state)
+                .expect("could not get legacy recovery");
+            if !first {
+                w.write_all(b",")?;
+            }
+            first = false;
+            serde_json::to_writer(&mut w, &legacy_recovery)?;
+            Ok(())
+        },
+    )
+    .await
+    .expect("could not parse snapshot");
+    w.write_all(b"]").expect("could not save file");
+    w.flush().expect("could not save file");
 }
 
 #[tokio::test]
@@ -158,7 +300,63 @@ async fn test_export() {
     let this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
     let manifest_path = this_path.join("fixtures/state_epoch_79_ver_33217173.795d/state.manifest");
     let export_path = this_path.join("json/v6_migration.json");
-    manifest_to_json(manifest_path, Some(export_path)).await;
+    manifest_to_json(manifest_path, Some(export_path), None).await;
+}
+
+#[tokio::test]
+async fn test_stream_accounts() {
+    use diem_types::account_view::AccountView;
+    use std::str::FromStr;
+    let mut this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+    this_path.push("fixtures/state_epoch_79_ver_33217173.795d/state.manifest");
+    let snapshot_manifest = load_snapshot_manifest(&this_path).expect("parse manifest");
+    let archive_path = this_path.parent().unwrap();
+
+    let mut items = 0;
+    for_each_state_item(&snapshot_manifest, archive_path, |_, _| {
+        items += 1;
+        Ok(())
+    })
+    .await
+    .expect("could not stream state items");
+    assert!(items > 0);
+
+    // every account is seen once, though its resources are in many chunks
+    let mut addresses = std::collections::HashSet::new();
+    for_each_account(&snapshot_manifest, archive_path, None, |a_state| {
+        let address = a_state.get_account_address()?.expect("no address");
+        assert!(addresses.insert(address));
+        Ok(())
+    })
+    .await
+    .expect("could not stream accounts");
+    assert_eq!(addresses.len(), 23634);
+}
+
+#[tokio::test]
+async fn test_buckets_spread() {
+    use std::str::FromStr;
+    let mut this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+    this_path.push("fixtures/state_epoch_79_ver_33217173.795d/state.manifest");
+    let snapshot_manifest = load_snapshot_manifest(&this_path).expect("parse manifest");
+    let archive_path = this_path.parent().unwrap();
+
+    let buckets = AccountBuckets::from_snapshot(&snapshot_manifest, archive_path, None)
+        .await
+        .expect("could not bucket the snapshot");
+    let mut sizes = vec![];
+    for i in 0..ACCOUNT_BUCKETS {
+        let accounts = buckets.accounts(i).unwrap();
+        assert!(accounts.keys().all(|a| bucket_of(a) == i));
+        sizes.push(accounts.len());
+    }
+    assert_eq!(sizes.iter().sum::<usize>(), 23634);
+
+    // about 92 accounts a bucket, none holds a large part of the snapshot
+    let used = sizes.iter().filter(|n| **n > 0).count();
+    let largest = *sizes.iter().max().unwrap();
+    assert!(used > 200, "only {} buckets are used", used);
+    assert!(largest < 23634 / 50, "a bucket has {} accounts", largest);
 }
 
 #[tokio::test]
@@ -0,0 +1,527 @@
+//! read the transactions of a transaction backup, and export their history
+use crate::read_snapshot::open_for_read;
+use anyhow::{anyhow, bail, Context, Result};
+use diem_backup_cli::{
+    backup_types::transaction::manifest::TransactionBackup, storage::FileHandle,
+    utils::read_record_bytes::ReadRecordBytes,
+};
+use diem_types::{
+    account_address::AccountAddress,
+    contract_event::ContractEvent,
+    transaction::{Transaction, TransactionInfo, TransactionPayload, Version},
+    write_set::WriteSet,
+};
+use lotus_framework::entry_args::{decode_entry_args, function_id};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The output formats of `storage export-transactions`
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum HistoryFormat {
+    /// one json object per line
+    Ndjson,
+    Csv,
+}
+
+/// An entry function, e.g. 0x1::ol_account::transfer
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionId {
+    pub address: AccountAddress,
+    pub module: String,
+    pub function: String,
+}
+
+impl FromStr for FunctionId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split("::").collect();
+        match parts.as_slice() {
+            [address, module, function] => Ok(Self {
+                address: AccountAddress::from_hex_literal(address)
+                    .map_err(|e| anyhow!("{} is not an address: {}", address, e))?,
+                module: module.to_string(),
+                function: function.to_string(),
+            }),
+            _ => bail!(
+                "{} is not a function, use <address>::<module>::<function>",
+                s
+            ),
+        }
+    }
+}
+
+/// Which transactions to export, everything by default
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub sender: Option<AccountAddress>,
+    pub function: Option<FunctionId>,
+    pub start_version: Option<Version>,
+    pub end_version: Option<Version>,
+}
+
+impl HistoryFilter {
+    fn in_range(&self, first: Version, last: Version) -> bool {
+        self.start_version.map(|s| last >= s).unwrap_or(true)
+            && self.end_version.map(|e| first <= e).unwrap_or(true)
+    }
+
+    fn matches(&self, record: &TxRecord) -> bool {
+        if !self.in_range(record.version, record.version) {
+            return false;
+        }
+        if let Some(s) = self.sender {
+            if record.sender != Some(s) {
+                return false;
+            }
+        }
+        match &self.function {
+            Some(f) => record.function_id.as_ref() == Some(f),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TxEvent {
+    pub type_tag: String,
+    /// bcs of the event, hex encoded
+    pub data: String,
+}
+
+/// One transaction of the backup, with its outcome
+#[derive(Debug, Clone, Serialize)]
+pub struct TxRecord {
+    pub version: Version,
+    pub hash: String,
+    /// user, genesis, block_metadata or state_checkpoint
+    pub kind: String,
+    /// of the last block metadata before this transaction in the backup
+    pub timestamp_usecs: Option<u64>,
+    pub sender: Option<AccountAddress>,
+    pub sequence_number: Option<u64>,
+    pub function: Option<String>,
+    #[serde(skip)]
+    pub function_id: Option<FunctionId>,
+    pub type_args: Vec<String>,
+    /// bcs of each argument, hex encoded
+    pub args: Vec<String>,
+    /// the type and value of each argument, for the framework's entry functions
+    pub decoded_args: Option<Value>,
+    pub success: bool,
+    pub status: String,
+    pub gas_used: u64,
+    pub events: Vec<TxEvent>,
+}
+
+/// the csv has one row per transaction, lists are joined with `;`
+#[derive(Serialize)]
+struct TxCsvRow<'a> {
+    version: Version,
+    hash: &'a str,
+    kind: &'a str,
+    timestamp_usecs: Option<u64>,
+    sender: Option<String>,
+    sequence_number: Option<u64>,
+    function: Option<&'a str>,
+    type_args: String,
+    args: String,
+    /// json array of the typed arguments
+    decoded_args: Option<String>,
+    success: bool,
+    status: &'a str,
+    gas_used: u64,
+    events: String,
+}
+
+/// read transaction backup manifest file into object
+pub fn load_transaction_manifest(path: &Path) -> Result<TransactionBackup> {
+    let s = fs::read_to_string(path)
+        .with_context(|| format!("could not read manifest {}", path.display()))?;
+    Ok(serde_json::from_str(&s)?)
+}
+
+/// Stream the transactions of a backup in the version range of the filter.
+/// Only one record is in memory at a time, chunks outside of the range are
+/// not read.
+pub async fn for_each_transaction<F>(
+    manifest: &TransactionBackup,
+    archive_path: &Path,
+    filter: &HistoryFilter,
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(TxRecord) -> Result<()>,
+{
+    let mut timestamp_usecs = None;
+    for chunk in &manifest.chunks {
+        if !filter.in_range(chunk.first_version, chunk.last_version) {
+            continue;
+        }
+        let mut version = chunk.first_version;
+        let mut file = open_chunk(chunk.transactions.clone(), archive_path).await?;
+        while let Some(record_bytes) = file.read_record_bytes().await? {
+            let (txn, info, events, _write_set): (
+                Transaction,
+                TransactionInfo,
+                Vec<ContractEvent>,
+                WriteSet,
+            ) = bcs::from_bytes(&record_bytes)
+                .with_context(|| format!("could not decode transaction {}", version))?;
+            if let Transaction::BlockMetadata(b) = &txn {
+                timestamp_usecs = Some(b.timestamp_usecs());
+            }
+            let record = tx_record(version, &txn, &info, &events, timestamp_usecs);
+            if filter.matches(&record) {
+                f(record)?;
+            }
+            version += 1;
+        }
+    }
+    Ok(())
+}
+
+async fn open_chunk(
+    file_handle: FileHandle,
+    archive_path: &Path,
+) -> Result<Box<dyn tokio::io::AsyncRead + Send + Unpin>> {
+    let full_handle = archive_path
+        .parent()
+        .context("could not read archive path")?
+        .join(file_handle);
+    let handle_str = full_handle.to_str().context("chunk path is not utf8")?;
+    open_for_read(handle_str)
+        .await
+        .map_err(|e| anyhow!("transaction chunk {:?}, {:?}", &handle_str, e))
+}
+
+fn tx_record(
+    version: Version,
+    txn: &Transaction,
+    info: &TransactionInfo,
+    events: &[ContractEvent],
+    timestamp_usecs: Option<u64>,
+) -> TxRecord {
+    let mut record = TxRecord {
+        version,
+        hash: info.transaction_hash().to_hex_literal(),
+        kind: match txn {
+            Transaction::UserTransaction(_) => "user",
+            Transaction::GenesisTransaction(_) => "genesis",
+            Transaction::BlockMetadata(_) => "block_metadata",
+            Transaction::StateCheckpoint(_) => "state_checkpoint",
+        }
+        .to_string(),
+        timestamp_usecs,
+        sender: None,
+        sequence_number: None,
+        function: None,
+        function_id: None,
+        type_args: vec![],
+        args: vec![],
+        decoded_args: None,
+        success: info.status().is_success(),
+        status: format!("{:?}", info.status()),
+        gas_used: info.gas_used(),
+        events: events
+            .iter()
+            .map(|e| TxEvent {
+                type_tag: e.type_tag().to_string(),
+                data: hex::encode(e.event_data()),
+            })
+            .collect(),
+    };
+
+    if let Transaction::UserTransaction(signed) = txn {
+        record.sender = Some(signed.sender());
+        record.sequence_number = Some(signed.sequence_number());
+        if let TransactionPayload::EntryFunction(entry) = signed.payload() {
+            let id = FunctionId {
+                address: *entry.module().address(),
+                module: entry.module().name().to_string(),
+                function: entry.function().to_string(),
+            };
+            record.function = Some(function_id(entry));
+            record.function_id = Some(id);
+            record.type_args = entry.ty_args().iter().map(|t| t.to_string()).collect();
+            record.args = entry.args().iter().map(hex::encode).collect();
+            record.decoded_args = decode_entry_args(entry).ok();
+        }
+    }
+    record
+}
+
+/// Export the history of a transaction backup as ndjson or csv, to the file
+/// or stdout. Returns the number of transactions exported.
+pub async fn export_history(
+    manifest_path: &Path,
+    filter: &HistoryFilter,
+    format: HistoryFormat,
+    out_path: Option<PathBuf>,
+) -> Result<u64> {
+    let manifest = load_transaction_manifest(manifest_path)?;
+    // the file handles in the manifest are relative to the parent of the backup dir
+    let archive_path = manifest_path
+        .parent()
+        .context("the manifest is not in a backup dir")?;
+
+    let out: Box<dyn Write> = match out_path {
+        Some(p) => Box::new(fs::File::create(&p).with_context(|| format!("{}", p.display()))?),
+        None => Box::new(io::stdout()),
+    };
+
+    let mut count = 0;
+    match format {
+        HistoryFormat::Ndjson => {
+            let mut w = BufWriter::new(out);
+            for_each_transaction(&manifest, archive_path, filter, |record| {
+                serde_json::to_writer(&mut w, &record)?;
+                w.write_all(b"\n")?;
+                count += 1;
+                Ok(())
+            })
+            .await?;
+            w.flush()?;
+        }
+        HistoryFormat::Csv => {
+            let mut w = csv::Writer::from_writer(out);
+            for_each_transaction(&manifest, archive_path, filter, |r| {
+                w.serialize(TxCsvRow {
+                    version: r.version,
+                    hash: &r.hash,
+                    kind: &r.kind,
+                    timestamp_usecs: r.timestamp_usecs,
+                    sender: r.sender.map(|s| s.to_hex_literal()),
+                    sequence_number: r.sequence_number,
+                    function: r.function.as_deref(),
+                    type_args: r.type_args.join(";"),
+                    args: r.args.join(";"),
+                    decoded_args: r.decoded_args.as_ref().map(|a| a.to_string()),
+                    success: r.success,
+                    status: &r.status,
+                    gas_used: r.gas_used,
+                    events: r
+                        .events
+                        .iter()
+                        .map(|e| e.type_tag.as_str())
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                })?;
+                count += 1;
+                Ok(())
+            })
+            .await?;
+            w.flush()?;
+        }
+    }
+    Ok(count)
+}
+
+#[test]
+fn test_history_filter() {
+    let f: FunctionId = "0x1::ol_account::transfer".parse().unwrap();
+    assert_eq!(f.address, AccountAddress::ONE);
+    assert_eq!(f.module, "ol_account");
+    assert!("0x1::ol_account".parse::<FunctionId>().is_err());
+
+    let filter = HistoryFilter {
+        start_version: Some(10),
+        end_version: Some(20),
+        ..Default::default()
+    };
+    assert!(filter.in_range(0, 10));
+    assert!(filter.in_range(15, 30));
+    assert!(!filter.in_range(0, 9));
+    assert!(!filter.in_range(21, 30));
+}
+
+#[tokio::test]
+async fn test_export_history() {
+    let backup = diem_temppath::TempPath::new();
+    backup.create_as_dir().unwrap();
+    let manifest_path = write_transaction_backup(backup.path());
+    let alice = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+    let bob = AccountAddress::from_hex_literal("0xb0b").unwrap();
+
+    // every transaction, decoded
+    let out = diem_temppath::TempPath::new();
+    let count = export_history(
+        &manifest_path,
+        &HistoryFilter::default(),
+        HistoryFormat::Ndjson,
+        Some(out.path().to_owned()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(count, 4);
+    let records: Vec<serde_json::Value> = fs::read_to_string(out.path())
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0]["kind"], "block_metadata");
+    assert_eq!(records[1]["kind"], "user");
+    assert_eq!(records[1]["version"], 101);
+    assert_eq!(records[1]["timestamp_usecs"], 5_000_000);
+    assert_eq!(records[1]["sender"], alice.to_hex_literal());
+    assert_eq!(records[1]["function"], "0x1::ol_account::transfer");
+    assert_eq!(
+        records[1]["decoded_args"],
+        serde_json::json!([
+            { "type": "address", "value": bob.to_hex_literal() },
+            { "type": "u64", "value": "100" },
+        ])
+    );
+    assert_eq!(records[2]["decoded_args"], serde_json::json!([]));
+
+    // by sender and function
+    let count_with = |filter: HistoryFilter| {
+        let manifest_path = manifest_path.clone();
+        async move {
+            let out = diem_temppath::TempPath::new();
+            export_history(
+                &manifest_path,
+                &filter,
+                HistoryFormat::Ndjson,
+                Some(out.path().to_owned()),
+            )
+            .await
+            .unwrap()
+        }
+    };
+    let transfer: FunctionId = "0x1::ol_account::transfer".parse().unwrap();
+    let by_alice = HistoryFilter {
+        sender: Some(alice),
+        ..Default::default()
+    };
+    let transfers = HistoryFilter {
+        function: Some(transfer.clone()),
+        ..Default::default()
+    };
+    let alice_transfers = HistoryFilter {
+        sender: Some(alice),
+        function: Some(transfer),
+        ..Default::default()
+    };
+    let first_two = HistoryFilter {
+        end_version: Some(101),
+        ..Default::default()
+    };
+    assert_eq!(count_with(by_alice).await, 2);
+    assert_eq!(count_with(transfers).await, 2);
+    assert_eq!(count_with(alice_transfers.clone()).await, 1);
+    assert_eq!(count_with(first_two).await, 2);
+
+    // csv
+    let out = diem_temppath::TempPath::new();
+    let count = export_history(
+        &manifest_path,
+        &alice_transfers,
+        HistoryFormat::Csv,
+        Some(out.path().to_owned()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(count, 1);
+    let mut reader = csv::Reader::from_path(out.path()).unwrap();
+    let headers = reader.headers().unwrap().clone();
+    let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+    assert_eq!(rows.len(), 1);
+    let column = |name: &str| {
+        let i = headers.iter().position(|h| h == name).unwrap();
+        rows[0][i].to_string()
+    };
+    assert_eq!(column("version"), "101");
+    assert_eq!(column("sender"), alice.to_hex_literal());
+    assert_eq!(column("function"), "0x1::ol_account::transfer");
+    let args: serde_json::Value = serde_json::from_str(&column("decoded_args")).unwrap();
+    assert_eq!(args[1]["value"], "100");
+}
+
+/// A transaction backup of versions 100 to 103 in `dir`: a block, a transfer
+/// from 0xa11ce to 0xb0b, a bid by 0xa11ce and a transfer from 0xb0b.
+/// Returns the path of the manifest.
+#[cfg(test)]
+fn write_transaction_backup(dir: &Path) -> PathBuf {
+    use diem_backup_cli::backup_types::transaction::manifest::TransactionChunk;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, hash::HashValue, PrivateKey, Uniform};
+    use diem_types::{
+        block_metadata::BlockMetadata,
+        chain_id::ChainId,
+        transaction::{ExecutionStatus, RawTransaction},
+    };
+    use lotus_cached_packages::lotus_stdlib;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let alice = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+    let bob = AccountAddress::from_hex_literal("0xb0b").unwrap();
+    let key = Ed25519PrivateKey::generate(&mut StdRng::from_seed([1; 32]));
+    let user_tx = |sender, sequence_number, payload| {
+        let raw = RawTransaction::new(
+            sender,
+            sequence_number,
+            payload,
+            1_000,
+            100,
+            10,
+            ChainId::test(),
+        );
+        Transaction::UserTransaction(raw.sign(&key, key.public_key()).unwrap().into_inner())
+    };
+    let transactions = vec![
+        Transaction::BlockMetadata(BlockMetadata::new(
+            HashValue::zero(),
+            1,
+            1,
+            bob,
+            vec![],
+            vec![],
+            5_000_000,
+        )),
+        user_tx(alice, 0, lotus_stdlib::ol_account_transfer(bob, 100)),
+        user_tx(alice, 1, lotus_stdlib::proof_of_fee_init_bidding()),
+        user_tx(bob, 0, lotus_stdlib::ol_account_transfer(alice, 5)),
+    ];
+
+    // records are bcs, each prefixed with its length as a big endian u32
+    let mut chunk = vec![];
+    for (i, txn) in transactions.into_iter().enumerate() {
+        let info = TransactionInfo::new(
+            HashValue::sha3_256_of(&[i as u8]),
+            HashValue::zero(),
+            HashValue::zero(),
+            None,
+            10,
+            ExecutionStatus::Success,
+        );
+        let events: Vec<ContractEvent> = vec![];
+        let record = bcs::to_bytes(&(txn, info, events, WriteSet::default())).unwrap();
+        chunk.extend((record.len() as u32).to_be_bytes());
+        chunk.extend(record);
+    }
+
+    // file handles are relative to the parent of the backup dir
+    let backup_dir = dir.join("transaction_100-.0001");
+    fs::create_dir_all(&backup_dir).unwrap();
+    fs::write(backup_dir.join("100-.chunk"), chunk).unwrap();
+    let manifest = TransactionBackup {
+        first_version: 100,
+        last_version: 103,
+        chunks: vec![TransactionChunk {
+            first_version: 100,
+            last_version: 103,
+            transactions: "transaction_100-.0001/100-.chunk".to_string(),
+            proof: "transaction_100-.0001/100-.proof".to_string(),
+        }],
+    };
+    let manifest_path = backup_dir.join("transaction.manifest");
+    fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+    manifest_path
+}
@@ -0,0 +1,318 @@
+//! diff the accounts of two state snapshots, e.g. before and after a hard fork
+#[cfg(test)]
+use crate::read_snapshot::bucket_of;
+use crate::read_snapshot::{load_snapshot_manifest, AccountBuckets, ACCOUNT_BUCKETS};
+use anyhow::{Context, Result};
+use diem_types::{
+    access_path::Path as AccessPathKind, account_address::AccountAddress,
+    account_state::AccountState,
+};
+use lotus_types::legacy_types::legacy_recovery_v6::{get_legacy_recovery, LegacyRecoveryV6};
+use serde::Serialize;
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountChange {
+    Created,
+    Removed,
+    Changed,
+}
+
+/// What changed in one account from snapshot a to snapshot b
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDiff {
+    pub account: AccountAddress,
+    pub change: AccountChange,
+    pub balance_before: Option<u64>,
+    pub balance_after: Option<u64>,
+    pub balance_delta: i128,
+    pub slow_unlocked_before: Option<u64>,
+    pub slow_unlocked_after: Option<u64>,
+    pub validator_config_changed: bool,
+    pub vouches_added: Vec<AccountAddress>,
+    pub vouches_removed: Vec<AccountAddress>,
+    pub resources_added: Vec<String>,
+    pub resources_removed: Vec<String>,
+}
+
+impl AccountDiff {
+    fn is_empty(&self) -> bool {
+        self.change == AccountChange::Changed
+            && self.balance_delta == 0
+            && self.slow_unlocked_before == self.slow_unlocked_after
+            && !self.validator_config_changed
+            && self.vouches_added.is_empty()
+            && self.vouches_removed.is_empty()
+            && self.resources_added.is_empty()
+            && self.resources_removed.is_empty()
+    }
+}
+
+/// The coins in the accounts of a snapshot
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SupplyTotals {
+    pub accounts: u64,
+    pub total: u128,
+    pub slow_total: u128,
+    pub slow_unlocked: u128,
+    pub slow_locked: u128,
+    /// balances of the accounts with a validator config
+    pub validator: u128,
+}
+
+impl SupplyTotals {
+    fn add(&mut self, r: &LegacyRecoveryV6) {
+        let balance = r.balance.as_ref().map(|b| b.coin).unwrap_or(0) as u128;
+        self.accounts += 1;
+        self.total += balance;
+        if let Some(s) = &r.slow_wallet {
+            // the unlocked can be more than the balance after transfers out
+            let unlocked = balance.min(s.unlocked as u128);
+            self.slow_total += balance;
+            self.slow_unlocked += unlocked;
+            self.slow_locked += balance - unlocked;
+        }
+        if r.val_cfg.is_some() {
+            self.validator += balance;
+        }
+    }
+}
+
+/// An account which could not be converted in one of the snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionError {
+    pub account: AccountAddress,
+    /// a or b
+    pub snapshot: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffSummary {
+    pub created: u64,
+    pub removed: u64,
+    pub changed: u64,
+    pub supply_a: SupplyTotals,
+    pub supply_b: SupplyTotals,
+    /// these accounts are left out of the diff and of both supplies
+    pub errors: Vec<ConversionError>,
+}
+
+/// Diff two snapshots and write one json line per changed account, to the
+/// file or stdout.
+/// Both snapshots are split into the same address buckets, so only one
+/// bucket of each, about 1/256 of the accounts, is in memory at a time, and
+/// the diffs are written as each bucket is done.
+pub async fn diff_snapshots(
+    manifest_a: &Path,
+    manifest_b: &Path,
+    out_path: Option<PathBuf>,
+    scratch_dir: Option<&Path>,
+) -> Result<DiffSummary> {
+    let buckets_a = bucket_snapshot(manifest_a, scratch_dir).await?;
+    let buckets_b = bucket_snapshot(manifest_b, scratch_dir).await?;
+
+    let out: Box<dyn Write> = match out_path {
+        Some(p) => Box::new(fs::File::create(&p).with_context(|| format!("{}", p.display()))?),
+        None => Box::new(io::stdout()),
+    };
+    let mut w = BufWriter::new(out);
+
+    let mut summary = DiffSummary::default();
+    for i in 0..ACCOUNT_BUCKETS {
+        let mut a = buckets_a.accounts(i)?;
+        let mut b = buckets_b.accounts(i)?;
+        let addresses: BTreeSet<AccountAddress> = a.keys().chain(b.keys()).copied().collect();
+        for address in addresses {
+            let before = a.remove(&address);
+            let after = b.remove(&address);
+            let diff = match diff_account(address, before.as_ref(), after.as_ref(), &mut summary)? {
+                Some(d) if !d.is_empty() => d,
+                _ => continue,
+            };
+            match diff.change {
+                AccountChange::Created => summary.created += 1,
+                AccountChange::Removed => summary.removed += 1,
+                AccountChange::Changed => summary.changed += 1,
+            }
+            serde_json::to_writer(&mut w, &diff)?;
+            w.write_all(b"\n")?;
+        }
+    }
+    w.flush()?;
+    Ok(summary)
+}
+
+async fn bucket_snapshot(
+    manifest_path: &Path,
+    scratch_dir: Option<&Path>,
+) -> Result<AccountBuckets> {
+    let manifest = load_snapshot_manifest(&manifest_path.to_path_buf())
+        .with_context(|| format!("could not read manifest {}", manifest_path.display()))?;
+    let archive_path = manifest_path
+        .parent()
+        .context("the manifest is not in a backup dir")?;
+    AccountBuckets::from_snapshot(&manifest, archive_path, scratch_dir).await
+}
+
+/// None if the account could not be converted in either snapshot, the error
+/// is added to the summary
+fn diff_account(
+    address: AccountAddress,
+    before: Option<&AccountState>,
+    after: Option<&AccountState>,
+    summary: &mut DiffSummary,
+) -> Result<Option<AccountDiff>> {
+    let legacy = |s: Option<&AccountState>| s.map(get_legacy_recovery).transpose();
+    let (rec_a, rec_b) = match (legacy(before), legacy(after)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (a, b) => {
+            for (snapshot, e) in [("a", a.err()), ("b", b.err())] {
+                if let Some(e) = e {
+                    summary.errors.push(ConversionError {
+                        account: address,
+                        snapshot: snapshot.to_string(),
+                        message: format!("{:#}", e),
+                    });
+                }
+            }
+            return Ok(None);
+        }
+    };
+    if let Some(r) = &rec_a {
+        summary.supply_a.add(r);
+    }
+    if let Some(r) = &rec_b {
+        summary.supply_b.add(r);
+    }
+
+    let balance =
+        |r: &Option<LegacyRecoveryV6>| r.as_ref().and_then(|r| r.balance.as_ref()).map(|b| b.coin);
+    let unlocked = |r: &Option<LegacyRecoveryV6>| {
+        r.as_ref()
+            .and_then(|r| r.slow_wallet.as_ref())
+            .map(|s| s.unlocked)
+    };
+    let vouches = |r: &Option<LegacyRecoveryV6>| -> BTreeSet<AccountAddress> {
+        r.as_ref()
+            .and_then(|r| r.my_vouches.as_ref())
+            .map(|v| v.my_buddies.iter().copied().collect())
+            .unwrap_or_default()
+    };
+    let val_cfg = |r: &Option<LegacyRecoveryV6>| -> Result<Option<Vec<u8>>> {
+        Ok(match r.as_ref().and_then(|r| r.val_cfg.as_ref()) {
+            Some(c) => Some(bcs::to_bytes(c)?),
+            None => None,
+        })
+    };
+    let resources_a = resource_names(before);
+    let resources_b = resource_names(after);
+    let vouches_a = vouches(&rec_a);
+    let vouches_b = vouches(&rec_b);
+
+    let balance_before = balance(&rec_a);
+    let balance_after = balance(&rec_b);
+    Ok(Some(AccountDiff {
+        account: address,
+        change: match (before, after) {
+            (None, Some(_)) => AccountChange::Created,
+            (Some(_), None) => AccountChange::Removed,
+            _ => AccountChange::Changed,
+        },
+        balance_before,
+        balance_after,
+        balance_delta: balance_after.unwrap_or(0) as i128 - balance_before.unwrap_or(0) as i128,
+        slow_unlocked_before: unlocked(&rec_a),
+        slow_unlocked_after: unlocked(&rec_b),
+        validator_config_changed: val_cfg(&rec_a)? != val_cfg(&rec_b)?,
+        vouches_added: vouches_b.difference(&vouches_a).copied().collect(),
+        vouches_removed: vouches_a.difference(&vouches_b).copied().collect(),
+        resources_added: resources_b.difference(&resources_a).cloned().collect(),
+        resources_removed: resources_a.difference(&resources_b).cloned().collect(),
+    }))
+}
+
+/// the resources and modules of an account, by type
+fn resource_names(state: Option<&AccountState>) -> BTreeSet<String> {
+    let state = match state {
+        Some(s) => s,
+        None => return BTreeSet::new(),
+    };
+    state
+        .iter()
+        .map(|(path, _)| match bcs::from_bytes::<AccessPathKind>(path) {
+            Ok(AccessPathKind::Resource(tag)) => tag.to_string(),
+            Ok(AccessPathKind::ResourceGroup(tag)) => format!("resource group {}", tag),
+            Ok(AccessPathKind::Code(m)) => format!("module {}", m.name()),
+            Err(_) => hex::encode(path),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_diff_same_snapshot() {
+    use std::str::FromStr;
+    let this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let manifest_path = this_path.join("fixtures/state_epoch_79_ver_33217173.795d/state.manifest");
+    let out = diem_temppath::TempPath::new();
+
+    let summary = diff_snapshots(
+        &manifest_path,
+        &manifest_path,
+        Some(out.path().to_owned()),
+        None,
+    )
+    .await
+    .expect("could not diff snapshots");
+    assert_eq!(summary.created + summary.removed + summary.changed, 0);
+    assert!(summary.errors.is_empty());
+    assert_eq!(summary.supply_a.accounts, 23634);
+    assert_eq!(summary.supply_a.total, summary.supply_b.total);
+    assert!(fs::read_to_string(out.path()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_diff_collects_conversion_errors() {
+    use std::str::FromStr;
+    let this_path = PathBuf::from_str(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let manifest_path = this_path.join("fixtures/state_epoch_79_ver_33217173.795d/state.manifest");
+    let buckets = bucket_snapshot(&manifest_path, None).await.unwrap();
+    let system = AccountAddress::ONE;
+    let accounts = buckets.accounts(bucket_of(&system)).unwrap();
+
+    // every resource of the system account emptied in snapshot b
+    let state = accounts.get(&system).expect("no system account");
+    let broken = AccountState::new(
+        system,
+        state.iter().map(|(k, _)| (k.clone(), vec![])).collect(),
+    );
+    let mut summary = DiffSummary::default();
+    assert!(
+        diff_account(system, Some(state), Some(&broken), &mut summary)
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(summary.errors.len(), 1);
+    assert_eq!(summary.errors[0].account, system);
+    assert_eq!(summary.errors[0].snapshot, "b");
+    assert_eq!(summary.supply_a.accounts, 0);
+
+    // the other accounts are still diffed, e.g. the lowest address of the bucket
+    let (address, state) = accounts
+        .iter()
+        .filter(|(a, _)| **a != system)
+        .min_by_key(|(a, _)| **a)
+        .unwrap();
+    let diff = diff_account(*address, Some(state), Some(state), &mut summary)
+        .unwrap()
+        .unwrap();
+    assert!(diff.is_empty());
+    assert_eq!(summary.errors.len(), 1);
+    assert_eq!(summary.supply_a.accounts, 1);
+}
@@ -1,6 +1,9 @@
 //! Module for managing transaction sending and management with Diem blockchain.
 
-use crate::txs_cli::to_legacy_address;
+use crate::{
+    txs_cli::to_legacy_address,
+    txs_cli_multi_key::{MultiKeyFile, MULTI_KEY_TX_EXPIRY_SECS},
+};
 use anyhow::{bail, Context};
 use diem::common::types::{CliConfig, ConfigSearchMode};
 use diem_logger::prelude::*;
@@ -13,7 +16,7 @@ use diem_sdk::{
     transaction_builder::TransactionBuilder,
     types::{
         chain_id::ChainId,
-        transaction::{ExecutionStatus, SignedTransaction, TransactionPayload},
+        transaction::{ExecutionStatus, RawTransaction, SignedTransaction, TransactionPayload},
         AccountKey, LocalAccount,
     },
 };
@@ -27,7 +30,7 @@ use lotus_types::{
     },
 };
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
 use url::Url;
@@ -39,6 +42,8 @@ pub struct Sender {
     client: Client,
     chain_id: ChainId,
     pub response: Option<TransactionOnChainData>,
+    /// the account is controlled by the multi-ed25519 key in this file, transactions get staged there
+    multi_key_file: Option<PathBuf>,
 }
 
 impl Sender {
@@ -76,9 +81,48 @@ impl Sender {
             local_account,
             chain_id,
             response: None,
+            multi_key_file: None,
+        })
+    }
+
+    /// A sender for an account controlled by a multi-ed25519 key.
+    /// Transactions are staged in the multi-key file instead of being submitted,
+    /// the `key_holder` signs first if it is one of the keys.
+    pub async fn from_multi_key_file(
+        multi_key_file: PathBuf,
+        key_holder: AccountKey,
+        chain_id: ChainId,
+        client_opt: Option<Client>,
+    ) -> anyhow::Result<Self> {
+        let client = match client_opt {
+            Some(c) => c,
+            None => Client::default().await?,
+        };
+
+        let multi_key = MultiKeyFile::read(&multi_key_file)?;
+        let address = client
+            .lookup_originating_address(multi_key.auth_key()?)
+            .await?;
+        info!("using multi-key address {}", &address);
+
+        let seq = client.get_sequence_number(address).await?;
+        let local_account = LocalAccount::new(address, key_holder, seq);
+
+        Ok(Self {
+            client,
+            tx_cost: TxCost::default_baseline_cost(),
+            local_account,
+            chain_id,
+            response: None,
+            multi_key_file: Some(multi_key_file),
         })
     }
 
+    /// true if the account is controlled by a multi-ed25519 key
+    pub fn is_multi_key(&self) -> bool {
+        self.multi_key_file.is_some()
+    }
+
     /// Sets the transaction cost for the `Sender`.
     pub fn set_tx_cost(&mut self, cost: &TxCost) {
         cost.clone_into(&mut self.tx_cost);
@@ -129,6 +173,7 @@ impl Sender {
             local_account,
             chain_id,
             response: None,
+            multi_key_file: None,
         };
 
         Ok(s)
@@ -185,6 +230,7 @@ impl Sender {
                 local_account,
                 chain_id,
                 response: None,
+                multi_key_file: None,
             };
             return Ok(s);
         }
@@ -196,15 +242,20 @@ impl Sender {
     }
 
     /// Signs and submits a transaction payload, waiting for the transaction on-chain data.
+    /// None if the transaction was staged for the other key holders of a multi-key account.
     pub async fn sign_submit_wait(
         &mut self,
         payload: TransactionPayload,
-    ) -> anyhow::Result<TransactionOnChainData> {
+    ) -> anyhow::Result<Option<TransactionOnChainData>> {
         if let TransactionPayload::Script(s) = &payload {
             let hash = HashValue::sha3_256_of(s.code());
             info!("script code hash: {}", &hash.to_hex_literal());
         }
 
+        if let Some(path) = self.multi_key_file.clone() {
+            return self.stage_multi_key(&path, payload).await;
+        }
+
         let signed = self.sign_payload(payload);
         let spin = OLProgress::spin_steady(500, "awaiting transaction response".to_string());
         println!("sending transaction...");
@@ -214,7 +265,7 @@ impl Sender {
         spin.finish_and_clear();
         debug!("{:?}", &r);
         OLProgress::complete("transaction success");
-        Ok(r)
+        Ok(Some(r))
     }
 
     /// Signs a transaction payload.
@@ -232,6 +283,71 @@ impl Sender {
         self.local_account.sign_with_transaction_builder(tb)
     }
 
+    /// Stage the transaction for the key holders of a multi-ed25519 account.
+    /// It only gets submitted here if the threshold is already met.
+    /// A pending request which already has signatures is never replaced, unless
+    /// it is the same transaction.
+    async fn stage_multi_key(
+        &mut self,
+        path: &Path,
+        payload: TransactionPayload,
+    ) -> anyhow::Result<Option<TransactionOnChainData>> {
+        let raw_txn = self.build_raw_txn(payload, MULTI_KEY_TX_EXPIRY_SECS);
+
+        let mut multi_key = MultiKeyFile::read(path)?;
+        let pending = match &multi_key.raw_txn {
+            Some(_) => Some(multi_key.raw_transaction()?),
+            None => None,
+        };
+        match pending {
+            // the same transaction again, only add our signature
+            Some(p)
+                if p.sender() == raw_txn.sender()
+                    && p.sequence_number() == raw_txn.sequence_number()
+                    && p.payload() == raw_txn.payload() => {}
+            _ if !multi_key.signatures.is_empty() => bail!(
+                "{} has a pending request with {} signatures: {}. Submit it with `txs multi-key submit`, or remove it from the file, before staging another transaction",
+                path.display(),
+                multi_key.signatures.len(),
+                multi_key.description.as_deref().unwrap_or("(no description)")
+            ),
+            _ => multi_key.stage_transaction(&raw_txn)?,
+        }
+        let idx = multi_key.sign(self.local_account.private_key())?;
+        println!("signed as key #{}", idx);
+        multi_key.save(path)?;
+
+        if !multi_key.is_complete() {
+            println!(
+                "transaction staged in {} but not sent, it has {} of {} signatures. Other key holders can sign with `txs multi-key sign` and then send it with `txs multi-key submit`",
+                path.display(),
+                multi_key.signature_count(),
+                multi_key.threshold
+            );
+            return Ok(None);
+        }
+
+        let signed = multi_key.signed_transaction()?;
+        let r = self.submit(&signed).await?;
+        self.response = Some(r.clone());
+        Ok(Some(r))
+    }
+
+    /// A transaction from this account which is not yet signed, expiring after `expiry_secs`
+    fn build_raw_txn(&self, payload: TransactionPayload, expiry_secs: u64) -> RawTransaction {
+        let t = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        TransactionBuilder::new(payload, t + expiry_secs, self.chain_id)
+            .sender(self.local_account.address())
+            .sequence_number(self.local_account.sequence_number())
+            .gas_unit_price(self.tx_cost.coin_price_per_unit)
+            .max_gas_amount(self.tx_cost.max_gas_unit_for_tx)
+            .build()
+    }
+
     /// submit to API and wait for the transaction on chain data
     pub async fn submit(
         &mut self,
@@ -270,6 +386,9 @@ impl Sender {
         &mut self,
         payload: TransactionPayload,
     ) -> anyhow::Result<Vec<UserTransaction>> {
+        if self.is_multi_key() {
+            bail!("gas estimates are not available for multi-key accounts");
+        }
         let signed = self.sign_payload(payload);
 
         let res = self
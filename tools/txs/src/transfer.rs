@@ -16,6 +16,7 @@ impl Sender {
     ) -> anyhow::Result<Option<TransactionOnChainData>> {
         // must scale the coin from decimal to onchain representation
         let coin_scaled = gas_coin::cast_decimal_to_coin(amount);
+        println!("transfer {} to {}", amount, to.to_hex_literal());
         let payload = OlAccountTransfer {
             to,
             amount: coin_scaled,
@@ -33,7 +34,7 @@ impl Sender {
             Ok(None)
         } else {
             match self.sign_submit_wait(payload).await {
-                Ok(tx) => Ok(Some(tx)),
+                Ok(tx) => Ok(tx),
                 Err(e) => {
                     bail!(
                         "ERROR: transaction could not complete, message: {}",
@@ -1,8 +1,9 @@
 use crate::{
     publish::encode_publish_payload, submit_transaction::Sender, txs_cli_community::CommunityTxs,
-    txs_cli_governance::GovernanceTxs, txs_cli_user::UserTxs, txs_cli_vals::ValidatorTxs,
+    txs_cli_governance::GovernanceTxs, txs_cli_multi_key::MultiKeyTxs,
+    txs_cli_multisig::MultisigTxs, txs_cli_user::UserTxs, txs_cli_vals::ValidatorTxs,
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
 use diem::common::types::MovePackageDir;
 use diem_sdk::{
@@ -12,7 +13,10 @@ use diem_sdk::{
 };
 use indoc::indoc;
 use lotus_types::{
-    core_types::app_cfg::{AppCfg, TxCost, TxType},
+    core_types::{
+        address_book::{parse_address_arg, AddressArg},
+        app_cfg::{AppCfg, TxCost, TxType},
+    },
     exports::{ChainId, NamedChain},
 };
 use lotus_wallet::account_keys::{get_keys_from_mnem, get_keys_from_prompt};
@@ -64,6 +68,16 @@ pub struct TxsCli {
     /// optional, use legacy (v5) 16-byte address format for a sender
     #[clap(long)]
     pub legacy_address: bool,
+
+    /// optional, send from an account controlled by the multi-ed25519 key in this file.
+    /// The transaction is staged in the file for the other key holders to sign, see `multi-key`
+    #[clap(long)]
+    pub multi_key_file: Option<PathBuf>,
+
+    /// optional, nickname or address of a profile in lotus-cli-config.yaml to sign with.
+    /// Its chain is used unless --chain-id is set
+    #[clap(long)]
+    pub profile: Option<String>,
 }
 
 #[derive(clap::Subcommand)]
@@ -80,12 +94,18 @@ pub enum TxsSub {
     #[clap(subcommand)]
     /// Perform transactions for addresses with Community Wallet settings
     Community(CommunityTxs),
+    #[clap(subcommand)]
+    /// Sign for accounts controlled by a multi-ed25519 (k-of-n) key
+    MultiKey(MultiKeyTxs),
+    #[clap(subcommand)]
+    /// Create, propose to and vote on native multisig accounts
+    Multisig(MultisigTxs),
     /// Transfer coins between accounts (and create new account)
     // Transferring can also be used to create accounts
     Transfer {
-        /// Address of the recipient
-        #[clap(short, long)]
-        to_account: AccountAddress,
+        /// Address of the recipient, or @name of a contact
+        #[clap(short, long, value_parser = parse_address_arg)]
+        to_account: AddressArg,
 
         /// The amount of coins to transfer
         #[clap(short, long)]
@@ -138,39 +158,86 @@ pub enum TxsSub {
 impl TxsCli {
     /// Executes the transaction CLI command based on parsed arguments.
     pub async fn run(&self) -> Result<()> {
-        // Determine private key based on CLI options or prompts
-        let pri_key = if let Some(pk) = &self.test_private_key {
-            Ed25519PrivateKey::from_encoded_string(pk)?
-        } else if let Some(m) = &self.mnemonic {
-            let legacy = get_keys_from_mnem(m.to_string())?;
-            legacy.child_0_owner.pri_key
-        } else {
-            let legacy = get_keys_from_prompt()?;
-            legacy.child_0_owner.pri_key
-        };
+        // Key holders of a multi-key don't need an account of their own
+        if let Some(TxsSub::MultiKey(multi_key_txs)) = &self.subcommand {
+            return multi_key_txs.run(self).await;
+        }
 
         // Load application configuration
         let app_cfg = AppCfg::load(self.config_path.clone())?;
 
-        // Determine chain ID and URL for client
-        let chain_name = self.chain_id.unwrap_or(app_cfg.workspace.default_chain_id);
-        let url = if let Some(u) = self.url.as_ref() {
-            u.to_owned()
-        } else {
-            app_cfg.pick_url(Some(chain_name))?
+        let profile = match &self.profile {
+            Some(p) => Some(app_cfg.find_profile(p)?),
+            None => None,
+        };
+
+        let pri_key = match profile.and_then(|p| p.borrow_private_key().ok()) {
+            Some(k) if self.test_private_key.is_none() && self.mnemonic.is_none() => k.to_owned(),
+            _ => self.get_private_key()?,
         };
 
+        // Determine chain ID and URL for client
+        let chain_name = self.get_chain_name(&app_cfg)?;
+
+        // don't sign for a profile's account on another network
+        if let Some(p) = profile {
+            let profile_chain = app_cfg.profile_chain(p);
+            if chain_name != profile_chain {
+                bail!(
+                    "profile {} is for chain {}, but --chain-id is {}, refusing to sign",
+                    p.nickname,
+                    profile_chain,
+                    chain_name
+                );
+            }
+        }
+
         // Initialize client
-        let client = Client::new(url);
+        let client = self.get_client(&app_cfg)?;
+
+        if let Some(p) = profile {
+            let profile_chain = app_cfg.profile_chain(p);
+            let remote_id = client.get_index().await?.into_inner().chain_id;
+            if remote_id != profile_chain.id() {
+                bail!(
+                    "profile {} is for chain {} (id {}), but the node is on chain id {}, refusing to sign",
+                    p.nickname,
+                    profile_chain,
+                    profile_chain.id(),
+                    remote_id
+                );
+            }
+        }
 
         // Initialize sender
-        let mut send = Sender::new(
-            AccountKey::from_private_key(pri_key),
-            ChainId::new(chain_name.id()),
-            Some(client),
-            self.legacy_address,
-        )
-        .await?;
+        let mut send = if let Some(multi_key_file) = &self.multi_key_file {
+            Sender::from_multi_key_file(
+                multi_key_file.to_owned(),
+                AccountKey::from_private_key(pri_key),
+                ChainId::new(chain_name.id()),
+                Some(client),
+            )
+            .await?
+        } else {
+            Sender::new(
+                AccountKey::from_private_key(pri_key),
+                ChainId::new(chain_name.id()),
+                Some(client),
+                self.legacy_address,
+            )
+            .await?
+        };
+
+        if let Some(p) = profile {
+            if send.local_account.address() != p.account {
+                bail!(
+                    "the key does not sign for account {} of profile {}, it controls {}",
+                    p.account,
+                    p.nickname,
+                    send.local_account.address()
+                );
+            }
+        }
 
         // Handle mutually exclusive options for transaction cost
         if self.tx_cost.is_some() && self.tx_profile.is_some() {
@@ -189,7 +256,8 @@ impl TxsCli {
         // Execute subcommand based on parsed input
         match &self.subcommand {
             Some(TxsSub::Transfer { to_account, amount }) => {
-                send.transfer(to_account.to_owned(), amount.to_owned(), self.estimate_only)
+                let to_account = to_account.resolve(&app_cfg)?;
+                send.transfer(to_account, amount.to_owned(), self.estimate_only)
                     .await?;
                 Ok(())
             }
@@ -204,10 +272,11 @@ impl TxsCli {
                 type_args: ty_args,
                 args,
             }) => send.generic(function_id, ty_args, args).await,
-            Some(TxsSub::Validator(val_txs)) => val_txs.run(&mut send).await,
+            Some(TxsSub::Validator(val_txs)) => val_txs.run(&mut send, &app_cfg).await,
             Some(TxsSub::Governance(upgrade_txs)) => upgrade_txs.run(&mut send).await,
-            Some(TxsSub::User(user_txs)) => user_txs.run(&mut send).await,
-            Some(TxsSub::Community(comm_txs)) => comm_txs.run(&mut send).await,
+            Some(TxsSub::User(user_txs)) => user_txs.run(&mut send, &app_cfg).await,
+            Some(TxsSub::Community(comm_txs)) => comm_txs.run(&mut send, &app_cfg).await,
+            Some(TxsSub::Multisig(multisig_txs)) => multisig_txs.run(&mut send, &app_cfg).await,
             _ => {
                 println!(
                     "\n\"I'm searching, though I don't succeed
@@ -220,6 +289,42 @@ All that's left is an unhappy ending\"
             }
         }
     }
+
+    /// Determine private key based on CLI options or prompts
+    pub fn get_private_key(&self) -> Result<Ed25519PrivateKey> {
+        let pri_key = if let Some(pk) = &self.test_private_key {
+            Ed25519PrivateKey::from_encoded_string(pk)?
+        } else if let Some(m) = &self.mnemonic {
+            let legacy = get_keys_from_mnem(m.to_string())?;
+            legacy.child_0_owner.pri_key
+        } else {
+            let legacy = get_keys_from_prompt()?;
+            legacy.child_0_owner.pri_key
+        };
+        Ok(pri_key)
+    }
+
+    /// The --chain-id, otherwise the chain of the --profile, otherwise the config default
+    pub fn get_chain_name(&self, app_cfg: &AppCfg) -> Result<NamedChain> {
+        if let Some(c) = self.chain_id {
+            return Ok(c);
+        }
+        match &self.profile {
+            Some(p) => Ok(app_cfg.profile_chain(app_cfg.find_profile(p)?)),
+            None => Ok(app_cfg.workspace.default_chain_id),
+        }
+    }
+
+    /// Client for the --url, or for a node of the chain in the config file
+    pub fn get_client(&self, app_cfg: &AppCfg) -> Result<Client> {
+        let chain_name = self.get_chain_name(app_cfg)?;
+        let url = if let Some(u) = self.url.as_ref() {
+            u.to_owned()
+        } else {
+            app_cfg.pick_url(Some(chain_name))?
+        };
+        Ok(Client::new(url))
+    }
 }
 
 /// Converts an account address to a legacy format (v5).
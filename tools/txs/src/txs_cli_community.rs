@@ -4,7 +4,13 @@ use crate::submit_transaction::Sender;
 use diem_types::account_address::AccountAddress;
 use lotus_cached_packages::lotus_stdlib;
 use lotus_query::{account_queries, query_view};
-use lotus_types::move_resource::gas_coin;
+use lotus_types::{
+    core_types::{
+        address_book::{parse_address_arg, resolve_all, AddressArg},
+        app_cfg::AppCfg,
+    },
+    move_resource::gas_coin,
+};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs, path::PathBuf};
 
@@ -34,9 +40,9 @@ pub enum CommunityTxs {
 }
 
 impl CommunityTxs {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
         match &self {
-            CommunityTxs::GovInit(init) => match init.run(sender).await {
+            CommunityTxs::GovInit(init) => match init.run(sender, app_cfg).await {
                 Ok(_) => println!("SUCCESS: community wallet initialized"),
                 Err(e) => {
                     println!(
@@ -45,13 +51,13 @@ impl CommunityTxs {
                     );
                 }
             },
-            CommunityTxs::GovOffer(offer) => match offer.run(sender).await {
+            CommunityTxs::GovOffer(offer) => match offer.run(sender, app_cfg).await {
                 Ok(_) => println!("SUCCESS: community wallet offer proposed"),
                 Err(e) => {
                     println!("ERROR: could not propose offer, message: {}", e);
                 }
             },
-            CommunityTxs::GovClaim(claim) => match claim.run(sender).await {
+            CommunityTxs::GovClaim(claim) => match claim.run(sender, app_cfg).await {
                 Ok(_) => println!("SUCCESS: community wallet offer claimed"),
                 Err(e) => {
                     println!("ERROR: could not claim offer, message: {}", e);
@@ -63,31 +69,31 @@ impl CommunityTxs {
                     println!("ERROR: could not finalize wallet, message: {}", e);
                 }
             },
-            CommunityTxs::GovAdmin(admin) => match admin.run(sender).await {
+            CommunityTxs::GovAdmin(admin) => match admin.run(sender, app_cfg).await {
                 Ok(_) => println!("SUCCESS: community wallet admin proposed"),
                 Err(e) => {
                     println!("ERROR: could not propose new admin, message: {}", e);
                 }
             },
-            CommunityTxs::Propose(propose) => match propose.run(sender).await {
+            CommunityTxs::Propose(propose) => match propose.run(sender, app_cfg).await {
                 Ok(_) => println!("SUCCESS: community wallet transfer proposed"),
                 Err(e) => {
                     println!("ERROR: community wallet transfer rejected, message: {}", e);
                 }
             },
-            CommunityTxs::Veto(veto) => match veto.run(sender).await {
+            CommunityTxs::Veto(veto) => match veto.run(sender, app_cfg).await {
                 Ok(_) => println!("SUCCESS: veto vote submitted"),
                 Err(e) => {
                     println!("ERROR: veto vote rejected, message: {}", e);
                 }
             },
-            CommunityTxs::Batch(batch) => match batch.run(sender).await {
+            CommunityTxs::Batch(batch) => match batch.run(sender, app_cfg).await {
                 Ok(_) => {}
                 Err(e) => {
                     println!("ERROR: could not add admin, message: {}", e);
                 }
             },
-            CommunityTxs::Migration(migration) => match migration.run(sender).await {
+            CommunityTxs::Migration(migration) => match migration.run(sender, app_cfg).await {
                 Ok(_) => {}
                 Err(e) => {
                     println!("ERROR: could not migrate, message: {}", e);
@@ -119,9 +125,9 @@ impl CommunityTxs {
 #[derive(clap::Args)]
 /// Initialize a community wallet offering the initial authorities
 pub struct InitTx {
-    #[clap(short, long)]
-    /// The initial admins of the multi-sig (cannot add self)
-    pub admins: Vec<AccountAddress>,
+    #[clap(short, long, value_parser = parse_address_arg)]
+    /// The initial admins of the multi-sig (cannot add self), or @name of contacts
+    pub admins: Vec<AddressArg>,
 
     #[clap(short, long)]
     /// Num of signatures needed for the n-of-m
@@ -129,9 +135,9 @@ pub struct InitTx {
 }
 
 impl InitTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
         let payload = lotus_stdlib::community_wallet_init_init_community(
-            self.admins.clone(),
+            resolve_all(&self.admins, app_cfg)?,
             self.num_signers,
         );
 
@@ -145,17 +151,17 @@ impl InitTx {
 #[derive(clap::Args)]
 /// Propose offer to authorities to become an authority in the community wallet
 pub struct OfferTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The Community Wallet to propose the offer
-    pub admins: Vec<AccountAddress>,
+    pub admins: Vec<AddressArg>,
     /// Num of signatures needed for the n-of-m
     pub num_signers: u64,
 }
 
 impl OfferTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
         let payload = lotus_stdlib::community_wallet_init_propose_offer(
-            self.admins.clone(),
+            resolve_all(&self.admins, app_cfg)?,
             self.num_signers,
         );
         sender.sign_submit_wait(payload).await?;
@@ -167,14 +173,15 @@ impl OfferTx {
 #[derive(clap::Args)]
 /// Claim the offer to become an authority in the multi-sig
 pub struct ClaimTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The Community Wallet to claim the offer
-    pub community_wallet: AccountAddress,
+    pub community_wallet: AddressArg,
 }
 
 impl ClaimTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
-        let payload = lotus_stdlib::multi_action_claim_offer(self.community_wallet);
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        let payload =
+            lotus_stdlib::multi_action_claim_offer(self.community_wallet.resolve(app_cfg)?);
         sender.sign_submit_wait(payload).await?;
         println!("You have claimed the community wallet offer.");
         Ok(())
@@ -200,12 +207,12 @@ impl CageTx {
 
 #[derive(clap::Args)]
 pub struct AdminTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The SlowWallet recipient of funds
-    pub community_wallet: AccountAddress,
-    #[clap(short, long)]
-    /// Admin to add (or remove) from the multisig
-    pub admin: AccountAddress,
+    pub community_wallet: AddressArg,
+    #[clap(short, long, value_parser = parse_address_arg)]
+    /// Admin to add (or remove) from the multisig, or @name of a contact
+    pub admin: AddressArg,
     #[clap(short, long)]
     /// Drops this admin from the multisig
     pub drop: Option<bool>,
@@ -218,13 +225,13 @@ pub struct AdminTx {
 }
 
 impl AdminTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
         // Default to adding a signer if the `drop` flag is not provided
         let is_add_operation = self.drop.unwrap_or(true);
 
         let payload = lotus_stdlib::community_wallet_init_change_signer_community_multisig(
-            self.community_wallet,
-            self.admin,
+            self.community_wallet.resolve(app_cfg)?,
+            self.admin.resolve(app_cfg)?,
             is_add_operation,
             self.n,
             self.epochs.unwrap_or(10), // todo: remo
@@ -236,12 +243,12 @@ impl AdminTx {
 
 #[derive(clap::Args)]
 pub struct ProposeTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The Community Wallet to schedule transaction
-    pub community_wallet: AccountAddress,
-    #[clap(short, long)]
-    /// The SlowWallet recipient of funds
-    pub recipient: AccountAddress,
+    pub community_wallet: AddressArg,
+    #[clap(short, long, value_parser = parse_address_arg)]
+    /// The SlowWallet recipient of funds, or @name of a contact
+    pub recipient: AddressArg,
     #[clap(short, long)]
     /// Amount of coins (units) to transfer
     pub amount: u64,
@@ -251,10 +258,10 @@ pub struct ProposeTx {
 }
 
 impl ProposeTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
         let payload = lotus_stdlib::donor_voice_txs_propose_payment_tx(
-            self.community_wallet,
-            self.recipient,
+            self.community_wallet.resolve(app_cfg)?,
+            self.recipient.resolve(app_cfg)?,
             gas_coin::cast_decimal_to_coin(self.amount as f64),
             self.description.clone().into_bytes(),
         );
@@ -265,9 +272,9 @@ impl ProposeTx {
 
 #[derive(clap::Args)]
 pub struct BatchTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The Community Wallet to schedule transaction
-    pub community_wallet: AccountAddress,
+    pub community_wallet: AddressArg,
     #[clap(short, long)]
     /// JSON file with batch payments
     pub file: PathBuf,
@@ -297,12 +304,13 @@ struct ProposePay {
 // script that submits all TXS in a batch and executes all or aborts
 // (an atomic batch).
 impl BatchTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        let community_wallet = self.community_wallet.resolve(app_cfg)?;
         let data = fs::read_to_string(&self.file).expect("Unable to read file");
         let mut list: Vec<ProposePay> = serde_json::from_str(&data).expect("Unable to parse");
 
         let ballots =
-            account_queries::multi_auth_ballots(sender.client(), self.community_wallet).await?;
+            account_queries::multi_auth_ballots(sender.client(), community_wallet).await?;
         let d = ballots.as_object().unwrap();
         let v = d.get("vote").unwrap().as_object().unwrap();
         let mut approved = v
@@ -419,7 +427,7 @@ impl BatchTx {
 
             println!("scheduling tx");
 
-            match propose_single(sender, &self.community_wallet, inst).await {
+            match propose_single(sender, &community_wallet, inst).await {
                 Ok(_) => {
                     inst.proposed = Some(true);
                 }
@@ -479,18 +487,20 @@ async fn propose_single(
 
 #[derive(clap::Args)]
 pub struct VetoTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The Slow Wallet recipient of funds
-    pub community_wallet: AccountAddress,
+    pub community_wallet: AddressArg,
     #[clap(short, long)]
     /// Proposal number
     pub proposal_id: u64,
 }
 
 impl VetoTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
-        let payload =
-            lotus_stdlib::donor_voice_txs_propose_veto_tx(self.community_wallet, self.proposal_id);
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        let payload = lotus_stdlib::donor_voice_txs_propose_veto_tx(
+            self.community_wallet.resolve(app_cfg)?,
+            self.proposal_id,
+        );
         sender.sign_submit_wait(payload).await?;
         Ok(())
     }
@@ -499,14 +509,16 @@ impl VetoTx {
 // TODO remove after migration is completed
 #[derive(clap::Args)]
 pub struct MigrateOfferTx {
-    #[clap(short, long)]
+    #[clap(short, long, value_parser = parse_address_arg)]
     /// The Community Wallet to propose the offer
-    pub community_wallet: AccountAddress,
+    pub community_wallet: AddressArg,
 }
 
 impl MigrateOfferTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
-        let payload = lotus_stdlib::multi_action_migration_migrate_offer(self.community_wallet);
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        let payload = lotus_stdlib::multi_action_migration_migrate_offer(
+            self.community_wallet.resolve(app_cfg)?,
+        );
         sender.sign_submit_wait(payload).await?;
         println!("You have migrated the account to have the Offer structure. You can proceed with the authority offer now.");
         Ok(())
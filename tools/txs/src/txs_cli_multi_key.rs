@@ -0,0 +1,345 @@
+//! Multi-ed25519 (k-of-n) keys.
+//! An account whose authentication key is a MultiEd25519 key cannot be signed
+//! for by a single person. Key holders instead pass a json file between each
+//! other, each adding a partial signature. Once the threshold is reached the
+//! signatures are assembled into one authenticator.
+
+use crate::txs_cli::TxsCli;
+use anyhow::{bail, Context};
+use dialoguer::Confirm;
+use diem_sdk::{
+    crypto::{
+        ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+        multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+        signing_message, PrivateKey, Signature, SigningKey, ValidCryptoMaterialStringExt,
+    },
+    types::transaction::{RawTransaction, SignedTransaction},
+};
+use lotus_types::{core_types::app_cfg::AppCfg, exports::AuthenticationKey};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, path::PathBuf};
+
+/// Key holders need time to pass the file around, so staged transactions
+/// expire after a day instead of the usual few minutes.
+pub const MULTI_KEY_TX_EXPIRY_SECS: u64 = 60 * 60 * 24;
+
+/// A multi-ed25519 key and, optionally, one pending request for the key holders to sign.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiKeyFile {
+    /// hex encoded ed25519 public keys of the key holders. The order is part of the key.
+    pub public_keys: Vec<String>,
+    /// number of signatures required
+    pub threshold: u8,
+    /// what is being signed, for the key holders to review
+    #[serde(default)]
+    pub description: Option<String>,
+    /// hex of the bytes being signed
+    #[serde(default)]
+    pub message: Option<String>,
+    /// hex of the bcs encoded RawTransaction, if the request is a transaction
+    #[serde(default)]
+    pub raw_txn: Option<String>,
+    /// partial signatures collected so far, by index of the public key
+    #[serde(default)]
+    pub signatures: BTreeMap<u8, String>,
+}
+
+impl MultiKeyFile {
+    /// describe a k-of-n key, with no pending request
+    pub fn new(public_keys: &[Ed25519PublicKey], threshold: u8) -> anyhow::Result<Self> {
+        // check the key is well formed before anyone starts signing
+        MultiEd25519PublicKey::new(public_keys.to_vec(), threshold)?;
+
+        let public_keys = public_keys
+            .iter()
+            .map(|k| k.to_encoded_string())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            public_keys,
+            threshold,
+            description: None,
+            message: None,
+            raw_txn: None,
+            signatures: BTreeMap::new(),
+        })
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("could not read multi-key file at {}", path.display()))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// the ed25519 keys of the key holders, in order
+    pub fn ed25519_keys(&self) -> anyhow::Result<Vec<Ed25519PublicKey>> {
+        Ok(self
+            .public_keys
+            .iter()
+            .map(|k| Ed25519PublicKey::from_encoded_string(k))
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    pub fn public_key(&self) -> anyhow::Result<MultiEd25519PublicKey> {
+        Ok(MultiEd25519PublicKey::new(
+            self.ed25519_keys()?,
+            self.threshold,
+        )?)
+    }
+
+    pub fn auth_key(&self) -> anyhow::Result<AuthenticationKey> {
+        Ok(AuthenticationKey::multi_ed25519(&self.public_key()?))
+    }
+
+    /// replace any pending request with a new message. Collected signatures are dropped.
+    pub fn stage_message(&mut self, message: &[u8], description: String) {
+        self.description = Some(description);
+        self.message = Some(hex::encode(message));
+        self.raw_txn = None;
+        self.signatures.clear();
+    }
+
+    /// replace any pending request with a transaction
+    pub fn stage_transaction(&mut self, raw_txn: &RawTransaction) -> anyhow::Result<()> {
+        let msg = signing_message(raw_txn)?;
+        self.stage_message(&msg, format!("transaction from {}", raw_txn.sender()));
+        self.raw_txn = Some(hex::encode(bcs::to_bytes(raw_txn)?));
+        Ok(())
+    }
+
+    /// true if the pending request is for exactly this message
+    pub fn is_staged(&self, message: &[u8]) -> bool {
+        self.message.as_deref() == Some(hex::encode(message).as_str())
+    }
+
+    pub fn message_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match &self.message {
+            Some(m) => Ok(hex::decode(m)?),
+            None => bail!("there is no pending request to sign in this file"),
+        }
+    }
+
+    pub fn raw_transaction(&self) -> anyhow::Result<RawTransaction> {
+        let raw = match &self.raw_txn {
+            Some(r) => r,
+            None => bail!("the pending request is not a transaction"),
+        };
+        let raw_txn: RawTransaction = bcs::from_bytes(&hex::decode(raw)?)?;
+
+        // don't let anyone swap the message under a transaction the key holders reviewed
+        if signing_message(&raw_txn)? != self.message_bytes()? {
+            bail!("the message does not match the staged transaction, refusing to use this file");
+        }
+        Ok(raw_txn)
+    }
+
+    /// add a partial signature from one key holder, returns the index of their key
+    pub fn sign(&mut self, key: &Ed25519PrivateKey) -> anyhow::Result<u8> {
+        let public_key = key.public_key().to_encoded_string()?;
+        let idx = match self.public_keys.iter().position(|k| k == &public_key) {
+            Some(i) => i,
+            None => bail!(
+                "this key is not one of the keys of the multi-key: {}",
+                public_key
+            ),
+        };
+
+        if self.raw_txn.is_some() {
+            self.raw_transaction()?;
+        }
+        let msg = self.message_bytes()?;
+        let sig = key.sign_arbitrary_message(&msg);
+
+        let idx = idx as u8;
+        self.signatures.insert(idx, sig.to_encoded_string()?);
+        Ok(idx)
+    }
+
+    /// the valid signatures collected so far, invalid ones are skipped
+    fn valid_signatures(&self) -> anyhow::Result<Vec<(Ed25519Signature, u8)>> {
+        let keys = self.ed25519_keys()?;
+        let msg = self.message_bytes()?;
+
+        let valid = self
+            .signatures
+            .iter()
+            .filter_map(|(idx, sig)| {
+                let sig = Ed25519Signature::from_encoded_string(sig).ok()?;
+                let key = keys.get(*idx as usize)?;
+                sig.verify_arbitrary_msg(&msg, key).ok()?;
+                Some((sig, *idx))
+            })
+            .collect();
+        Ok(valid)
+    }
+
+    pub fn signature_count(&self) -> usize {
+        self.valid_signatures().map(|v| v.len()).unwrap_or(0)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.signature_count() >= self.threshold as usize
+    }
+
+    /// assemble the partial signatures into a MultiEd25519 signature
+    pub fn multi_signature(&self) -> anyhow::Result<MultiEd25519Signature> {
+        let sigs = self.valid_signatures()?;
+        if sigs.len() < self.threshold as usize {
+            bail!(
+                "only {} of the {} required signatures have been collected",
+                sigs.len(),
+                self.threshold
+            );
+        }
+        Ok(MultiEd25519Signature::new(sigs)?)
+    }
+
+    /// the staged transaction with the assembled authenticator
+    pub fn signed_transaction(&self) -> anyhow::Result<SignedTransaction> {
+        Ok(SignedTransaction::new_multisig(
+            self.raw_transaction()?,
+            self.public_key()?,
+            self.multi_signature()?,
+        ))
+    }
+}
+
+/// Work with multi-ed25519 (k-of-n) keys. The signing key holders don't need to have an account on chain.
+#[derive(clap::Subcommand)]
+pub enum MultiKeyTxs {
+    /// Describe a new k-of-n key, which an account can then be rotated to with `user rotate-key --to-multi-key-file`
+    Init {
+        #[clap(short, long)]
+        /// File to write the multi-key to
+        file: PathBuf,
+        #[clap(short, long)]
+        /// Hex ed25519 public key of each key holder, in order
+        public_keys: Vec<String>,
+        #[clap(short, long)]
+        /// Number of signatures required
+        threshold: u8,
+    },
+    /// Review the pending request in a multi-key file and add your signature
+    Sign {
+        #[clap(short, long)]
+        file: PathBuf,
+    },
+    /// Show the pending request and how many signatures it has
+    Status {
+        #[clap(short, long)]
+        file: PathBuf,
+    },
+    /// Submit a staged transaction once enough key holders have signed
+    Submit {
+        #[clap(short, long)]
+        file: PathBuf,
+    },
+}
+
+impl MultiKeyTxs {
+    /// these run without a Sender, a key holder may not have an account on chain
+    pub async fn run(&self, cli: &TxsCli) -> anyhow::Result<()> {
+        match &self {
+            MultiKeyTxs::Init {
+                file,
+                public_keys,
+                threshold,
+            } => {
+                let keys = public_keys
+                    .iter()
+                    .map(|k| Ed25519PublicKey::from_encoded_string(k))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let multi_key = MultiKeyFile::new(&keys, *threshold)?;
+                multi_key.save(file)?;
+                println!(
+                    "SUCCESS: {}-of-{} multi-key written to {}\nauthentication key: {}",
+                    threshold,
+                    keys.len(),
+                    file.display(),
+                    multi_key.auth_key()?
+                );
+            }
+            MultiKeyTxs::Sign { file } => {
+                let mut multi_key = MultiKeyFile::read(file)?;
+                print_status(&multi_key)?;
+
+                if !Confirm::new()
+                    .with_prompt("Do you want to sign this request?")
+                    .interact()?
+                {
+                    bail!("better safe than sorry, exiting.");
+                }
+
+                let pri_key = cli.get_private_key()?;
+                let idx = multi_key.sign(&pri_key)?;
+                multi_key.save(file)?;
+                println!(
+                    "SUCCESS: signed as key #{}, {} of {} signatures collected",
+                    idx,
+                    multi_key.signature_count(),
+                    multi_key.threshold
+                );
+            }
+            MultiKeyTxs::Status { file } => {
+                print_status(&MultiKeyFile::read(file)?)?;
+            }
+            MultiKeyTxs::Submit { file } => {
+                let multi_key = MultiKeyFile::read(file)?;
+                let signed = multi_key.signed_transaction()?;
+                let client = cli.get_client(&AppCfg::load(cli.config_path.clone())?)?;
+
+                let pending = client.submit(&signed).await?.into_inner();
+                println!("transaction sent, hash: {}", pending.hash);
+                let res = client
+                    .wait_for_transaction_bcs(&pending)
+                    .await?
+                    .into_inner();
+                if !res.info.status().is_success() {
+                    bail!(
+                        "transaction not successful, status: {:?}",
+                        res.info.status()
+                    );
+                }
+                println!("SUCCESS: multi-key transaction executed");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_status(multi_key: &MultiKeyFile) -> anyhow::Result<()> {
+    println!(
+        "{}-of-{} multi-key, authentication key: {}",
+        multi_key.threshold,
+        multi_key.public_keys.len(),
+        multi_key.auth_key()?
+    );
+
+    if multi_key.message.is_none() {
+        println!("no pending request");
+        return Ok(());
+    }
+
+    println!(
+        "pending request: {}",
+        multi_key
+            .description
+            .as_deref()
+            .unwrap_or("(no description)")
+    );
+    if multi_key.raw_txn.is_some() {
+        println!("{:#?}", multi_key.raw_transaction()?);
+    }
+    println!(
+        "signatures: {} of {} collected, from keys {:?}",
+        multi_key.signature_count(),
+        multi_key.threshold,
+        multi_key.signatures.keys().collect::<Vec<_>>()
+    );
+    Ok(())
+}
@@ -0,0 +1,279 @@
+//! Multisig account subcommands, for the vendor multisig_account module
+
+use crate::{generic_tx::build_entry_function, submit_transaction::Sender};
+use anyhow::{bail, Context};
+use diem_types::{
+    account_address::AccountAddress,
+    transaction::{EntryFunction, Multisig, MultisigTransactionPayload, TransactionPayload},
+};
+use lotus_cached_packages::lotus_stdlib::EntryFunctionCall::{
+    self, MultisigAccountAddOwners, MultisigAccountApproveTransaction,
+    MultisigAccountCreateTransaction, MultisigAccountCreateWithOwners,
+    MultisigAccountExecuteRejectedTransaction, MultisigAccountRejectTransaction,
+    MultisigAccountUpdateSignaturesRequired,
+};
+use lotus_query::account_queries::{
+    multisig_next_account_address, multisig_next_can_be_executed, multisig_next_sequence_number,
+    multisig_num_signatures_required,
+};
+use lotus_types::core_types::{
+    address_book::{parse_address_arg, resolve_all, AddressArg},
+    app_cfg::AppCfg,
+};
+
+#[derive(clap::Subcommand)]
+pub enum MultisigTxs {
+    /// Create a new multisig account, the sender is also an owner
+    Create {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The other owners of the multisig account, or @name of contacts
+        owners: Vec<AddressArg>,
+        #[clap(short, long)]
+        /// Num of approvals needed for the n-of-m
+        num_signatures: u64,
+    },
+    /// Propose an entry function for the multisig account to execute
+    Propose {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+        #[clap(short, long)]
+        /// Function identifier, e.g. 0x1::ol_account::transfer
+        function_id: String,
+        #[clap(short, long)]
+        /// Type arguments separated by commas
+        type_args: Option<String>,
+        #[clap(short, long)]
+        /// Function arguments separated by commas
+        args: Option<String>,
+        #[clap(short, long)]
+        /// Execute right away if the proposal has enough approvals
+        execute: bool,
+    },
+    /// Approve a pending transaction
+    Approve {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+        #[clap(short, long)]
+        /// Sequence number of the pending transaction
+        sequence_number: u64,
+        #[clap(short, long)]
+        /// Execute right away if this approval reaches the threshold
+        execute: bool,
+    },
+    /// Reject a pending transaction
+    Reject {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+        #[clap(short, long)]
+        /// Sequence number of the pending transaction
+        sequence_number: u64,
+    },
+    /// Remove the next pending transaction, if enough owners rejected it
+    ExecuteRejected {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+    },
+    /// Execute the next pending transaction, if enough owners approved it
+    Execute {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+    },
+    /// Propose adding owners to the multisig account
+    AddOwners {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The owners to add, or @name of contacts
+        owners: Vec<AddressArg>,
+        #[clap(short, long)]
+        /// Execute right away if the proposal has enough approvals
+        execute: bool,
+    },
+    /// Propose changing the number of approvals needed
+    UpdateSignaturesRequired {
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// The multisig account, or @name of a contact
+        multisig_address: AddressArg,
+        #[clap(short, long)]
+        /// Num of approvals needed for the n-of-m
+        num_signatures: u64,
+        #[clap(short, long)]
+        /// Execute right away if the proposal has enough approvals
+        execute: bool,
+    },
+}
+
+impl MultisigTxs {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        self.send(sender, app_cfg)
+            .await
+            .context("could not send multisig transaction")?;
+        println!("SUCCESS: multisig transaction sent");
+        Ok(())
+    }
+
+    async fn send(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        match self {
+            MultisigTxs::Create {
+                owners,
+                num_signatures,
+            } => {
+                let multisig_address =
+                    multisig_next_account_address(sender.client(), sender.local_account.address())
+                        .await?;
+                let payload = MultisigAccountCreateWithOwners {
+                    additional_owners: resolve_all(owners, app_cfg)?,
+                    num_signatures_required: *num_signatures,
+                    metadata_keys: vec![],
+                    metadata_values: vec![],
+                };
+                sender.sign_submit_wait(payload.encode()).await?;
+                println!("multisig account created at: {}", multisig_address);
+                Ok(())
+            }
+            MultisigTxs::Propose {
+                multisig_address,
+                function_id,
+                type_args,
+                args,
+                execute,
+            } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                let entry = build_entry_function(function_id, type_args, args)?;
+                propose(sender, multisig_address, entry, *execute).await
+            }
+            MultisigTxs::AddOwners {
+                multisig_address,
+                owners,
+                execute,
+            } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                let call = MultisigAccountAddOwners {
+                    new_owners: resolve_all(owners, app_cfg)?,
+                };
+                propose_call(sender, multisig_address, call, *execute).await
+            }
+            MultisigTxs::UpdateSignaturesRequired {
+                multisig_address,
+                num_signatures,
+                execute,
+            } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                let call = MultisigAccountUpdateSignaturesRequired {
+                    new_num_signatures_required: *num_signatures,
+                };
+                propose_call(sender, multisig_address, call, *execute).await
+            }
+            MultisigTxs::Approve {
+                multisig_address,
+                sequence_number,
+                execute,
+            } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                let payload = MultisigAccountApproveTransaction {
+                    multisig_account: multisig_address,
+                    sequence_number: *sequence_number,
+                };
+                sender.sign_submit_wait(payload.encode()).await?;
+                if *execute {
+                    execute_when_ready(sender, multisig_address).await?;
+                }
+                Ok(())
+            }
+            MultisigTxs::Reject {
+                multisig_address,
+                sequence_number,
+            } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                let payload = MultisigAccountRejectTransaction {
+                    multisig_account: multisig_address,
+                    sequence_number: *sequence_number,
+                };
+                sender.sign_submit_wait(payload.encode()).await.map(|_| ())
+            }
+            MultisigTxs::ExecuteRejected { multisig_address } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                let payload = MultisigAccountExecuteRejectedTransaction {
+                    multisig_account: multisig_address,
+                };
+                sender.sign_submit_wait(payload.encode()).await.map(|_| ())
+            }
+            MultisigTxs::Execute { multisig_address } => {
+                let multisig_address = multisig_address.resolve(app_cfg)?;
+                if !execute_when_ready(sender, multisig_address).await? {
+                    bail!("the next transaction does not have enough approvals yet");
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Propose a framework function for the multisig account to call on itself
+async fn propose_call(
+    sender: &mut Sender,
+    multisig_address: AccountAddress,
+    call: EntryFunctionCall,
+    execute: bool,
+) -> anyhow::Result<()> {
+    match call.encode() {
+        TransactionPayload::EntryFunction(entry) => {
+            propose(sender, multisig_address, entry, execute).await
+        }
+        _ => bail!("only entry functions can be proposed"),
+    }
+}
+
+/// Create a multisig transaction. The proposer's approval is counted, so with
+/// `execute` a 1-of-n account runs it immediately.
+pub async fn propose(
+    sender: &mut Sender,
+    multisig_address: AccountAddress,
+    entry: EntryFunction,
+    execute: bool,
+) -> anyhow::Result<()> {
+    let payload = MultisigAccountCreateTransaction {
+        multisig_account: multisig_address,
+        payload: bcs::to_bytes(&MultisigTransactionPayload::EntryFunction(entry))?,
+    };
+    sender.sign_submit_wait(payload.encode()).await?;
+
+    let seq = multisig_next_sequence_number(sender.client(), multisig_address).await? - 1;
+    println!("proposal created with sequence number {}", seq);
+
+    if execute {
+        execute_when_ready(sender, multisig_address).await?;
+    }
+    Ok(())
+}
+
+/// Execute the next pending transaction of the multisig account if it reached
+/// the threshold. Returns false, and sends nothing, if it has not.
+pub async fn execute_when_ready(
+    sender: &mut Sender,
+    multisig_address: AccountAddress,
+) -> anyhow::Result<bool> {
+    if !multisig_next_can_be_executed(sender.client(), multisig_address).await? {
+        let threshold = multisig_num_signatures_required(sender.client(), multisig_address).await?;
+        println!(
+            "the next transaction needs {} approvals before it can be executed, see `query multisig`",
+            threshold
+        );
+        return Ok(false);
+    }
+
+    // the payload is stored on chain, so it does not need to be sent again
+    let payload = TransactionPayload::Multisig(Multisig {
+        multisig_address,
+        transaction_payload: None,
+    });
+    sender.sign_submit_wait(payload).await?;
+    println!("executed the next multisig transaction");
+    Ok(true)
+}
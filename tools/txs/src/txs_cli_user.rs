@@ -1,6 +1,6 @@
 //! Validator subcommands
 
-use crate::submit_transaction::Sender;
+use crate::{submit_transaction::Sender, txs_cli_multi_key::MultiKeyFile};
 use dialoguer::Confirm;
 use diem::common::types::RotationProofChallenge;
 use diem_sdk::{
@@ -13,11 +13,16 @@ use diem_types::{
 };
 use lotus_cached_packages::lotus_stdlib;
 use lotus_types::{
+    core_types::{
+        address_book::{parse_address_arg, AddressArg},
+        app_cfg::AppCfg,
+    },
     exports::{AuthenticationKey, Ed25519PrivateKey},
     type_extensions::client_ext::ClientExt,
 };
 use lotus_wallet::account_keys::get_keys_from_prompt;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 #[derive(clap::Subcommand)]
 pub enum UserTxs {
@@ -27,9 +32,9 @@ pub enum UserTxs {
 }
 
 impl UserTxs {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
         match &self {
-            UserTxs::RotateKey(rotate) => match rotate.run(sender, true).await {
+            UserTxs::RotateKey(rotate) => match rotate.run(sender, app_cfg, true).await {
                 Ok(_) => println!("SUCCESS: private key rotated"),
                 Err(e) => {
                     println!("ERROR: could not rotate private key, message: {}", e);
@@ -45,7 +50,7 @@ impl UserTxs {
                 }
             },
             UserTxs::RotationCapability(offer_rotation_capability) => {
-                match offer_rotation_capability.run(sender).await {
+                match offer_rotation_capability.run(sender, app_cfg).await {
                     Ok(_) => println!("SUCCESS: offered rotation capability"),
                     Err(e) => {
                         println!("ERROR: could not offer rotation capability, message: {}", e);
@@ -80,18 +85,34 @@ pub struct RotateKeyTx {
     #[clap(short, long)]
     /// Private key to be used, optional.
     pub new_private_key: Option<String>,
-    #[clap(short, long)]
-    /// Account being claimed, if being transferred
-    pub claim_address: Option<AccountAddress>,
+    #[clap(short, long, value_parser = parse_address_arg)]
+    /// Account being claimed, if being transferred, or @name of a contact
+    pub claim_address: Option<AddressArg>,
+    #[clap(long, conflicts_with = "new_private_key")]
+    /// Rotate to the multi-ed25519 (k-of-n) key in this file instead, see `txs multi-key init`
+    pub to_multi_key_file: Option<PathBuf>,
 }
 
 impl RotateKeyTx {
-    pub async fn run(&self, sender: &mut Sender, confirm: bool) -> anyhow::Result<()> {
+    pub async fn run(
+        &self,
+        sender: &mut Sender,
+        app_cfg: &AppCfg,
+        confirm: bool,
+    ) -> anyhow::Result<()> {
+        if sender.is_multi_key() {
+            anyhow::bail!("rotating away from a multi-key is not supported, the current key must be a single ed25519 key");
+        }
+
         println!("\nWARN: you will be rotating the keys for an account, this could permanently lock you out!");
 
         let sender_account = sender.local_account.address();
 
-        let rotating_account = if let Some(claim) = self.claim_address {
+        let claim_address = match &self.claim_address {
+            Some(claim) => Some(claim.resolve(app_cfg)?),
+            None => None,
+        };
+        let rotating_account = if let Some(claim) = claim_address {
             println!("You have set --claim-address, this means you are claiming someone else's account: {}", &claim);
             println!("IMPORTANT: The account submitting the transaction {} must have previously been delegated responsibility for rotating the keys of the address being claimed", &sender_account.short_str_lossless());
 
@@ -111,6 +132,12 @@ impl RotateKeyTx {
             anyhow::bail!("better safe than sorry, exiting.");
         }
 
+        if let Some(path) = &self.to_multi_key_file {
+            return self
+                .run_multi_key(sender, claim_address, path, confirm)
+                .await;
+        }
+
         let new_private_key = if let Some(pk) = &self.new_private_key {
             Ed25519PrivateKey::from_encoded_string(pk)?
         } else {
@@ -121,7 +148,7 @@ impl RotateKeyTx {
         };
 
         let seq = sender.client().get_sequence_number(sender_account).await?;
-        let payload = if let Some(target_account_address) = self.claim_address {
+        let payload = if let Some(target_account_address) = claim_address {
             let target_account = sender
                 .client()
                 .get_account(target_account_address)
@@ -157,6 +184,106 @@ impl RotateKeyTx {
         sender.sign_submit_wait(payload).await?;
         Ok(())
     }
+
+    /// Rotate to a multi-key. The first run stages the rotation challenge in the
+    /// file, the new key holders sign it, and a second run submits the rotation.
+    async fn run_multi_key(
+        &self,
+        sender: &mut Sender,
+        claim_address: Option<AccountAddress>,
+        path: &Path,
+        confirm: bool,
+    ) -> anyhow::Result<()> {
+        let sender_account = sender.local_account.address();
+        let seq = sender.client().get_sequence_number(sender_account).await?;
+
+        let (originator, current_auth_key) = if let Some(target) = claim_address {
+            let target_account = sender.client().get_account(target).await?.into_inner();
+            (target, target_account.authentication_key)
+        } else {
+            (sender_account, sender.local_account.authentication_key())
+        };
+
+        let mut multi_key = MultiKeyFile::read(path)?;
+        let rotation_msg = rotation_challenge(
+            originator,
+            &current_auth_key,
+            seq,
+            multi_key.public_key()?.to_bytes(),
+        )?;
+
+        if !multi_key.is_staged(&rotation_msg) {
+            if !multi_key.signatures.is_empty() {
+                anyhow::bail!(
+                    "{} has another pending request with {} signatures: {}. Submit it, or remove it from the file, before staging the rotation",
+                    path.display(),
+                    multi_key.signatures.len(),
+                    multi_key.description.as_deref().unwrap_or("(no description)")
+                );
+            }
+            multi_key.stage_message(
+                &rotation_msg,
+                format!(
+                    "rotate the authentication key of account {} to this multi-key",
+                    originator
+                ),
+            );
+            multi_key.save(path)?;
+        }
+
+        if !multi_key.is_complete() {
+            println!(
+                "the rotation challenge is staged in {}, it has {} of {} signatures. The new key holders can sign it with `txs multi-key sign`, then run this command again",
+                path.display(),
+                multi_key.signature_count(),
+                multi_key.threshold
+            );
+            return Ok(());
+        }
+
+        let payload = if let Some(target) = claim_address {
+            rotate_key_delegated_to_multi_key(seq, &target, &current_auth_key, &multi_key)
+        } else {
+            rotate_key_to_multi_key(
+                sender_account,
+                sender.local_account.private_key().to_owned(),
+                current_auth_key,
+                seq,
+                &multi_key,
+            )
+        }?;
+
+        if confirm {
+            let msg = format!("\nYou are rotating account: {}\nto a {}-of-{} multi-key with authentication key: {}\nThis will be permanent, you will get no other confirmation! Do you wish to continue? ", originator, multi_key.threshold, multi_key.public_keys.len(), multi_key.auth_key()?);
+
+            if !Confirm::new().with_prompt(msg).interact().unwrap() {
+                anyhow::bail!("better safe than sorry, exiting.");
+            }
+        }
+
+        sender.sign_submit_wait(payload).await?;
+        Ok(())
+    }
+}
+
+/// form a rotation proof challenge and get its bytes. See account.move
+fn rotation_challenge(
+    originator: AccountAddress,
+    current_auth_key: &AuthenticationKey,
+    sequence_number: u64,
+    new_public_key: Vec<u8>,
+) -> anyhow::Result<Vec<u8>> {
+    let rotation_proof = RotationProofChallenge {
+        account_address: CORE_CODE_ADDRESS,
+        module_name: "account".to_string(),
+        struct_name: "RotationProofChallenge".to_string(),
+        sequence_number,
+        originator,
+        current_auth_key: AccountAddress::from_bytes(current_auth_key)?,
+        new_public_key,
+    };
+
+    Ok(bcs::to_bytes(&rotation_proof)?)
 }
 
 /// create the TransactionPayload for a key rotation (a signed rotation challenge)
@@ -167,19 +294,12 @@ pub fn rotate_key(
     sequence_number: u64,
     new_private_key: &Ed25519PrivateKey,
 ) -> anyhow::Result<TransactionPayload> {
-    // form a rotation proof challenge. See account.move
-    let rotation_proof = RotationProofChallenge {
-        account_address: CORE_CODE_ADDRESS,
-        module_name: "account".to_string(),
-        struct_name: "RotationProofChallenge".to_string(),
+    let rotation_msg = rotation_challenge(
+        sender_address,
+        &auth_key,
         sequence_number,
-        originator: sender_address,
-        current_auth_key: AccountAddress::from_bytes(auth_key)?,
-        new_public_key: new_private_key.public_key().to_bytes().to_vec(),
-    };
-
-    // get the bytes of the challenge
-    let rotation_msg = bcs::to_bytes(&rotation_proof)?;
+        new_private_key.public_key().to_bytes().to_vec(),
+    )?;
 
     // Signs the struct using both the current private key and the next private key
     let rotation_proof_signed_by_current_private_key =
@@ -211,17 +331,12 @@ pub fn rotate_key_delegated(
     new_private_key: &Ed25519PrivateKey,
 ) -> anyhow::Result<TransactionPayload> {
     let new_public_key = Ed25519PublicKey::from(new_private_key);
-    let rotation_proof = RotationProofChallenge {
-        account_address: CORE_CODE_ADDRESS,
-        module_name: String::from("account"),
-        struct_name: String::from("RotationProofChallenge"),
+    let rotation_msg = rotation_challenge(
+        *target_account_address,
+        target_auth_key,
         sequence_number,
-        originator: *target_account_address,
-        current_auth_key: AccountAddress::from_bytes(target_auth_key)?,
-        new_public_key: new_public_key.to_bytes().to_vec(),
-    };
-
-    let rotation_msg = bcs::to_bytes(&rotation_proof)?;
+        new_public_key.to_bytes().to_vec(),
+    )?;
 
     // Signs the struct using the next private key
     let rotation_proof_signed_by_new_private_key =
@@ -237,6 +352,71 @@ pub fn rotate_key_delegated(
     Ok(payload)
 }
 
+/// create the TransactionPayload rotating to a multi-ed25519 key. The rotation
+/// challenge must already be signed by enough of the new key holders.
+pub fn rotate_key_to_multi_key(
+    sender_address: AccountAddress,
+    current_private_key: Ed25519PrivateKey,
+    auth_key: AuthenticationKey,
+    sequence_number: u64,
+    multi_key: &MultiKeyFile,
+) -> anyhow::Result<TransactionPayload> {
+    let new_public_key = multi_key.public_key()?.to_bytes();
+    let rotation_msg = rotation_challenge(
+        sender_address,
+        &auth_key,
+        sequence_number,
+        new_public_key.clone(),
+    )?;
+    if !multi_key.is_staged(&rotation_msg) {
+        anyhow::bail!("the multi-key file has not signed this rotation challenge");
+    }
+
+    let rotation_proof_signed_by_current_private_key =
+        current_private_key.sign_arbitrary_message(&rotation_msg);
+
+    let payload = lotus_stdlib::account_rotate_authentication_key(
+        0,
+        current_private_key.public_key().to_bytes().to_vec(),
+        1,
+        new_public_key,
+        rotation_proof_signed_by_current_private_key
+            .to_bytes()
+            .to_vec(),
+        multi_key.multi_signature()?.to_bytes(),
+    );
+
+    Ok(payload)
+}
+
+/// Create the TransactionPayload for a delegated rotation to a multi-ed25519 key
+pub fn rotate_key_delegated_to_multi_key(
+    sequence_number: u64,
+    target_account_address: &AccountAddress,
+    target_auth_key: &AuthenticationKey,
+    multi_key: &MultiKeyFile,
+) -> anyhow::Result<TransactionPayload> {
+    let new_public_key = multi_key.public_key()?.to_bytes();
+    let rotation_msg = rotation_challenge(
+        *target_account_address,
+        target_auth_key,
+        sequence_number,
+        new_public_key.clone(),
+    )?;
+    if !multi_key.is_staged(&rotation_msg) {
+        anyhow::bail!("the multi-key file has not signed this rotation challenge");
+    }
+
+    let payload = lotus_stdlib::account_rotate_authentication_key_with_rotation_capability(
+        *target_account_address,
+        1,
+        new_public_key,
+        multi_key.multi_signature()?.to_bytes(),
+    );
+
+    Ok(payload)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct RotationCapabilityOfferProofChallengeV2 {
     account_address: AccountAddress,
@@ -255,12 +435,14 @@ pub struct RotationCapabilityTx {
     #[clap(short, long)]
     pub revoke: bool,
 
-    #[clap(short, long)]
-    pub delegate_address: AccountAddress,
+    #[clap(short, long, value_parser = parse_address_arg)]
+    /// The delegate, or @name of a contact
+    pub delegate_address: AddressArg,
 }
 
 impl RotationCapabilityTx {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        let delegate_address = self.delegate_address.resolve(app_cfg)?;
         let user_account = sender.local_account.address();
         let index_response = sender.client().get_index().await?;
 
@@ -268,14 +450,9 @@ impl RotationCapabilityTx {
 
         let seq = sender.client().get_sequence_number(user_account).await?;
         let payload = if self.revoke {
-            revoke_rotation_capability(self.delegate_address)
+            revoke_rotation_capability(delegate_address)
         } else {
-            offer_rotation_capability_v2(
-                &sender.local_account,
-                self.delegate_address,
-                chain_id,
-                seq,
-            )
+            offer_rotation_capability_v2(&sender.local_account, delegate_address, chain_id, seq)
         }?;
 
         sender.sign_submit_wait(payload).await?;
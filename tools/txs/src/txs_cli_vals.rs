@@ -3,13 +3,18 @@
 use crate::submit_transaction::Sender;
 use anyhow::{bail, Context};
 use diem_genesis::config::OperatorConfiguration;
-use diem_types::account_address::AccountAddress;
 use lotus_cached_packages::lotus_stdlib::EntryFunctionCall::{
     self, JailUnjailByVoucher, ProofOfFeePofRetractBid, ProofOfFeePofUpdateBid,
     StakeUpdateNetworkAndFullnodeAddresses, ValidatorUniverseRegisterValidator, VouchRevoke,
     VouchVouchFor,
 };
-use lotus_types::global_config_dir;
+use lotus_types::{
+    core_types::{
+        address_book::{parse_address_arg, AddressArg},
+        app_cfg::AppCfg,
+    },
+    global_config_dir,
+};
 use lotus_wallet::validator_files::OPERATOR_FILE;
 use std::{fs, path::PathBuf};
 
@@ -30,15 +35,15 @@ pub enum ValidatorTxs {
     },
     /// Jail and unjail transactions
     Jail {
-        #[clap(short, long)]
+        #[clap(short, long, value_parser = parse_address_arg)]
         /// Un-jail this validator. Used by any validators which are vouching for a validator which is jailed
-        unjail_acct: AccountAddress,
+        unjail_acct: AddressArg,
     },
     /// Vouch for accounts
     Vouch {
-        #[clap(short, long)]
-        /// Vouch for another account, usually for validators
-        vouch_for: AccountAddress,
+        #[clap(short, long, value_parser = parse_address_arg)]
+        /// Vouch for another account, usually for validators. Or @name of a contact
+        vouch_for: AddressArg,
         #[clap(short, long)]
         /// Revoke a vouch for an account
         revoke: bool,
@@ -58,14 +63,15 @@ pub enum ValidatorTxs {
 }
 
 impl ValidatorTxs {
-    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
-        let payload = self.make_payload()?;
+    pub async fn run(&self, sender: &mut Sender, app_cfg: &AppCfg) -> anyhow::Result<()> {
+        let payload = self.make_payload(app_cfg)?;
         sender.sign_submit_wait(payload.encode()).await?;
         Ok(())
     }
 
     //  Create the Entry function which the txs will run.
-    pub fn make_payload(&self) -> anyhow::Result<EntryFunctionCall> {
+    /// Contacts in address arguments are resolved from the app_cfg
+    pub fn make_payload(&self, app_cfg: &AppCfg) -> anyhow::Result<EntryFunctionCall> {
         let p = match self {
             ValidatorTxs::Pof {
                 bid_pct,
@@ -89,19 +95,20 @@ impl ValidatorTxs {
                 }
             }
             ValidatorTxs::Jail { unjail_acct } => JailUnjailByVoucher {
-                addr: unjail_acct.to_owned(),
+                addr: unjail_acct.resolve(app_cfg)?,
             },
             ValidatorTxs::Vouch {
                 vouch_for: vouch_acct,
                 revoke,
             } => {
+                let vouch_acct = vouch_acct.resolve(app_cfg)?;
                 if *revoke {
                     VouchRevoke {
-                        its_not_me_its_you: *vouch_acct,
+                        its_not_me_its_you: vouch_acct,
                     }
                 } else {
                     VouchVouchFor {
-                        wanna_be_my_friend: *vouch_acct,
+                        wanna_be_my_friend: vouch_acct,
                     }
                 }
             }
@@ -201,7 +201,7 @@ async fn new_community_wallet_cant_transfer() -> Result<(), anyhow::Error> {
     // Transfer funds to ensure the account exists on-chain
     let cli_transfer = TxsCli {
         subcommand: Some(Transfer {
-            to_account: new_admin_address,
+            to_account: new_admin_address.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -255,7 +255,7 @@ async fn new_community_wallet_cant_transfer() -> Result<(), anyhow::Error> {
     // Attempt to create a transfer from the new community wallet
     let transfer_cli = TxsCli {
         subcommand: Some(Transfer {
-            to_account: new_admin_address,
+            to_account: new_admin_address.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -700,7 +700,7 @@ async fn community_wallet_payment() -> Result<(), anyhow::Error> {
     // Configuration for transferring funds to new admin account
     let cli_transfer = TxsCli {
         subcommand: Some(Transfer {
-            to_account: new_worker_address,
+            to_account: new_worker_address.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -779,8 +779,8 @@ async fn community_wallet_payment() -> Result<(), anyhow::Error> {
     // Configure payment proposal command
     let cli_propose_payment = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::Propose(ProposeTx {
-            community_wallet: comm_wallet_addr,
-            recipient: new_worker_address,
+            community_wallet: comm_wallet_addr.into(),
+            recipient: new_worker_address.into(),
             amount: 10,
             description: "Thanks Mate".to_string(),
         }))),
@@ -855,8 +855,8 @@ async fn add_community_wallet_admin() -> Result<(), anyhow::Error> {
 
     let cli_add_new_admin_proposal = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::GovAdmin(AdminTx {
-            community_wallet: comm_wallet_addr,
-            admin: new_admin_address,
+            community_wallet: comm_wallet_addr.into(),
+            admin: new_admin_address.into(),
             drop: Some(true),
             n: 3,
             epochs: Some(10),
@@ -870,6 +870,8 @@ async fn add_community_wallet_admin() -> Result<(), anyhow::Error> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli_add_new_admin_proposal
@@ -915,8 +917,8 @@ async fn add_community_wallet_admin() -> Result<(), anyhow::Error> {
             .expect("cannot decode pri key");
         let cli_add_new_admin_proposal = TxsCli {
             subcommand: Some(TxsSub::Community(CommunityTxs::GovAdmin(AdminTx {
-                community_wallet: comm_wallet_addr,
-                admin: new_admin_address,
+                community_wallet: comm_wallet_addr.into(),
+                admin: new_admin_address.into(),
                 drop: Some(true),
                 n: 3,
                 epochs: Some(10),
@@ -930,6 +932,8 @@ async fn add_community_wallet_admin() -> Result<(), anyhow::Error> {
             tx_cost: Some(TxCost::default_baseline_cost()),
             estimate_only: false,
             legacy_address: false,
+            multi_key_file: None,
+            profile: None,
         };
 
         cli_add_new_admin_proposal
@@ -1093,8 +1097,8 @@ async fn remove_community_wallet_admin() -> Result<(), anyhow::Error> {
 
     let cli_add_new_admin_proposal = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::GovAdmin(AdminTx {
-            community_wallet: comm_wallet_addr,
-            admin: admin_to_remove,
+            community_wallet: comm_wallet_addr.into(),
+            admin: admin_to_remove.into(),
             drop: Some(false),
             n: 2,
             epochs: Some(10),
@@ -1108,6 +1112,8 @@ async fn remove_community_wallet_admin() -> Result<(), anyhow::Error> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli_add_new_admin_proposal
@@ -1154,8 +1160,8 @@ async fn remove_community_wallet_admin() -> Result<(), anyhow::Error> {
             .expect("cannot decode pri key");
         let cli_add_new_admin_proposal = TxsCli {
             subcommand: Some(TxsSub::Community(CommunityTxs::GovAdmin(AdminTx {
-                community_wallet: comm_wallet_addr,
-                admin: admin_to_remove,
+                community_wallet: comm_wallet_addr.into(),
+                admin: admin_to_remove.into(),
                 drop: Some(false),
                 n: 2,
                 epochs: Some(10),
@@ -1169,6 +1175,8 @@ async fn remove_community_wallet_admin() -> Result<(), anyhow::Error> {
             tx_cost: Some(TxCost::default_baseline_cost()),
             estimate_only: false,
             legacy_address: false,
+            multi_key_file: None,
+            profile: None,
         };
 
         cli_add_new_admin_proposal
@@ -1309,7 +1317,7 @@ async fn cancel_community_wallet_payment() -> Result<(), anyhow::Error> {
     // Transfer funds to ensure the account exists on-chain
     let cli_transfer = TxsCli {
         subcommand: Some(Transfer {
-            to_account: new_worker_address,
+            to_account: new_worker_address.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -1385,8 +1393,8 @@ async fn cancel_community_wallet_payment() -> Result<(), anyhow::Error> {
     // Propose payment signer 1
     let cli_propose_payment = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::Propose(ProposeTx {
-            community_wallet: comm_wallet_addr,
-            recipient: new_worker_address,
+            community_wallet: comm_wallet_addr.into(),
+            recipient: new_worker_address.into(),
             amount: 10,
             description: "Thanks Mate".to_string(),
         }))),
@@ -1415,8 +1423,8 @@ async fn cancel_community_wallet_payment() -> Result<(), anyhow::Error> {
     // Propose payment signer 2
     let cli_propose_payment_signer_two = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::Propose(ProposeTx {
-            community_wallet: comm_wallet_addr,
-            recipient: new_worker_address,
+            community_wallet: comm_wallet_addr.into(),
+            recipient: new_worker_address.into(),
             amount: 10,
             description: "Thanks Mate".to_string(),
         }))),
@@ -1458,7 +1466,7 @@ async fn cancel_community_wallet_payment() -> Result<(), anyhow::Error> {
 
     let cli_transfer = TxsCli {
         subcommand: Some(Transfer {
-            to_account: comm_wallet_addr,
+            to_account: comm_wallet_addr.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -1539,7 +1547,7 @@ async fn setup_environment() -> (LotusSmoke, TempPath, AccountAddress, String, A
     // Transfer funds to ensure the account exists on-chain
     let cli_transfer = TxsCli {
         subcommand: Some(Transfer {
-            to_account: account_address_wrapped,
+            to_account: account_address_wrapped.into(),
             amount: 100.0,
         }),
         mnemonic: None,
@@ -1551,6 +1559,8 @@ async fn setup_environment() -> (LotusSmoke, TempPath, AccountAddress, String, A
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli_transfer
@@ -1592,7 +1602,10 @@ async fn run_cli_transfer(
 ) {
     // Build the CLI command
     let cli_transfer = TxsCli {
-        subcommand: Some(Transfer { to_account, amount }),
+        subcommand: Some(Transfer {
+            to_account: to_account.into(),
+            amount,
+        }),
         mnemonic: None,
         test_private_key: Some(private_key),
         chain_id: None,
@@ -1602,6 +1615,8 @@ async fn run_cli_transfer(
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     // Execute the transfer
@@ -1621,7 +1636,7 @@ async fn run_cli_community_init(
     // Build the CLI command
     let cli_set_community_wallet = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::GovInit(InitTx {
-            admins,
+            admins: admins.into_iter().map(Into::into).collect(),
             num_signers,
         }))),
         mnemonic: None,
@@ -1633,6 +1648,8 @@ async fn run_cli_community_init(
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     // Execute the transaction
@@ -1650,7 +1667,7 @@ async fn run_cli_claim_offer(
 ) {
     let cli_claim_offer = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::GovClaim(ClaimTx {
-            community_wallet: community_address,
+            community_wallet: community_address.into(),
         }))),
         mnemonic: None,
         test_private_key: Some(signer_pk),
@@ -1661,6 +1678,8 @@ async fn run_cli_claim_offer(
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli_claim_offer
@@ -1688,6 +1707,8 @@ async fn run_cli_community_cage(
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli_finalize_cage
@@ -1705,7 +1726,7 @@ async fn run_cli_community_propose_offer(
 ) {
     let cli_propose_offer = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::GovOffer(OfferTx {
-            admins,
+            admins: admins.into_iter().map(Into::into).collect(),
             num_signers,
         }))),
         mnemonic: None,
@@ -1717,6 +1738,8 @@ async fn run_cli_community_propose_offer(
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli_propose_offer
@@ -1807,6 +1830,8 @@ async fn test_offer_migration() -> Result<(), anyhow::Error> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     init_gov_deprecated
@@ -1832,7 +1857,7 @@ async fn test_offer_migration() -> Result<(), anyhow::Error> {
     // 4. Run offer migration
     let offer_migration = TxsCli {
         subcommand: Some(TxsSub::Community(CommunityTxs::Migration(MigrateOfferTx {
-            community_wallet: community_wallet_address,
+            community_wallet: community_wallet_address.into(),
         }))),
         mnemonic: None,
         test_private_key: Some(community_wallet_pk),
@@ -1843,6 +1868,8 @@ async fn test_offer_migration() -> Result<(), anyhow::Error> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     offer_migration
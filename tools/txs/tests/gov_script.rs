@@ -46,6 +46,8 @@ async fn smoke_gov_script() {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli.run()
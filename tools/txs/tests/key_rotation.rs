@@ -1,7 +1,11 @@
-use diem_sdk::crypto::{ed25519::Ed25519PrivateKey, Uniform, ValidCryptoMaterialStringExt};
+use diem_sdk::{
+    crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform, ValidCryptoMaterialStringExt},
+    types::{chain_id::ChainId, AccountKey},
+};
 use lotus_smoke_tests::lotus_smoke::LotusSmoke;
 use lotus_txs::{
     submit_transaction::Sender,
+    txs_cli_multi_key::MultiKeyFile,
     txs_cli_user::{RotateKeyTx, RotationCapabilityTx},
 };
 use lotus_types::core_types::app_cfg::Profile;
@@ -70,9 +74,10 @@ async fn rotate_key() -> anyhow::Result<()> {
     let cli = RotateKeyTx {
         new_private_key: Some(generated_private_key_encoded.unwrap()),
         claim_address: None,
+        to_multi_key_file: None,
     };
 
-    let res = cli.run(&mut alice_sender, false).await;
+    let res = cli.run(&mut alice_sender, &val_app_cfg, false).await;
     assert!(res.is_ok());
 
     // check new auth key
@@ -144,11 +149,11 @@ async fn offer_rotation_capability() -> anyhow::Result<()> {
 
     // allow bob to rotate keys for alice
     let cli = RotationCapabilityTx {
-        delegate_address: bob_sender.local_account.address(),
+        delegate_address: bob_sender.local_account.address().into(),
         revoke: false,
     };
 
-    let res = cli.run(&mut alice_sender).await;
+    let res = cli.run(&mut alice_sender, &val_app_cfg).await;
     match res.as_ref() {
         Ok(_) => {}
         Err(err) => {
@@ -165,10 +170,11 @@ async fn offer_rotation_capability() -> anyhow::Result<()> {
 
     let cli = RotateKeyTx {
         new_private_key: Some(generated_private_key_encoded.unwrap()),
-        claim_address: Some(alice_acct.to_owned()),
+        claim_address: Some(alice_acct.to_owned().into()),
+        to_multi_key_file: None,
     };
 
-    let res_rotation = cli.run(&mut bob_sender, false).await;
+    let res_rotation = cli.run(&mut bob_sender, &val_app_cfg, false).await;
     match res_rotation.as_ref() {
         Ok(_) => {}
         Err(err) => {
@@ -245,11 +251,11 @@ async fn revoke_rotation_capability() -> anyhow::Result<()> {
 
     // allow bob to rotate keys for alice
     let cli = RotationCapabilityTx {
-        delegate_address: bob_sender.local_account.address(),
+        delegate_address: bob_sender.local_account.address().into(),
         revoke: false,
     };
 
-    let res = cli.run(&mut alice_sender).await;
+    let res = cli.run(&mut alice_sender, &val_app_cfg).await;
     match res.as_ref() {
         Ok(_) => {}
         Err(err) => {
@@ -260,11 +266,11 @@ async fn revoke_rotation_capability() -> anyhow::Result<()> {
 
     // revoke rotation capability from bob
     let cli = RotationCapabilityTx {
-        delegate_address: bob_sender.local_account.address(),
+        delegate_address: bob_sender.local_account.address().into(),
         revoke: true,
     };
 
-    let res = cli.run(&mut alice_sender).await;
+    let res = cli.run(&mut alice_sender, &val_app_cfg).await;
     match res.as_ref() {
         Ok(_) => {}
         Err(err) => {
@@ -281,10 +287,11 @@ async fn revoke_rotation_capability() -> anyhow::Result<()> {
 
     let cli = RotateKeyTx {
         new_private_key: Some(generated_private_key_encoded.unwrap()),
-        claim_address: Some(alice_acct.to_owned()),
+        claim_address: Some(alice_acct.to_owned().into()),
+        to_multi_key_file: None,
     };
 
-    let res_rotation = cli.run(&mut bob_sender, false).await;
+    let res_rotation = cli.run(&mut bob_sender, &val_app_cfg, false).await;
     match res_rotation.as_ref() {
         Ok(_) => {}
         Err(err) => {
@@ -295,3 +302,109 @@ async fn revoke_rotation_capability() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Test rotating to a 2-of-3 multi-key, and sending from it with partial signatures
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn rotate_key_to_multi_key() -> anyhow::Result<()> {
+    let mut ls = LotusSmoke::new(Some(1), None)
+        .await
+        .expect("could not start lotus smoke");
+    let mut val_app_cfg = ls.first_account_app_cfg()?;
+    let d = diem_temppath::TempPath::new();
+    d.create_as_dir()?;
+    let multi_key_path = d.path().join("multi_key.json");
+
+    let alice = account_keys::get_keys_from_mnem("talent sunset lizard pill fame nuclear spy noodle basket okay critic grow sleep legend hurry pitch blanket clerk impose rough degree sock insane purse".to_owned())?;
+
+    // create an account for alice by transferring funds
+    let mut s = Sender::from_app_cfg(&val_app_cfg, None).await?;
+    let res = s
+        .transfer(alice.child_0_owner.account, 100.0, false)
+        .await?
+        .unwrap();
+    assert!(res.info.status().is_success());
+
+    let mut p = Profile::new(alice.child_0_owner.auth_key, alice.child_0_owner.account);
+    p.set_private_key(&alice.child_0_owner.pri_key);
+    val_app_cfg.maybe_add_profile(p)?;
+    let mut alice_sender =
+        Sender::from_app_cfg(&val_app_cfg, Some(alice.child_0_owner.account.to_string())).await?;
+
+    // three key holders, any two can sign
+    let holders: Vec<Ed25519PrivateKey> = (1..=3u8)
+        .map(|i| Ed25519PrivateKey::try_from([i; 32].as_slice()).unwrap())
+        .collect();
+    let public_keys: Vec<_> = holders.iter().map(|k| k.public_key()).collect();
+    MultiKeyFile::new(&public_keys, 2)?.save(&multi_key_path)?;
+
+    let cli = RotateKeyTx {
+        new_private_key: None,
+        claim_address: None,
+        to_multi_key_file: Some(multi_key_path.clone()),
+    };
+
+    // first run only stages the rotation challenge
+    cli.run(&mut alice_sender, &val_app_cfg, false).await?;
+    assert_eq!(MultiKeyFile::read(&multi_key_path)?.signature_count(), 0);
+
+    let mut multi_key = MultiKeyFile::read(&multi_key_path)?;
+    multi_key.sign(&holders[0])?;
+    multi_key.sign(&holders[2])?;
+    multi_key.save(&multi_key_path)?;
+    assert!(multi_key.is_complete());
+
+    cli.run(&mut alice_sender, &val_app_cfg, false).await?;
+
+    let account_updated = ls
+        .client()
+        .get_account(alice.child_0_owner.account)
+        .await?
+        .into_inner();
+    assert_eq!(account_updated.authentication_key, multi_key.auth_key()?);
+
+    // now send from the multi-key account, the first key holder stages the tx
+    let chain_id = ChainId::new(ls.client().get_index().await?.into_inner().chain_id);
+    let mut multi_sender = Sender::from_multi_key_file(
+        multi_key_path.clone(),
+        AccountKey::from_private_key(holders[0].clone()),
+        chain_id,
+        Some(ls.client()),
+    )
+    .await?;
+    assert_eq!(
+        multi_sender.local_account.address(),
+        alice.child_0_owner.account
+    );
+
+    let recipient = ls.marlon_rando().address();
+    assert!(multi_sender
+        .transfer(recipient, 1.0, false)
+        .await?
+        .is_none());
+    let staged = MultiKeyFile::read(&multi_key_path)?;
+    assert_eq!(staged.signature_count(), 1);
+
+    // the signed request is not replaced by another transaction
+    assert!(multi_sender.transfer(recipient, 2.0, false).await.is_err());
+    assert_eq!(MultiKeyFile::read(&multi_key_path)?.raw_txn, staged.raw_txn);
+
+    // a key which is not one of the multi-key's can't sign for it
+    let mut outsider = Sender::from_multi_key_file(
+        multi_key_path.clone(),
+        AccountKey::from_private_key(Ed25519PrivateKey::try_from([9u8; 32].as_slice())?),
+        chain_id,
+        Some(ls.client()),
+    )
+    .await?;
+    assert!(outsider.transfer(recipient, 1.0, false).await.is_err());
+
+    // a second key holder signs and the transaction can be sent
+    let mut multi_key = MultiKeyFile::read(&multi_key_path)?;
+    assert_eq!(multi_key.signature_count(), 1);
+    multi_key.sign(&holders[1])?;
+    let signed = multi_key.signed_transaction()?;
+    let res = multi_sender.submit(&signed).await?;
+    assert!(res.info.status().is_success());
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use lotus_query::account_queries::{
+    multisig_account_state, multisig_next_account_address, multisig_owners,
+};
+use lotus_smoke_tests::lotus_smoke::LotusSmoke;
+use lotus_txs::{submit_transaction::Sender, txs_cli_multisig::MultisigTxs};
+use lotus_types::core_types::address_book::AddressArg;
+
+// Testing the native multisig accounts of the vendor multisig_account module.
+// A 1-of-1 multisig proposes adding a second owner, which executes right away.
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn multisig_propose_and_execute() -> anyhow::Result<()> {
+    let mut ls = LotusSmoke::new(Some(1), None)
+        .await
+        .expect("could not start lotus smoke");
+    let mut val_app_cfg = ls.first_account_app_cfg()?;
+    let mut s = Sender::from_app_cfg(&val_app_cfg, None).await?;
+    let creator = s.local_account.address();
+
+    let multisig_address = multisig_next_account_address(s.client(), creator).await?;
+
+    MultisigTxs::Create {
+        owners: vec![],
+        num_signatures: 1,
+    }
+    .run(&mut s, &val_app_cfg)
+    .await?;
+
+    let owners = multisig_owners(s.client(), multisig_address).await?;
+    assert_eq!(owners, vec![creator]);
+
+    // the new owner is named from the address book
+    let bob = ls.marlon_rando().address();
+    val_app_cfg.add_contact("bob", bob, None)?;
+    MultisigTxs::AddOwners {
+        multisig_address: multisig_address.into(),
+        owners: vec![AddressArg::Contact("bob".to_owned())],
+        execute: true,
+    }
+    .run(&mut s, &val_app_cfg)
+    .await?;
+
+    let owners = multisig_owners(s.client(), multisig_address).await?;
+    assert!(owners.contains(&bob));
+
+    let state = multisig_account_state(s.client(), multisig_address).await?;
+    assert_eq!(state["num_signatures_required"], 1);
+    assert!(state["pending_transactions"].as_array().unwrap().is_empty());
+
+    // failures reach the caller
+    assert!(MultisigTxs::Execute {
+        multisig_address: multisig_address.into()
+    }
+    .run(&mut s, &val_app_cfg)
+    .await
+    .is_err());
+
+    Ok(())
+}
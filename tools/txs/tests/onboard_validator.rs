@@ -41,7 +41,7 @@ async fn smoke_onboard_validator() -> anyhow::Result<()> {
     // 1. CREATE THE ACCOUNT
     let alice_cli = TxsCli {
         subcommand: Some(Transfer {
-            to_account: new_val_address,
+            to_account: new_val_address.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -53,6 +53,8 @@ async fn smoke_onboard_validator() -> anyhow::Result<()> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     alice_cli
@@ -81,6 +83,8 @@ async fn smoke_onboard_validator() -> anyhow::Result<()> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     rando_cli
@@ -43,6 +43,8 @@ async fn smoke_publish() {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli.run().await.expect("cli could not publish contract");
@@ -32,7 +32,7 @@ async fn smoke_transfer_existing_account() {
     let recipient = s.swarm.validators().nth(1).unwrap().peer_id(); // sending to second genesis node.
     let cli = TxsCli {
         subcommand: Some(Transfer {
-            to_account: recipient,
+            to_account: recipient.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -44,6 +44,8 @@ async fn smoke_transfer_existing_account() {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli.run()
@@ -73,7 +75,7 @@ async fn smoke_transfer_create_account() -> Result<(), anyhow::Error> {
     // case 2. Account does not yet exist.
     let cli = TxsCli {
         subcommand: Some(Transfer {
-            to_account: marlon,
+            to_account: marlon.into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -85,6 +87,8 @@ async fn smoke_transfer_create_account() -> Result<(), anyhow::Error> {
         tx_cost: Some(TxCost::default_baseline_cost()),
         estimate_only: false,
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli.run()
@@ -117,7 +121,7 @@ async fn smoke_transfer_estimate() {
     // case 2. Account does not yet exist.
     let cli = TxsCli {
         subcommand: Some(Transfer {
-            to_account: s.marlon_rando().address(),
+            to_account: s.marlon_rando().address().into(),
             amount: 1.0,
         }),
         mnemonic: None,
@@ -129,6 +133,8 @@ async fn smoke_transfer_estimate() {
         tx_cost: Some(TxCost::default_cheap_txs_cost()),
         estimate_only: true, // THIS IS THE TEST
         legacy_address: false,
+        multi_key_file: None,
+        profile: None,
     };
 
     cli.run().await.expect("could not get estimate");
@@ -0,0 +1,166 @@
+//! Address book of named accounts, so addresses don't need to be pasted around.
+//! Any address argument of the cli tools can be given as `@name`.
+
+use crate::{core_types::app_cfg::AppCfg, exports::AccountAddress};
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+/// An account which is not ours, saved under a name
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Contact {
+    /// name used to refer to the account, as `@name`
+    pub name: String,
+    /// the account address
+    pub address: AccountAddress,
+    /// optional, a note about the account
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Parse a hex address with or without the 0x prefix. Legacy (v5) 16-byte
+/// addresses are padded with zeros to the full 32 bytes.
+pub fn parse_hex_address(s: &str) -> anyhow::Result<AccountAddress> {
+    let hex = s.trim().trim_start_matches("0x");
+    if hex.is_empty() || hex.len() > AccountAddress::LENGTH * 2 {
+        bail!("{} is not an account address", s);
+    }
+    AccountAddress::from_hex_literal(&format!("0x{}", hex))
+        .with_context(|| format!("{} is not an account address", s))
+}
+
+/// An address argument of the cli tools, a hex address or `@name` of a
+/// contact. Contacts are resolved once the tool has loaded its config file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AddressArg {
+    Address(AccountAddress),
+    Contact(String),
+}
+
+impl From<AccountAddress> for AddressArg {
+    fn from(a: AccountAddress) -> Self {
+        AddressArg::Address(a)
+    }
+}
+
+impl AddressArg {
+    /// the address, looking up a contact in the config.
+    /// A contact is printed as it resolves, so it can be checked before signing.
+    pub fn resolve(&self, app_cfg: &AppCfg) -> anyhow::Result<AccountAddress> {
+        match self {
+            AddressArg::Address(a) => Ok(*a),
+            AddressArg::Contact(name) => {
+                let contact = app_cfg.find_contact(name)?;
+                eprintln!("@{} resolves to {}", contact.name, contact.address);
+                Ok(contact.address)
+            }
+        }
+    }
+
+    /// the address of an argument which has been resolved
+    pub fn address(&self) -> anyhow::Result<AccountAddress> {
+        match self {
+            AddressArg::Address(a) => Ok(*a),
+            AddressArg::Contact(name) => {
+                bail!("@{} needs to be resolved against the config file", name)
+            }
+        }
+    }
+}
+
+/// resolve a list of address arguments, e.g. the owners of a multisig
+pub fn resolve_all(args: &[AddressArg], app_cfg: &AppCfg) -> anyhow::Result<Vec<AccountAddress>> {
+    args.iter().map(|a| a.resolve(app_cfg)).collect()
+}
+
+/// Value parser for address arguments of the cli tools. Accepts a hex address,
+/// or `@name` of a contact, which is kept as is until `AddressArg::resolve`.
+pub fn parse_address_arg(s: &str) -> anyhow::Result<AddressArg> {
+    match s.strip_prefix('@') {
+        Some(name) if !name.is_empty() => Ok(AddressArg::Contact(name.to_owned())),
+        Some(_) => bail!("{} is not a contact name", s),
+        None => Ok(AddressArg::Address(parse_hex_address(s)?)),
+    }
+}
+
+impl AppCfg {
+    /// save a contact, names are unique
+    pub fn add_contact(
+        &mut self,
+        name: &str,
+        address: AccountAddress,
+        note: Option<String>,
+    ) -> anyhow::Result<()> {
+        let name = name.trim_start_matches('@');
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            bail!("contact names cannot be empty or contain spaces");
+        }
+        if self.contacts.iter().any(|c| c.name == name) {
+            bail!("there is already a contact named {}, remove it first", name);
+        }
+        self.contacts.push(Contact {
+            name: name.to_owned(),
+            address,
+            note,
+        });
+        Ok(())
+    }
+
+    /// remove a contact by name
+    pub fn remove_contact(&mut self, name: &str) -> anyhow::Result<Contact> {
+        let name = name.trim_start_matches('@');
+        match self.contacts.iter().position(|c| c.name == name) {
+            Some(i) => Ok(self.contacts.remove(i)),
+            None => bail!("no contact named {}", name),
+        }
+    }
+
+    /// find a contact by its exact name, with or without the @
+    pub fn find_contact(&self, name: &str) -> anyhow::Result<&Contact> {
+        let name = name.trim_start_matches('@');
+        self.contacts
+            .iter()
+            .find(|c| c.name == name)
+            .with_context(|| format!("no contact named @{} in the address book", name))
+    }
+
+    /// resolve `@name` from the contacts, otherwise parse a hex address
+    pub fn resolve_address(&self, s: &str) -> anyhow::Result<AccountAddress> {
+        if s.starts_with('@') {
+            return Ok(self.find_contact(s)?.address);
+        }
+        parse_hex_address(s)
+    }
+}
+
+#[test]
+fn contacts() {
+    let mut app_cfg = AppCfg::default();
+    let alice =
+        parse_hex_address("0x87515d94a244235a1433d7117bc0cb154c613c2f4b1e67ca8d98a542ee3f59f5")
+            .unwrap();
+    app_cfg.add_contact("alice", alice, None).unwrap();
+    assert!(app_cfg.add_contact("@alice", alice, None).is_err());
+
+    assert_eq!(app_cfg.resolve_address("@alice").unwrap(), alice);
+    assert!(app_cfg.resolve_address("@bob").is_err());
+
+    // legacy addresses are the last 16 bytes
+    let legacy = parse_hex_address("f0a8c3e08a4f3bbf91e6b0e5e8e1ee75").unwrap();
+    assert_eq!(
+        legacy.to_string(),
+        "00000000000000000000000000000000f0a8c3e08a4f3bbf91e6b0e5e8e1ee75"
+    );
+    assert!(parse_hex_address("0xnothex").is_err());
+
+    // contacts are resolved against the config which the tool loaded
+    let arg = parse_address_arg("@alice").unwrap();
+    assert_eq!(arg, AddressArg::Contact("alice".to_owned()));
+    assert_eq!(arg.resolve(&app_cfg).unwrap(), alice);
+    assert!(arg.resolve(&AppCfg::default()).is_err());
+    assert!(arg.address().is_err());
+    let owners = vec![arg, AddressArg::Address(legacy)];
+    assert_eq!(resolve_all(&owners, &app_cfg).unwrap(), vec![alice, legacy]);
+
+    app_cfg.remove_contact("alice").unwrap();
+    assert!(app_cfg.contacts.is_empty());
+}
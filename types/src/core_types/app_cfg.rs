@@ -14,7 +14,10 @@ use url::Url;
 
 use std::{fs, io::Write, path::PathBuf, str::FromStr};
 
-use super::network_playlist::{self, HostProfile, NetworkPlaylist};
+use super::{
+    address_book::Contact,
+    network_playlist::{self, HostProfile, NetworkPlaylist},
+};
 
 // TODO: the GAS_UNIT_PRICE is set in DIEM. IT IS ALSO THE MINIMUM GAS PRICE This is arbitrary and needs to be reviewed.
 pub const MINUMUM_GAS_PRICE_IN_DIEM: u64 = GAS_UNIT_PRICE;
@@ -33,6 +36,9 @@ pub struct AppCfg {
     pub network_playlist: Vec<NetworkPlaylist>,
     /// Transaction configurations
     pub tx_configs: TxConfigs,
+    /// Address book of other accounts
+    #[serde(default)]
+    pub contacts: Vec<Contact>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -167,6 +173,7 @@ impl AppCfg {
             user_profiles: vec![l.profile],
             network_playlist: vec![np],
             tx_configs: l.tx_configs,
+            contacts: vec![],
         };
 
         if let Some(p) = output {
@@ -206,14 +213,7 @@ impl AppCfg {
         };
 
         if let Some(n) = nickname {
-            let found = self.user_profiles.iter().enumerate().find_map(|(i, e)| {
-                if e.nickname.contains(&n) || e.account.to_string().contains(&n) {
-                    Some(i)
-                } else {
-                    None
-                }
-            });
-            return found.context("could not find a profile");
+            return self.find_profile_idx(&n);
         };
 
         bail!("no profiles found")
@@ -256,13 +256,83 @@ impl AppCfg {
         Ok(())
     }
 
+    /// find a profile by exact nickname or address, otherwise by a fragment which
+    /// matches only one profile. Unlike get_profile, never falls back to another profile.
+    fn find_profile_idx(&self, name: &str) -> anyhow::Result<usize> {
+        let exact = self.user_profiles.iter().position(|p| {
+            p.nickname == name || AccountAddress::from_str(name).map_or(false, |a| a == p.account)
+        });
+        if let Some(i) = exact {
+            return Ok(i);
+        }
+
+        let fragment = name.trim_start_matches("0x");
+        let found: Vec<usize> = self
+            .user_profiles
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.nickname.contains(name) || p.account.to_string().contains(fragment))
+            .map(|(i, _)| i)
+            .collect();
+
+        match found.len() {
+            0 => bail!("no profile matching {}", name),
+            1 => Ok(found[0]),
+            _ => bail!(
+                "{} matches more than one profile, use the full nickname or address",
+                name
+            ),
+        }
+    }
+
+    /// get a profile by nickname or address, see find_profile_idx
+    pub fn find_profile(&self, name: &str) -> anyhow::Result<&Profile> {
+        let idx = self.find_profile_idx(name)?;
+        Ok(&self.user_profiles[idx])
+    }
+
     /// remove a profile
     pub fn try_remove_profile(&mut self, nickname: &str) -> anyhow::Result<()> {
-        let idx = self.get_profile_idx(Some(nickname.to_owned()))?;
-        self.user_profiles.remove(idx);
+        let idx = self.find_profile_idx(nickname)?;
+        let removed = self.user_profiles.remove(idx);
+
+        // don't leave the default pointing at a profile which is gone
+        if self.workspace.default_profile.as_deref() == Some(&removed.account.to_hex_literal()) {
+            self.workspace.default_profile = None;
+        }
+        Ok(())
+    }
+
+    /// set the profile used when none is selected
+    pub fn set_default_profile(&mut self, nickname: &str) -> anyhow::Result<()> {
+        let account = self.find_profile(nickname)?.account;
+        // use the address, it survives a rename
+        self.workspace.set_default(account.to_hex_literal());
         Ok(())
     }
 
+    /// give a profile a new nickname
+    pub fn rename_profile(&mut self, nickname: &str, new_nickname: &str) -> anyhow::Result<()> {
+        let idx = self.find_profile_idx(nickname)?;
+        if self
+            .user_profiles
+            .iter()
+            .enumerate()
+            .any(|(i, p)| i != idx && p.nickname == new_nickname)
+        {
+            bail!("there is already a profile named {}", new_nickname);
+        }
+        new_nickname.clone_into(&mut self.user_profiles[idx].nickname);
+        Ok(())
+    }
+
+    /// the chain a profile lives on, otherwise the workspace default
+    pub fn profile_chain(&self, profile: &Profile) -> NamedChain {
+        profile
+            .chain_name
+            .unwrap_or(self.workspace.default_chain_id)
+    }
+
     pub fn set_chain_id(&mut self, chain_id: NamedChain) {
         self.workspace.default_chain_id = chain_id;
     }
@@ -351,6 +421,7 @@ impl Default for AppCfg {
             user_profiles: vec![],
             network_playlist: vec![],
             tx_configs: TxConfigs::default(),
+            contacts: vec![],
         }
     }
 }
@@ -406,6 +477,7 @@ impl Default for ChainInfo {
 }
 
 /// Miner profile to commit this work chain to a particular identity
+#[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Profile {
     /// The 0L account for the Miner and prospective validator. This is derived from auth_key
@@ -418,6 +490,10 @@ pub struct Profile {
     test_private_key: Option<Ed25519PrivateKey>,
     /// nickname for this profile
     pub nickname: String,
+    /// the chain this account is used on. Otherwise the workspace default_chain_id
+    #[serde(default)]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub chain_name: Option<NamedChain>,
     #[serde(default)]
     /// is it already on chain
     pub on_chain: bool,
@@ -449,6 +525,7 @@ impl Default for Profile {
             test_private_key: None,
             locale: None,
             nickname: "default".to_string(),
+            chain_name: None,
             on_chain: false,
             balance: SlowWalletBalance::default(),
             upstream_nodes: None, // Note: deprecated, here for migration
@@ -654,6 +731,42 @@ async fn test_init() {
     assert!(p.auth_key == b);
 }
 
+#[test]
+fn manage_profiles() {
+    let a = AuthenticationKey::from_str(
+        "8603ba96e87b810cebbec1a0fd7ea06285f9eb352a3eabde992a5594fe80af40",
+    )
+    .unwrap();
+    let b = AuthenticationKey::from_str(
+        "052dea65ac80cd4b2b1318a9420dc1568819882769346d9acffdc0d731504c66",
+    )
+    .unwrap();
+
+    let mut app_cfg = AppCfg {
+        user_profiles: vec![Profile::new(a, a.derived_address())],
+        ..Default::default()
+    };
+    let mut p = Profile::new(b, b.derived_address());
+    p.chain_name = Some(NamedChain::TESTNET);
+    app_cfg.maybe_add_profile(p).unwrap();
+
+    // a name that matches nothing is an error, not the first profile
+    assert!(app_cfg.find_profile("nope").is_err());
+
+    app_cfg.rename_profile("052d", "ops").unwrap();
+    assert!(app_cfg.rename_profile("8603", "ops").is_err());
+    assert_eq!(app_cfg.find_profile("ops").unwrap().auth_key, b);
+
+    app_cfg.set_default_profile("ops").unwrap();
+    let default = app_cfg.get_profile(None).unwrap();
+    assert_eq!(default.auth_key, b);
+    assert_eq!(app_cfg.profile_chain(default), NamedChain::TESTNET);
+
+    app_cfg.try_remove_profile("ops").unwrap();
+    assert!(app_cfg.workspace.default_profile.is_none());
+    assert_eq!(app_cfg.user_profiles.len(), 1);
+}
+
 #[test]
 fn read_write() {
     let raw_yaml = r"
@@ -1,3 +1,4 @@
+pub mod address_book;
 pub mod app_cfg;
 pub mod block;
 pub mod fixtures;
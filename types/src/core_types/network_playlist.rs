@@ -1,11 +1,22 @@
 //! network configs
-use crate::exports::{Client, NamedChain};
+use crate::{
+    exports::{Client, NamedChain},
+    type_extensions::client_ext::ClientExt,
+};
 use anyhow::{bail, Context};
 use futures::{stream::FuturesUnordered, StreamExt};
 use rand::{seq::SliceRandom, thread_rng};
 use serde_with::{serde_as, DisplayFromStr};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use url::Url;
 
+/// requests sent to each node by the doctor, to measure the error rate
+const DOCTOR_PROBES: u64 = 3;
+/// nodes this many versions behind the highest node are not in sync
+const MAX_LEDGER_LAG: u64 = 10_000;
+/// scores older than this are not used to pick a node, nodes go down
+const MAX_HEALTH_AGE_SECS: u64 = 3_600;
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct HostProfile {
     pub url: Url,
@@ -16,6 +27,54 @@ pub struct HostProfile {
     pub is_api: bool,
     #[serde(default)]
     pub is_sync: bool,
+    /// result of the last health check, see NetworkPlaylist::doctor
+    #[serde(default)]
+    pub health: Option<HostHealth>,
+}
+
+/// The health of a node as measured by `config network doctor`
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+pub struct HostHealth {
+    /// unix timestamp in seconds of the check
+    pub checked_at: u64,
+    /// the chain id the node reported, if any
+    pub chain_id: Option<u8>,
+    /// the node is on the chain of the playlist
+    pub chain_ok: bool,
+    /// mean response time of the successful requests
+    pub latency_ms: Option<u64>,
+    /// versions behind the highest node of the playlist
+    pub ledger_lag: Option<u64>,
+    /// fraction of the requests which failed
+    pub error_rate: f64,
+    /// the node answers view function requests
+    pub serves_views: bool,
+    /// no request succeeded
+    pub is_dead: bool,
+    /// 0 to 100, higher is better. Dead nodes and nodes on another chain score 0
+    pub score: u8,
+}
+
+impl HostHealth {
+    /// the check is recent enough to pick a node by its score
+    fn is_fresh(&self, now_secs: u64) -> bool {
+        now_secs.saturating_sub(self.checked_at) <= MAX_HEALTH_AGE_SECS
+    }
+
+    fn compute_score(&mut self) {
+        if self.is_dead || !self.chain_ok {
+            self.score = 0;
+            return;
+        }
+        let mut penalty = (self.error_rate * 30.0) as u64;
+        penalty += self.latency_ms.unwrap_or(0) / 50;
+        penalty += self.ledger_lag.unwrap_or(0) * 40 / MAX_LEDGER_LAG;
+        if !self.serves_views {
+            penalty += 10;
+        }
+        // a node which is alive always scores above a dead one
+        self.score = 100u64.saturating_sub(penalty).max(1) as u8;
+    }
 }
 /// from the list of seed_peers find the best peer to connect to.
 /// First does a light port check on all peers, and eliminated unresponsive
@@ -31,6 +90,7 @@ impl Default for HostProfile {
             is_api: false,
             is_sync: false,
             note: "default".to_string(),
+            health: None,
         }
     }
 }
@@ -63,6 +123,56 @@ impl HostProfile {
 
         Ok(self)
     }
+
+    /// Probe the node a few times, recording the chain id, latency, version and errors.
+    /// The ledger lag and score are set later, against the other nodes of the playlist.
+    async fn diagnose(mut self, chain_name: NamedChain) -> HostProfile {
+        let client = Client::new(self.url.clone());
+        let mut health = HostHealth {
+            checked_at: now_secs(),
+            ..Default::default()
+        };
+
+        let mut errors = 0;
+        let mut total_ms = 0;
+        // the version saved by an earlier check may be stale, only this round counts
+        let mut version = 0;
+        for _ in 0..DOCTOR_PROBES {
+            let start = Instant::now();
+            match client.get_index().await {
+                Ok(res) => {
+                    total_ms += start.elapsed().as_millis() as u64;
+                    let index = res.into_inner();
+                    health.chain_id = Some(index.chain_id);
+                    version = version.max(index.ledger_version.into());
+                }
+                Err(_) => errors += 1,
+            }
+        }
+
+        self.version = version;
+        let ok = DOCTOR_PROBES - errors;
+        health.is_dead = ok == 0;
+        health.error_rate = errors as f64 / DOCTOR_PROBES as f64;
+        if ok > 0 {
+            health.latency_ms = Some(total_ms / ok);
+        }
+        health.chain_ok = health.chain_id == Some(chain_name.id());
+        health.serves_views = !health.is_dead
+            && client
+                .view_ext("0x1::chain_id::get", None, None)
+                .await
+                .is_ok();
+
+        self.is_api = !health.is_dead;
+        self.health = Some(health);
+        self
+    }
+
+    /// the score from the last health check, unchecked nodes score 0
+    pub fn score(&self) -> u8 {
+        self.health.as_ref().map(|h| h.score).unwrap_or(0)
+    }
 }
 #[serde_as]
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -122,6 +232,7 @@ impl NetworkPlaylist {
                 version: 0,
                 is_api: true,
                 is_sync: true,
+                health: None,
             }],
         }
     }
@@ -196,7 +307,21 @@ impl NetworkPlaylist {
         }
     }
 
+    /// the node with the highest score from a recent health check, if any was checked
+    pub fn the_best_scored(&self) -> Option<Url> {
+        let now = now_secs();
+        self.nodes
+            .iter()
+            .filter(|e| e.health.as_ref().map_or(false, |h| h.is_fresh(now)))
+            .filter(|e| e.score() > 0)
+            .max_by_key(|e| e.score())
+            .map(|e| e.url.to_owned())
+    }
+
     pub fn pick_one(&self) -> anyhow::Result<Url> {
+        if let Some(u) = self.the_best_scored() {
+            return Ok(u);
+        }
         match self.the_best_one() {
             Ok(u) => Ok(u),
             Err(_) => self
@@ -252,6 +377,39 @@ impl NetworkPlaylist {
         Ok(())
     }
 
+    /// Check the health of all nodes concurrently and score them.
+    /// Nodes on another chain, dead or lagging are not marked as in sync.
+    /// The nodes are sorted with the best score first.
+    pub async fn doctor(&mut self) -> anyhow::Result<()> {
+        let futures = FuturesUnordered::new();
+        self.nodes.clone().into_iter().for_each(|p| {
+            futures.push(p.diagnose(self.chain_name));
+        });
+        let mut checked = futures.collect::<Vec<HostProfile>>().await;
+
+        let highest = checked
+            .iter()
+            .filter(|p| p.health.as_ref().map_or(false, |h| h.chain_ok))
+            .map(|p| p.version)
+            .max()
+            .unwrap_or(0);
+
+        for p in checked.iter_mut() {
+            let version = p.version;
+            if let Some(h) = p.health.as_mut() {
+                if h.chain_ok {
+                    h.ledger_lag = Some(highest.saturating_sub(version));
+                }
+                h.compute_score();
+                p.is_sync = h.chain_ok && h.ledger_lag.map_or(false, |l| l <= MAX_LEDGER_LAG);
+            }
+        }
+
+        checked.sort_by_key(|p| std::cmp::Reverse(p.score()));
+        self.nodes = checked;
+        Ok(())
+    }
+
     /// Checks which nodes are alive by performing a sync check.
     pub async fn check_which_are_alive(mut self) -> anyhow::Result<Self> {
         let mut upstream = self.nodes;
@@ -277,3 +435,54 @@ impl NetworkPlaylist {
         Ok(self)
     }
 }
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[test]
+fn pick_best_scored() {
+    let mut np = NetworkPlaylist::new(Some("http://a.com:8080".parse().unwrap()), None);
+    np.add_url("http://b.com:8080".parse().unwrap());
+    np.add_url("http://c.com:8080".parse().unwrap());
+
+    // nothing checked yet, the first one is used
+    assert_eq!(np.pick_one().unwrap().as_str(), "http://a.com:8080/");
+
+    let healthy = HostHealth {
+        checked_at: now_secs(),
+        chain_ok: true,
+        latency_ms: Some(100),
+        ledger_lag: Some(0),
+        serves_views: true,
+        ..Default::default()
+    };
+    let lagging = HostHealth {
+        ledger_lag: Some(MAX_LEDGER_LAG),
+        ..healthy.clone()
+    };
+    let wrong_chain = HostHealth {
+        chain_ok: false,
+        ..healthy.clone()
+    };
+
+    for (node, mut h) in np.nodes.iter_mut().zip([wrong_chain, lagging, healthy]) {
+        h.compute_score();
+        node.health = Some(h);
+    }
+    assert_eq!(np.nodes[0].score(), 0);
+    assert!(np.nodes[1].score() < np.nodes[2].score());
+    assert_eq!(np.pick_one().unwrap().as_str(), "http://c.com:8080/");
+
+    // an old check is ignored, the node may have died since
+    for node in np.nodes.iter_mut() {
+        if let Some(h) = node.health.as_mut() {
+            h.checked_at = now_secs() - MAX_HEALTH_AGE_SECS - 1;
+        }
+    }
+    assert!(np.the_best_scored().is_none());
+    assert_eq!(np.pick_one().unwrap().as_str(), "http://a.com:8080/");
+}
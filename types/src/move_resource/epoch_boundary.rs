@@ -0,0 +1,79 @@
+use diem_sdk::move_types::{
+    ident_str,
+    identifier::IdentStr,
+    language_storage::TypeTag,
+    move_resource::{MoveResource, MoveStructType},
+};
+use diem_types::account_address::AccountAddress;
+
+use serde::{Deserialize, Serialize};
+
+/// what happened in the last epoch boundary. The fields are in the same
+/// order as the Move struct.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BoundaryStatusResource {
+    pub security_bill_count: u64,
+    pub security_bill_amount: u64,
+    pub security_bill_success: bool,
+
+    pub dd_accounts_count: u64,
+    pub dd_accounts_amount: u64,
+    pub dd_accounts_success: bool,
+
+    pub set_fee_makers_success: bool,
+    pub system_fees_collected: u64,
+
+    pub outgoing_vals_paid: Vec<AccountAddress>,
+    pub outgoing_total_reward: u64,
+    pub outgoing_nominal_reward_to_vals: u64,
+    pub outgoing_entry_fee: u64,
+    pub outgoing_clearing_percent: u64,
+    pub outgoing_vals_success: bool,
+
+    pub tower_state_success: bool,
+    pub oracle_budget: u64,
+    pub oracle_pay_count: u64,
+    pub oracle_pay_amount: u64,
+    pub oracle_pay_success: bool,
+
+    pub epoch_burn_fees: u64,
+    pub epoch_burn_success: bool,
+
+    pub slow_wallet_drip_amount: u64,
+    pub slow_wallet_drip_success: bool,
+
+    pub incoming_compliant: Vec<AccountAddress>,
+    pub incoming_compliant_count: u64,
+    pub incoming_seats_offered: u64,
+
+    pub incoming_all_bidders: Vec<AccountAddress>,
+    pub incoming_only_qualified_bidders: Vec<AccountAddress>,
+    pub incoming_auction_winners: Vec<AccountAddress>,
+    pub incoming_filled_seats: u64,
+    pub incoming_fees: u64,
+    pub incoming_fees_success: bool,
+
+    pub incoming_post_failover_check: Vec<AccountAddress>,
+    pub incoming_vals_missing_configs: Vec<AccountAddress>,
+    pub incoming_actual_vals: Vec<AccountAddress>,
+    pub incoming_final_set_size: u64,
+    pub incoming_reconfig_success: bool,
+
+    pub infra_subsidize_amount: u64,
+    pub infra_subsidize_success: bool,
+
+    pub pof_thermo_success: bool,
+    pub pof_thermo_increase: bool,
+    pub pof_thermo_amount: u64,
+}
+
+impl MoveStructType for BoundaryStatusResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("epoch_boundary");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("BoundaryStatus");
+
+    fn type_params() -> Vec<TypeTag> {
+        vec![]
+    }
+}
+
+impl MoveResource for BoundaryStatusResource {}
@@ -6,6 +6,7 @@ pub mod coin_info;
 pub mod cumulative_deposits;
 pub mod donor_voice;
 pub mod donor_voice_txs;
+pub mod epoch_boundary;
 pub mod fee_maker;
 pub mod gas_coin;
 pub mod jail;
@@ -15,6 +16,7 @@ pub mod ol_account;
 pub mod pledge_account;
 pub mod proof_of_fee;
 pub mod receipts;
+pub mod recovery_mode;
 pub mod txschedule;
 pub mod validator_universe;
 pub mod vouch;
@@ -0,0 +1,25 @@
+use diem_sdk::move_types::{
+    ident_str,
+    identifier::IdentStr,
+    language_storage::TypeTag,
+    move_resource::{MoveResource, MoveStructType},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// when on, the validator selection stops, e.g. during an incident
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecoveryModeResource {
+    pub on: bool,
+}
+
+impl MoveStructType for RecoveryModeResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("recovery_mode");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("RecoveryMode");
+
+    fn type_params() -> Vec<TypeTag> {
+        vec![]
+    }
+}
+
+impl MoveResource for RecoveryModeResource {}